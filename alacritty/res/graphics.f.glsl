@@ -0,0 +1,17 @@
+#version 330 core
+
+in vec2 texCoords;
+
+out vec4 FragColor;
+
+uniform sampler2D graphic;
+
+void main()
+{
+    // Skip parts of the cell which are outside of the graphic.
+    if (texCoords.x > 1.0 || texCoords.y > 1.0) {
+        discard;
+    }
+
+    FragColor = texture(graphic, texCoords);
+}
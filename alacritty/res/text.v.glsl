@@ -27,6 +27,14 @@ uniform vec4 projection;
 uniform int backgroundPass;
 
 #define WIDE_CHAR 1
+#define DOUBLE_WIDTH 4
+#define DOUBLE_HEIGHT_TOP 8
+#define DOUBLE_HEIGHT_BOTTOM 16
+#define SUPERSCRIPT 32
+#define SUBSCRIPT 64
+
+// Size of superscript and subscript glyphs relative to regular glyphs.
+#define SCRIPT_SCALE 0.6
 
 void main() {
     vec2 projectionOffset = projection.xy;
@@ -40,12 +48,17 @@ void main() {
     // Position of cell from top-left
     vec2 cellPosition = cellDim * gridCoords;
 
+    int flags = int(textColor.a);
+
     if (backgroundPass != 0) {
         vec2 backgroundDim = cellDim;
-        if ((int(textColor.a) & WIDE_CHAR) != 0) {
+        if ((flags & WIDE_CHAR) != 0) {
             // Update wide char x dimension so it'll cover the following spacer.
             backgroundDim.x *= 2;
         }
+        if ((flags & DOUBLE_WIDTH) != 0) {
+            backgroundDim.x *= 2;
+        }
         vec2 finalPosition = cellPosition + backgroundDim * position;
         gl_Position =
             vec4(projectionOffset + projectionScale * finalPosition, 0.0, 1.0);
@@ -54,14 +67,50 @@ void main() {
     } else {
         vec2 glyphSize = glyph.zw;
         vec2 glyphOffset = glyph.xy;
-        glyphOffset.y = cellDim.y - glyphOffset.y;
+        vec2 uvOffset = uv.xy;
+        vec2 uvSize = uv.zw;
+
+        // Scale glyphs horizontally on double-width lines.
+        if ((flags & DOUBLE_WIDTH) != 0) {
+            glyphSize.x *= 2;
+            glyphOffset.x *= 2;
+        }
+
+        if ((flags & (DOUBLE_HEIGHT_TOP | DOUBLE_HEIGHT_BOTTOM)) != 0) {
+            // Scale the glyph to span two lines, starting at the top half's line.
+            glyphSize.y *= 2;
+            glyphOffset.y = 2 * (cellDim.y - glyphOffset.y);
+            if ((flags & DOUBLE_HEIGHT_BOTTOM) != 0) {
+                glyphOffset.y -= cellDim.y;
+            }
+
+            // Clip the glyph to the current line.
+            float top = clamp(glyphOffset.y, 0., cellDim.y);
+            float bottom = clamp(glyphOffset.y + glyphSize.y, top, cellDim.y);
+            if (glyphSize.y > 0.) {
+                uvOffset.y += uvSize.y * (top - glyphOffset.y) / glyphSize.y;
+                uvSize.y *= (bottom - top) / glyphSize.y;
+            }
+            glyphOffset.y = top;
+            glyphSize.y = bottom - top;
+        } else {
+            glyphOffset.y = cellDim.y - glyphOffset.y;
+        }
+
+        // Shrink superscript glyphs towards the top and subscript glyphs towards the bottom.
+        if ((flags & SUPERSCRIPT) != 0) {
+            glyphSize *= SCRIPT_SCALE;
+            glyphOffset *= SCRIPT_SCALE;
+        } else if ((flags & SUBSCRIPT) != 0) {
+            glyphSize *= SCRIPT_SCALE;
+            glyphOffset.x *= SCRIPT_SCALE;
+            glyphOffset.y = cellDim.y - (cellDim.y - glyphOffset.y) * SCRIPT_SCALE;
+        }
 
         vec2 finalPosition = cellPosition + glyphSize * position + glyphOffset;
         gl_Position =
             vec4(projectionOffset + projectionScale * finalPosition, 0.0, 1.0);
 
-        vec2 uvOffset = uv.xy;
-        vec2 uvSize = uv.zw;
         TexCoords = uvOffset + position * uvSize;
     }
 
@@ -155,6 +155,18 @@ pub enum Action {
     /// Scroll all the way to the bottom.
     ScrollToBottom,
 
+    /// Scroll to the previous shell prompt.
+    ScrollToPreviousPrompt,
+
+    /// Scroll to the next shell prompt.
+    ScrollToNextPrompt,
+
+    /// Select the output of the last command.
+    SelectLastCommandOutput,
+
+    /// Copy the output of the last command to the clipboard.
+    CopyLastCommandOutput,
+
     /// Clear the display buffer(s) to remove history.
     ClearHistory,
 
@@ -14,6 +14,7 @@ pub mod color;
 pub mod debug;
 pub mod font;
 pub mod monitor;
+pub mod notification;
 pub mod serde_utils;
 pub mod ui_config;
 pub mod window;
@@ -20,6 +20,7 @@ use crate::config::color::Colors;
 use crate::config::debug::Debug;
 use crate::config::font::Font;
 use crate::config::mouse::Mouse;
+use crate::config::notification::NotificationConfig;
 use crate::config::window::WindowConfig;
 
 /// Regex used for the default URL hint.
@@ -49,12 +50,18 @@ pub struct UiConfig {
     /// Bell configuration.
     pub bell: BellConfig,
 
+    /// Desktop notification configuration.
+    pub notifications: NotificationConfig,
+
     /// RGB values for colors.
     pub colors: Colors,
 
     /// Should draw bold text with brighter colors instead of bold font.
     pub draw_bold_text_with_bright_colors: bool,
 
+    /// Blink text with the blink attribute.
+    pub blinking_text: bool,
+
     /// Path where config was loaded from.
     #[config(skip)]
     pub config_paths: Vec<PathBuf>,
@@ -86,8 +93,10 @@ impl Default for UiConfig {
             mouse_bindings: Default::default(),
             background_opacity: Default::default(),
             bell: Default::default(),
+            notifications: Default::default(),
             colors: Default::default(),
             draw_bold_text_with_bright_colors: Default::default(),
+            blinking_text: true,
             hints: Default::default(),
         }
     }
@@ -110,7 +119,7 @@ impl UiConfig {
                 action: Action::Hint(hint.clone()),
             };
 
-            self.key_bindings.0.push(binding);
+            self.key_bindings.bindings.push(binding);
         }
     }
 
@@ -121,7 +130,13 @@ impl UiConfig {
 
     #[inline]
     pub fn key_bindings(&self) -> &[KeyBinding] {
-        self.key_bindings.0.as_slice()
+        self.key_bindings.bindings.as_slice()
+    }
+
+    /// Check if the key binding at `index` was configured by the user.
+    #[inline]
+    pub fn is_user_key_binding(&self, index: usize) -> bool {
+        index < self.key_bindings.user_bindings
     }
 
     #[inline]
@@ -131,11 +146,16 @@ impl UiConfig {
 }
 
 #[derive(Debug, PartialEq)]
-struct KeyBindings(Vec<KeyBinding>);
+struct KeyBindings {
+    bindings: Vec<KeyBinding>,
+
+    /// Number of bindings at the start of the list which were configured by the user.
+    user_bindings: usize,
+}
 
 impl Default for KeyBindings {
     fn default() -> Self {
-        Self(bindings::default_key_bindings())
+        Self { bindings: bindings::default_key_bindings(), user_bindings: 0 }
     }
 }
 
@@ -144,7 +164,10 @@ impl<'de> Deserialize<'de> for KeyBindings {
     where
         D: Deserializer<'de>,
     {
-        Ok(Self(deserialize_bindings(deserializer, Self::default().0)?))
+        let mut bindings = deserialize_bindings(deserializer)?;
+        let user_bindings = bindings.len();
+        extend_default_bindings(&mut bindings, Self::default().bindings);
+        Ok(Self { bindings, user_bindings })
     }
 }
 
@@ -162,17 +185,15 @@ impl<'de> Deserialize<'de> for MouseBindings {
     where
         D: Deserializer<'de>,
     {
-        Ok(Self(deserialize_bindings(deserializer, Self::default().0)?))
+        let mut bindings = deserialize_bindings(deserializer)?;
+        extend_default_bindings(&mut bindings, Self::default().0);
+        Ok(Self(bindings))
     }
 }
 
-fn deserialize_bindings<'a, D, T>(
-    deserializer: D,
-    mut default: Vec<Binding<T>>,
-) -> Result<Vec<Binding<T>>, D::Error>
+fn deserialize_bindings<'a, D, T>(deserializer: D) -> Result<Vec<Binding<T>>, D::Error>
 where
     D: Deserializer<'a>,
-    T: Copy + Eq,
     Binding<T>: Deserialize<'a>,
 {
     let values = Vec::<serde_yaml::Value>::deserialize(deserializer)?;
@@ -188,14 +209,20 @@ where
         }
     }
 
+    Ok(bindings)
+}
+
+/// Append the default bindings which aren't overridden by any of the `bindings`.
+fn extend_default_bindings<T: Copy + Eq>(
+    bindings: &mut Vec<Binding<T>>,
+    mut default: Vec<Binding<T>>,
+) {
     // Remove matching default bindings.
     for binding in bindings.iter() {
         default.retain(|b| !b.triggers_match(binding));
     }
 
     bindings.extend(default);
-
-    Ok(bindings)
 }
 
 /// A delta for a point in a 2 dimensional plane.
@@ -238,6 +265,7 @@ impl Default for Hints {
                 regex,
                 action,
                 post_processing: true,
+                hyperlinks: true,
                 mouse: Some(HintMouse { enabled: true, mods: Default::default() }),
                 binding: Some(HintBinding {
                     key: Key::Keycode(VirtualKeyCode::U),
@@ -328,6 +356,10 @@ pub struct Hint {
     #[serde(default)]
     pub post_processing: bool,
 
+    /// Match OSC 8 hyperlinks in addition to the regex.
+    #[serde(default)]
+    pub hyperlinks: bool,
+
     /// Hint mouse highlighting.
     pub mouse: Option<HintMouse>,
 
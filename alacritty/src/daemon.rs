@@ -5,6 +5,7 @@ use std::io;
 use std::os::unix::process::CommandExt;
 #[cfg(windows)]
 use std::os::windows::process::CommandExt;
+use std::path::Path;
 use std::process::{Command, Stdio};
 
 use log::{debug, warn};
@@ -13,28 +14,35 @@ use log::{debug, warn};
 use winapi::um::winbase::{CREATE_NEW_PROCESS_GROUP, CREATE_NO_WINDOW};
 
 /// Start the daemon and log error on failure.
-pub fn start_daemon<I, S>(program: &str, args: I)
+///
+/// The daemon inherits Alacritty's working directory, unless `working_directory` is specified.
+pub fn start_daemon<I, S>(program: &str, args: I, working_directory: Option<&Path>)
 where
     I: IntoIterator<Item = S> + Debug + Copy,
     S: AsRef<OsStr>,
 {
-    match spawn_daemon(program, args) {
+    match spawn_daemon(program, args, working_directory) {
         Ok(_) => debug!("Launched {} with args {:?}", program, args),
         Err(_) => warn!("Unable to launch {} with args {:?}", program, args),
     }
 }
 
 #[cfg(windows)]
-fn spawn_daemon<I, S>(program: &str, args: I) -> io::Result<()>
+fn spawn_daemon<I, S>(program: &str, args: I, working_directory: Option<&Path>) -> io::Result<()>
 where
     I: IntoIterator<Item = S> + Copy,
     S: AsRef<OsStr>,
 {
+    let mut command = Command::new(program);
+    if let Some(working_directory) = working_directory {
+        command.current_dir(working_directory);
+    }
+
     // Setting all the I/O handles to null and setting the
     // CREATE_NEW_PROCESS_GROUP and CREATE_NO_WINDOW has the effect
     // that console applications will run without opening a new
     // console window.
-    Command::new(program)
+    command
         .args(args)
         .stdin(Stdio::null())
         .stdout(Stdio::null())
@@ -45,13 +53,18 @@ where
 }
 
 #[cfg(not(windows))]
-fn spawn_daemon<I, S>(program: &str, args: I) -> io::Result<()>
+fn spawn_daemon<I, S>(program: &str, args: I, working_directory: Option<&Path>) -> io::Result<()>
 where
     I: IntoIterator<Item = S> + Copy,
     S: AsRef<OsStr>,
 {
+    let mut command = Command::new(program);
+    if let Some(working_directory) = working_directory {
+        command.current_dir(working_directory);
+    }
+
     unsafe {
-        Command::new(program)
+        command
             .args(args)
             .stdin(Stdio::null())
             .stdout(Stdio::null())
@@ -54,6 +54,15 @@ impl List {
         self[NamedColor::Foreground] = colors.primary.foreground;
         self[NamedColor::Background] = colors.primary.background;
 
+        // Cursor and selection colors, using the primary colors in place of the cell's colors.
+        let foreground = colors.primary.foreground;
+        let background = colors.primary.background;
+        self[NamedColor::Cursor] = colors.cursor.background.color(foreground, background);
+        self[NamedColor::SelectionForeground] =
+            colors.selection.foreground.color(foreground, background);
+        self[NamedColor::SelectionBackground] =
+            colors.selection.background.color(foreground, background);
+
         // Dims.
         self[NamedColor::DimForeground] =
             colors.primary.dim_foreground.unwrap_or(colors.primary.foreground * DIM_FACTOR);
@@ -3,12 +3,13 @@ use std::cmp::{max, min};
 use std::mem;
 use std::ops::{Deref, DerefMut, RangeInclusive};
 
-use alacritty_terminal::ansi::{Color, CursorShape, NamedColor};
+use alacritty_terminal::ansi::{Color, CursorShape, LineSize, NamedColor};
 use alacritty_terminal::config::Config;
 use alacritty_terminal::event::EventListener;
-use alacritty_terminal::grid::{Dimensions, Indexed};
+use alacritty_terminal::graphics::GraphicCell;
+use alacritty_terminal::grid::{Dimensions, Grid, Indexed};
 use alacritty_terminal::index::{Column, Direction, Line, Point};
-use alacritty_terminal::term::cell::{Cell, Flags};
+use alacritty_terminal::term::cell::{Cell, Flags, Hyperlink};
 use alacritty_terminal::term::color::{CellRgb, Rgb};
 use alacritty_terminal::term::search::{Match, RegexIter, RegexSearch};
 use alacritty_terminal::term::{RenderableContent as TerminalContent, Term, TermMode};
@@ -27,6 +28,7 @@ pub const MIN_CURSOR_CONTRAST: f64 = 1.5;
 /// This provides the terminal cursor and an iterator over all non-empty cells.
 pub struct RenderableContent<'a> {
     terminal_content: TerminalContent<'a>,
+    grid: &'a Grid<Cell>,
     cursor: Option<RenderableCursor>,
     cursor_shape: CursorShape,
     cursor_point: Point<usize>,
@@ -35,6 +37,8 @@ pub struct RenderableContent<'a> {
     config: &'a Config<UiConfig>,
     colors: &'a List,
     focused_match: Option<&'a Match>,
+    blinking_text_hidden: bool,
+    has_blinking_text: bool,
 }
 
 impl<'a> RenderableContent<'a> {
@@ -63,7 +67,15 @@ impl<'a> RenderableContent<'a> {
         // Convert terminal cursor point to viewport position.
         let cursor_point = terminal_content.cursor.point;
         let display_offset = terminal_content.display_offset;
-        let cursor_point = display::point_to_viewport(display_offset, cursor_point).unwrap();
+        let line_size = term.grid()[cursor_point.line].line_size();
+        let mut cursor_point = display::point_to_viewport(display_offset, cursor_point).unwrap();
+
+        // Cells on double-width lines span two columns.
+        if line_size.is_double_width() {
+            let last_column = Column(term.columns().saturating_sub(2));
+            let column = display::cell_to_viewport_column(line_size, cursor_point.column);
+            cursor_point.column = min(column, last_column);
+        }
 
         let hint = if display.hint_state.active() {
             display.hint_state.update_matches(term);
@@ -72,9 +84,12 @@ impl<'a> RenderableContent<'a> {
             None
         };
 
+        let blinking_text_hidden = config.ui_config.blinking_text && display.blinking_text_hidden;
+
         Self {
             colors: &display.colors,
             cursor: None,
+            grid: term.grid(),
             terminal_content,
             focused_match,
             cursor_shape,
@@ -82,6 +97,8 @@ impl<'a> RenderableContent<'a> {
             search,
             config,
             hint,
+            blinking_text_hidden,
+            has_blinking_text: false,
         }
     }
 
@@ -90,6 +107,11 @@ impl<'a> RenderableContent<'a> {
         self.terminal_content.display_offset
     }
 
+    /// Check if any of the rendered cells is blinking.
+    pub fn has_blinking_text(&self) -> bool {
+        self.has_blinking_text
+    }
+
     /// Get the terminal cursor.
     pub fn cursor(mut self) -> Option<RenderableCursor> {
         // Assure this function is only called after the iterator has been drained.
@@ -135,7 +157,7 @@ impl<'a> RenderableContent<'a> {
         let cursor_color = cursor_color.color(cell.fg, cell.bg);
 
         Some(RenderableCursor {
-            is_wide: cell.flags.contains(Flags::WIDE_CHAR),
+            is_wide: cell.flags.contains(Flags::WIDE_CHAR) || cell.line_size.is_double_width(),
             shape: self.cursor_shape,
             point: self.cursor_point,
             cursor_color,
@@ -157,6 +179,11 @@ impl<'a> Iterator for RenderableContent<'a> {
             let cell = self.terminal_content.display_iter.next()?;
             let mut cell = RenderableCell::new(self, cell);
 
+            // Skip cells pushed off the screen by double-width lines.
+            if cell.point.column >= self.grid.columns() {
+                continue;
+            }
+
             if self.cursor_point == cell.point {
                 // Store the cursor which should be rendered.
                 self.cursor = self.renderable_cursor(&cell).map(|cursor| {
@@ -190,7 +217,11 @@ pub struct RenderableCell {
     pub fg: Rgb,
     pub bg: Rgb,
     pub bg_alpha: f32,
+    pub underline: Rgb,
     pub flags: Flags,
+    pub line_size: LineSize,
+    pub hyperlink: Option<Hyperlink>,
+    pub graphic: Option<GraphicCell>,
 }
 
 impl RenderableCell {
@@ -218,6 +249,19 @@ impl RenderableCell {
         let viewport_start = Point::new(Line(-(display_offset as i32)), Column(0));
         let colors = &content.config.ui_config.colors;
         let mut character = cell.c;
+        let mut zerowidth = cell.zerowidth().map(|zerowidth| zerowidth.to_vec());
+        let mut flags = cell.flags;
+
+        if flags.contains(Flags::BLINK) {
+            content.has_blinking_text = true;
+
+            // Hide the text, while keeping the cell's background.
+            if content.blinking_text_hidden {
+                flags.remove(Flags::ALL_UNDERLINES | Flags::STRIKEOUT | Flags::OVERLINE);
+                character = ' ';
+                zerowidth = None;
+            }
+        }
 
         if let Some((c, is_first)) =
             content.hint.as_mut().and_then(|hint| hint.advance(viewport_start, cell.point))
@@ -231,8 +275,12 @@ impl RenderableCell {
 
             character = c;
         } else if is_selected {
-            let config_fg = colors.selection.foreground;
-            let config_bg = colors.selection.background;
+            // Selection colors modified by the terminal take precedence over the config.
+            let term_colors = &content.terminal_content.colors;
+            let config_fg = term_colors[NamedColor::SelectionForeground]
+                .map_or(colors.selection.foreground, CellRgb::Rgb);
+            let config_bg = term_colors[NamedColor::SelectionBackground]
+                .map_or(colors.selection.background, CellRgb::Rgb);
             Self::compute_cell_rgb(&mut fg, &mut bg, &mut bg_alpha, config_fg, config_bg);
 
             if fg == bg && !cell.flags.contains(Flags::HIDDEN) {
@@ -253,13 +301,26 @@ impl RenderableCell {
 
         // Convert cell point to viewport position.
         let cell_point = cell.point;
-        let point = display::point_to_viewport(display_offset, cell_point).unwrap();
+        let mut point = display::point_to_viewport(display_offset, cell_point).unwrap();
+
+        // Cells on double-width lines span two columns.
+        let line_size = content.grid[cell_point.line].line_size();
+        point.column = display::cell_to_viewport_column(line_size, point.column);
+
+        // Underlines without explicit color use the foreground color.
+        let underline = cell
+            .underline_color()
+            .map_or(fg, |underline| Self::compute_fg_rgb(content, underline, cell.flags));
 
         RenderableCell {
-            zerowidth: cell.zerowidth().map(|zerowidth| zerowidth.to_vec()),
-            flags: cell.flags,
+            hyperlink: cell.hyperlink(),
+            graphic: cell.graphic().cloned(),
+            zerowidth,
             character,
+            flags,
+            line_size,
             bg_alpha,
+            underline,
             point,
             fg,
             bg,
@@ -271,7 +332,9 @@ impl RenderableCell {
         self.bg_alpha == 0.
             && self.character == ' '
             && self.zerowidth.is_none()
-            && !self.flags.intersects(Flags::UNDERLINE | Flags::STRIKEOUT | Flags::DOUBLE_UNDERLINE)
+            && self.hyperlink.is_none()
+            && self.graphic.is_none()
+            && !self.flags.intersects(Flags::ALL_UNDERLINES | Flags::STRIKEOUT | Flags::OVERLINE)
     }
 
     /// Apply [`CellRgb`] colors to the cell's colors.
@@ -4,12 +4,13 @@ use glutin::event::ModifiersState;
 
 use alacritty_terminal::grid::BidirectionalIterator;
 use alacritty_terminal::index::{Boundary, Direction, Point};
+use alacritty_terminal::term::cell::Hyperlink;
 use alacritty_terminal::term::search::{Match, RegexIter, RegexSearch};
 use alacritty_terminal::term::{Term, TermMode};
 
 use crate::config::ui_config::{Hint, HintAction};
 use crate::config::Config;
-use crate::display::content::RegexMatches;
+use crate::display::content::{RegexMatches, RenderableCell};
 use crate::display::MAX_SEARCH_LINES;
 
 /// Percentage of characters in the hints alphabet used for the last character.
@@ -136,7 +137,7 @@ impl HintState {
 
             self.stop();
 
-            Some(HintMatch { action, bounds })
+            Some(HintMatch { action, bounds, hyperlink: None })
         } else {
             // Store character to preserve the selection.
             self.keys.push(c);
@@ -172,6 +173,19 @@ pub struct HintMatch {
 
     /// Terminal range matching the hint.
     pub bounds: Match,
+
+    /// OSC 8 hyperlink matching the hint.
+    pub hyperlink: Option<Hyperlink>,
+}
+
+impl HintMatch {
+    /// Check if the cell at the specified point should be highlighted as part of this hint.
+    ///
+    /// Cells outside of the hint's bounds are highlighted too if they share its hyperlink.
+    pub fn should_highlight(&self, point: Point, cell: &RenderableCell) -> bool {
+        self.bounds.contains(&point)
+            || self.hyperlink.as_ref().map_or(false, |link| cell.hyperlink.as_ref() == Some(link))
+    }
 }
 
 /// Generator for creating new hint labels.
@@ -259,6 +273,14 @@ pub fn highlighted_at<T>(
             return None;
         }
 
+        // Prefer OSC 8 hyperlinks over regex matches.
+        if hint.hyperlinks {
+            if let Some((hyperlink, bounds)) = hyperlink_at(term, point) {
+                let action = hint.action.clone();
+                return Some(HintMatch { action, bounds, hyperlink: Some(hyperlink) });
+            }
+        }
+
         hint.regex.with_compiled(|regex| {
             // Setup search boundaries.
             let mut start = term.line_search_left(point);
@@ -280,11 +302,47 @@ pub fn highlighted_at<T>(
                 Some(regex_match)
             };
 
-            regex_match.map(|bounds| HintMatch { action: hint.action.clone(), bounds })
+            regex_match.map(|bounds| HintMatch {
+                action: hint.action.clone(),
+                bounds,
+                hyperlink: None,
+            })
         })
     })
 }
 
+/// Find the hyperlink and its bounds at the specified point.
+///
+/// All adjacent cells sharing the same hyperlink are considered part of it, which allows
+/// hyperlinks to span multiple lines. Like regex hints, the search is limited to
+/// [`MAX_SEARCH_LINES`] in each direction.
+fn hyperlink_at<T>(term: &Term<T>, point: Point) -> Option<(Hyperlink, Match)> {
+    let grid = term.grid();
+    let hyperlink = grid[point].hyperlink()?;
+
+    let start_line = point.line - MAX_SEARCH_LINES;
+    let end_line = point.line + MAX_SEARCH_LINES;
+
+    let mut start = point;
+    let mut iter = grid.iter_from(point);
+    while let Some(cell) = iter.prev() {
+        if cell.point.line < start_line || !cell.has_hyperlink(&hyperlink) {
+            break;
+        }
+        start = cell.point;
+    }
+
+    let mut end = point;
+    for cell in grid.iter_from(point) {
+        if cell.point.line > end_line || !cell.has_hyperlink(&hyperlink) {
+            break;
+        }
+        end = cell.point;
+    }
+
+    Some((hyperlink, start..=end))
+}
+
 /// Iterator over all post-processed matches inside an existing hint match.
 struct HintPostProcessor<'a, T> {
     /// Regex search DFAs.
@@ -404,6 +462,15 @@ impl<'a, T> Iterator for HintPostProcessor<'a, T> {
 mod tests {
     use super::*;
 
+    use alacritty_terminal::ansi::{self, Handler};
+    use alacritty_terminal::event::EventListener;
+    use alacritty_terminal::grid::Dimensions;
+    use alacritty_terminal::index::Column;
+    use alacritty_terminal::term::SizeInfo;
+
+    struct MockEventProxy;
+    impl EventListener for MockEventProxy {}
+
     #[test]
     fn hint_label_generation() {
         let mut generator = HintLabels::new("0123", 0.5);
@@ -442,4 +509,22 @@ mod tests {
         assert_eq!(generator.next(), vec!['3', '3', '3', '0']);
         assert_eq!(generator.next(), vec!['3', '3', '3', '1']);
     }
+
+    #[test]
+    fn hyperlink_search_limit() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&Config::default(), size, MockEventProxy);
+
+        // Create a hyperlink spanning more lines than the search limit.
+        let uri = String::from("https://example.org");
+        term.set_hyperlink(Some(ansi::Hyperlink { id: None, uri }));
+        for _ in 0..(size.columns() * MAX_SEARCH_LINES * 3) {
+            term.input('x');
+        }
+
+        let point = Point::new(size.bottommost_line(), Column(0));
+        let (_, bounds) = hyperlink_at(&term, point).unwrap();
+        assert_eq!(*bounds.start(), Point::new(point.line - MAX_SEARCH_LINES, Column(0)));
+        assert_eq!(*bounds.end(), Point::new(point.line, size.last_column()));
+    }
 }
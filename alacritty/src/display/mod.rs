@@ -17,13 +17,13 @@ use glutin::platform::unix::EventLoopWindowTargetExtUnix;
 use glutin::window::CursorIcon;
 use log::{debug, info};
 use parking_lot::MutexGuard;
-use unicode_width::UnicodeWidthChar;
 #[cfg(all(feature = "wayland", not(any(target_os = "macos", windows))))]
 use wayland_client::{Display as WaylandDisplay, EventQueue};
 
 use crossfont::{self, Rasterize, Rasterizer};
 
-use alacritty_terminal::ansi::NamedColor;
+use alacritty_terminal::ansi::{LineSize, NamedColor};
+use alacritty_terminal::config::AmbiguousWidth;
 use alacritty_terminal::event::{EventListener, OnResize};
 use alacritty_terminal::grid::Dimensions as _;
 use alacritty_terminal::index::{Column, Direction, Line, Point};
@@ -187,6 +187,12 @@ pub struct Display {
     /// UI cursor visibility for blinking.
     pub cursor_hidden: bool,
 
+    /// UI text visibility for blinking.
+    pub blinking_text_hidden: bool,
+
+    /// Blinking text was visible during the last draw.
+    pub has_blinking_text: bool,
+
     pub visual_bell: VisualBell,
 
     /// Mapped RGB values for each terminal color.
@@ -347,6 +353,8 @@ impl Display {
             #[cfg(all(feature = "wayland", not(any(target_os = "macos", windows))))]
             wayland_event_queue,
             cursor_hidden: false,
+            blinking_text_hidden: false,
+            has_blinking_text: false,
             visual_bell: VisualBell::from(&config.ui_config.bell),
             colors: List::from(&config.ui_config.colors),
         })
@@ -479,7 +487,7 @@ impl Display {
     /// This call may block if vsync is enabled.
     pub fn draw<T: EventListener>(
         &mut self,
-        terminal: MutexGuard<'_, Term<T>>,
+        mut terminal: MutexGuard<'_, Term<T>>,
         message_buffer: &MessageBuffer,
         config: &Config,
         search_state: &SearchState,
@@ -492,7 +500,9 @@ impl Display {
         }
         let background_color = content.color(NamedColor::Background as usize);
         let display_offset = content.display_offset();
+        let has_blinking_text = content.has_blinking_text();
         let cursor = content.cursor();
+        self.has_blinking_text = has_blinking_text;
 
         let cursor_point = terminal.grid().cursor.point;
         let total_lines = terminal.grid().total_lines();
@@ -502,13 +512,22 @@ impl Display {
         let vi_mode = terminal.mode().contains(TermMode::VI);
         let vi_mode_cursor = if vi_mode { Some(terminal.vi_mode_cursor) } else { None };
 
+        let graphics_queues = terminal.graphics_take_queues();
+
         // Drop terminal as early as possible to free lock.
         drop(terminal);
 
+        if let Some(graphics_queues) = graphics_queues {
+            self.renderer.graphics_run_updates(graphics_queues);
+        }
+
         self.renderer.with_api(&config.ui_config, &size_info, |api| {
             api.clear(background_color);
         });
 
+        // Draw graphics below the text.
+        self.renderer.draw_graphics(&size_info, &grid_cells);
+
         let mut lines = RenderLines::new();
 
         // Draw grid.
@@ -522,9 +541,12 @@ impl Display {
                 // Iterate over all non-empty cells in the grid.
                 for mut cell in grid_cells {
                     // Underline hints hovered by mouse or vi mode cursor.
-                    let point = viewport_to_point(display_offset, cell.point);
-                    if highlighted_hint.as_ref().map_or(false, |h| h.bounds.contains(&point))
-                        || vi_highlighted_hint.as_ref().map_or(false, |h| h.bounds.contains(&point))
+                    let mut point = viewport_to_point(display_offset, cell.point);
+                    point.column = viewport_to_cell_column(cell.line_size, point.column);
+                    if highlighted_hint.as_ref().map_or(false, |h| h.should_highlight(point, &cell))
+                        || vi_highlighted_hint
+                            .as_ref()
+                            .map_or(false, |h| h.should_highlight(point, &cell))
                     {
                         cell.flags.insert(Flags::UNDERLINE);
                     }
@@ -617,7 +639,9 @@ impl Display {
                     Direction::Left => BACKWARD_SEARCH_LABEL,
                 };
 
-                let search_text = Self::format_search(&size_info, regex, search_label);
+                let ambiguous_width = config.ambiguous_width;
+                let search_text =
+                    Self::format_search(&size_info, regex, search_label, ambiguous_width);
 
                 // Render the search bar.
                 self.draw_search(config, &size_info, &search_text);
@@ -685,14 +709,18 @@ impl Display {
         }
 
         // Find highlighted hint at mouse position.
-        let point = mouse.point(&self.size_info, term.grid().display_offset());
+        let point = mouse.point(&self.size_info, term);
         let highlighted_hint = hint::highlighted_at(term, config, point, modifiers);
 
         // Update cursor shape.
         if highlighted_hint.is_some() {
             self.window.set_mouse_cursor(CursorIcon::Hand);
         } else if self.highlighted_hint.is_some() {
-            if term.mode().intersects(TermMode::MOUSE_MODE) && !term.mode().contains(TermMode::VI) {
+            if let Some(mouse_cursor) = self.window.terminal_mouse_cursor() {
+                self.window.set_mouse_cursor(mouse_cursor);
+            } else if term.mode().intersects(TermMode::MOUSE_MODE)
+                && !term.mode().contains(TermMode::VI)
+            {
                 self.window.set_mouse_cursor(CursorIcon::Default);
             } else {
                 self.window.set_mouse_cursor(CursorIcon::Text);
@@ -706,12 +734,17 @@ impl Display {
     }
 
     /// Format search regex to account for the cursor and fullwidth characters.
-    fn format_search(size_info: &SizeInfo, search_regex: &str, search_label: &str) -> String {
+    fn format_search(
+        size_info: &SizeInfo,
+        search_regex: &str,
+        search_label: &str,
+        ambiguous_width: AmbiguousWidth,
+    ) -> String {
         // Add spacers for wide chars.
         let mut formatted_regex = String::with_capacity(search_regex.len());
         for c in search_regex.chars() {
             formatted_regex.push(c);
-            if c.width() == Some(2) {
+            if ambiguous_width.char_width(c) == Some(2) {
                 formatted_regex.push(' ');
             }
         }
@@ -828,6 +861,28 @@ pub fn viewport_to_point(display_offset: usize, point: Point<usize>) -> Point {
     Point::new(line, point.column)
 }
 
+/// Convert the column of a cell to the viewport column it is drawn at.
+///
+/// Cells on double-width lines span two viewport columns.
+#[inline]
+pub fn cell_to_viewport_column(line_size: LineSize, column: Column) -> Column {
+    if line_size.is_double_width() {
+        Column(column.0 * 2)
+    } else {
+        column
+    }
+}
+
+/// Convert a viewport column to the column of the cell drawn at it.
+#[inline]
+pub fn viewport_to_cell_column(line_size: LineSize, column: Column) -> Column {
+    if line_size.is_double_width() {
+        Column(column.0 / 2)
+    } else {
+        column
+    }
+}
+
 /// Calculate the cell dimensions based on font metrics.
 ///
 /// This will return a tuple of the cell width and height.
@@ -162,6 +162,7 @@ pub struct Window {
 
     windowed_context: WindowedContext<PossiblyCurrent>,
     current_mouse_cursor: CursorIcon,
+    terminal_mouse_cursor: Option<CursorIcon>,
     mouse_visible: bool,
 }
 
@@ -231,6 +232,7 @@ impl Window {
 
         Ok(Self {
             current_mouse_cursor,
+            terminal_mouse_cursor: None,
             mouse_visible: true,
             windowed_context,
             #[cfg(not(any(target_os = "macos", windows)))]
@@ -268,6 +270,21 @@ impl Window {
         }
     }
 
+    /// Mouse cursor requested by the terminal application.
+    #[inline]
+    pub fn terminal_mouse_cursor(&self) -> Option<CursorIcon> {
+        self.terminal_mouse_cursor
+    }
+
+    /// Update the mouse cursor requested by the terminal application.
+    ///
+    /// The shape is a comma separated list of CSS or X11 cursor names, the first known name is
+    /// used. Without any known name, the default mouse cursor is restored.
+    pub fn set_terminal_mouse_cursor(&mut self, shape: Option<&str>) {
+        self.terminal_mouse_cursor =
+            shape.and_then(|shape| shape.split(',').find_map(|name| cursor_icon(name.trim())));
+    }
+
     /// Set mouse cursor visible.
     pub fn set_mouse_visible(&mut self, visible: bool) {
         if visible != self.mouse_visible {
@@ -364,6 +381,16 @@ impl Window {
         self.window().set_outer_position(pos);
     }
 
+    /// Position of the window's top-left corner.
+    pub fn outer_position(&self) -> Option<PhysicalPosition<i32>> {
+        self.window().outer_position().ok()
+    }
+
+    /// Size of the monitor the window is on.
+    pub fn monitor_size(&self) -> Option<PhysicalSize<u32>> {
+        self.window().current_monitor().map(|monitor| monitor.size())
+    }
+
     #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
     pub fn x11_window_id(&self) -> Option<usize> {
         self.window().xlib_window().map(|xlib_window| xlib_window as usize)
@@ -378,7 +405,6 @@ impl Window {
         self.window().id()
     }
 
-    #[cfg(not(any(target_os = "macos", windows)))]
     pub fn set_maximized(&self, maximized: bool) {
         self.window().set_maximized(maximized);
     }
@@ -457,6 +483,48 @@ impl Window {
     }
 }
 
+/// Convert a CSS or X11 cursor name to its mouse cursor icon.
+fn cursor_icon(name: &str) -> Option<CursorIcon> {
+    let icon = match name {
+        "default" | "left_ptr" => CursorIcon::Default,
+        "text" | "xterm" => CursorIcon::Text,
+        "pointer" | "hand1" | "hand2" => CursorIcon::Hand,
+        "crosshair" | "cross" | "tcross" => CursorIcon::Crosshair,
+        "move" | "fleur" => CursorIcon::Move,
+        "wait" | "watch" => CursorIcon::Wait,
+        "help" | "question_arrow" => CursorIcon::Help,
+        "progress" | "left_ptr_watch" => CursorIcon::Progress,
+        "not-allowed" | "crossed_circle" => CursorIcon::NotAllowed,
+        "context-menu" => CursorIcon::ContextMenu,
+        "cell" | "plus" => CursorIcon::Cell,
+        "vertical-text" => CursorIcon::VerticalText,
+        "alias" | "dnd-link" => CursorIcon::Alias,
+        "copy" | "dnd-copy" => CursorIcon::Copy,
+        "no-drop" | "dnd-no-drop" => CursorIcon::NoDrop,
+        "grab" | "openhand" => CursorIcon::Grab,
+        "grabbing" | "closedhand" => CursorIcon::Grabbing,
+        "all-scroll" => CursorIcon::AllScroll,
+        "zoom-in" => CursorIcon::ZoomIn,
+        "zoom-out" => CursorIcon::ZoomOut,
+        "e-resize" | "right_side" => CursorIcon::EResize,
+        "n-resize" | "top_side" => CursorIcon::NResize,
+        "ne-resize" | "top_right_corner" => CursorIcon::NeResize,
+        "nw-resize" | "top_left_corner" => CursorIcon::NwResize,
+        "s-resize" | "bottom_side" => CursorIcon::SResize,
+        "se-resize" | "bottom_right_corner" => CursorIcon::SeResize,
+        "sw-resize" | "bottom_left_corner" => CursorIcon::SwResize,
+        "w-resize" | "left_side" => CursorIcon::WResize,
+        "ew-resize" | "sb_h_double_arrow" => CursorIcon::EwResize,
+        "ns-resize" | "sb_v_double_arrow" => CursorIcon::NsResize,
+        "nesw-resize" | "fd_double_arrow" => CursorIcon::NeswResize,
+        "nwse-resize" | "bd_double_arrow" => CursorIcon::NwseResize,
+        "col-resize" => CursorIcon::ColResize,
+        "row-resize" => CursorIcon::RowResize,
+        _ => return None,
+    };
+    Some(icon)
+}
+
 #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
 fn x_embed_window(window: &GlutinWindow, parent_id: std::os::raw::c_ulong) {
     let (xlib_display, xlib_window) = match (window.xlib_display(), window.xlib_window()) {
@@ -2,7 +2,7 @@
 
 use std::borrow::Cow;
 use std::cmp::{max, min};
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
 use std::env;
 use std::f32;
 use std::fmt::Debug;
@@ -17,7 +17,7 @@ use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use glutin::dpi::PhysicalSize;
+use glutin::dpi::{PhysicalPosition, PhysicalSize};
 use glutin::event::{ElementState, Event as GlutinEvent, ModifiersState, MouseButton, WindowEvent};
 use glutin::event_loop::{ControlFlow, EventLoop, EventLoopProxy, EventLoopWindowTarget};
 use glutin::platform::run_return::EventLoopExtRunReturn;
@@ -28,6 +28,7 @@ use serde_json as json;
 
 use crossfont::{self, Size};
 
+use alacritty_terminal::ansi::WindowOperation;
 use alacritty_terminal::config::LOG_TARGET_CONFIG;
 use alacritty_terminal::event::{Event as TerminalEvent, EventListener, Notify, OnResize};
 use alacritty_terminal::grid::{Dimensions, Scroll};
@@ -71,6 +72,7 @@ pub enum Event {
     ConfigReload(PathBuf),
     Message(Message),
     BlinkCursor,
+    BlinkText,
     SearchNext,
 }
 
@@ -163,6 +165,8 @@ pub struct ActionContext<'a, N, T> {
     pub mouse: &'a mut Mouse,
     pub received_count: &'a mut usize,
     pub suppress_chars: &'a mut bool,
+    pub pressed_key: &'a mut Option<u32>,
+    pub reported_keys: &'a mut HashSet<u32>,
     pub modifiers: &'a mut ModifiersState,
     pub display: &'a mut Display,
     pub message_buffer: &'a mut MessageBuffer,
@@ -212,8 +216,7 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         } else if self.mouse.left_button_state == ElementState::Pressed
             || self.mouse.right_button_state == ElementState::Pressed
         {
-            let display_offset = self.terminal.grid().display_offset();
-            let point = self.mouse.point(&self.size_info(), display_offset);
+            let point = self.mouse.point(&self.size_info(), self.terminal);
             self.update_selection(point, self.mouse.cell_side);
         }
         self.copy_selection(ClipboardType::Selection);
@@ -313,6 +316,16 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         &mut self.suppress_chars
     }
 
+    #[inline]
+    fn pressed_key(&mut self) -> &mut Option<u32> {
+        &mut self.pressed_key
+    }
+
+    #[inline]
+    fn reported_keys(&mut self) -> &mut HashSet<u32> {
+        &mut self.reported_keys
+    }
+
     #[inline]
     fn modifiers(&mut self) -> &mut ModifiersState {
         &mut self.modifiers
@@ -342,29 +355,11 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         let mut env_args = env::args();
         let alacritty = env_args.next().unwrap();
 
-        #[cfg(unix)]
-        let mut args = {
-            // Use working directory of controlling process, or fallback to initial shell.
-            let mut pid = unsafe { libc::tcgetpgrp(tty::master_fd()) };
-            if pid < 0 {
-                pid = tty::child_pid();
-            }
-
-            #[cfg(not(any(target_os = "macos", target_os = "freebsd")))]
-            let link_path = format!("/proc/{}/cwd", pid);
-            #[cfg(target_os = "freebsd")]
-            let link_path = format!("/compat/linux/proc/{}/cwd", pid);
-            #[cfg(not(target_os = "macos"))]
-            let cwd = fs::read_link(link_path);
-            #[cfg(target_os = "macos")]
-            let cwd = macos::proc::cwd(pid);
-
-            // Add the current working directory as parameter.
-            cwd.map(|path| vec!["--working-directory".into(), path]).unwrap_or_default()
-        };
-
-        #[cfg(not(unix))]
-        let mut args: Vec<PathBuf> = Vec::new();
+        // Add the current working directory as parameter.
+        let mut args: Vec<PathBuf> = self
+            .working_directory()
+            .map(|path| vec!["--working-directory".into(), path])
+            .unwrap_or_default();
 
         let working_directory_set = !args.is_empty();
 
@@ -379,7 +374,7 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
             args.push(arg.into());
         }
 
-        start_daemon(&alacritty, &args);
+        start_daemon(&alacritty, &args, None);
     }
 
     fn change_font_size(&mut self, delta: f32) {
@@ -635,22 +630,28 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
             return;
         }
 
+        // Use the hyperlink's URI if present, instead of the text within the hint's bounds.
+        let hint_text = || match &hint.hyperlink {
+            Some(hyperlink) => hyperlink.uri().to_owned(),
+            None => self.terminal.bounds_to_string(*hint.bounds.start(), *hint.bounds.end()),
+        };
+
         match &hint.action {
             // Launch an external program.
             HintAction::Command(command) => {
-                let text = self.terminal.bounds_to_string(*hint.bounds.start(), *hint.bounds.end());
+                let text = hint_text();
                 let mut args = command.args().to_vec();
                 args.push(text);
-                start_daemon(command.program(), &args);
+                start_daemon(command.program(), &args, self.working_directory().as_deref());
             },
             // Copy the text to the clipboard.
             HintAction::Action(HintInternalAction::Copy) => {
-                let text = self.terminal.bounds_to_string(*hint.bounds.start(), *hint.bounds.end());
+                let text = hint_text();
                 self.clipboard.store(ClipboardType::Clipboard, text);
             },
             // Write the text to the PTY/search.
             HintAction::Action(HintInternalAction::Paste) => {
-                let text = self.terminal.bounds_to_string(*hint.bounds.start(), *hint.bounds.end());
+                let text = hint_text();
                 self.paste(&text);
             },
             // Select the text.
@@ -727,6 +728,56 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
 }
 
 impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
+    /// Get the shell's current working directory.
+    ///
+    /// This prefers the directory reported by the shell through OSC 7, falling back to the working
+    /// directory of the controlling process.
+    fn working_directory(&self) -> Option<PathBuf> {
+        if let Some(working_directory) = self.terminal.working_directory() {
+            return Some(working_directory.to_owned());
+        }
+
+        #[cfg(unix)]
+        {
+            // Use working directory of controlling process, or fallback to initial shell.
+            let mut pid = unsafe { libc::tcgetpgrp(tty::master_fd()) };
+            if pid < 0 {
+                pid = tty::child_pid();
+            }
+
+            #[cfg(not(any(target_os = "macos", target_os = "freebsd")))]
+            let link_path = format!("/proc/{}/cwd", pid);
+            #[cfg(target_os = "freebsd")]
+            let link_path = format!("/compat/linux/proc/{}/cwd", pid);
+            #[cfg(not(target_os = "macos"))]
+            let cwd = fs::read_link(link_path);
+            #[cfg(target_os = "macos")]
+            let cwd = macos::proc::cwd(pid);
+
+            cwd.ok()
+        }
+
+        #[cfg(not(unix))]
+        None
+    }
+
+    /// Reset the window title to its default.
+    ///
+    /// This uses the working directory reported by the shell, falling back to the configured title.
+    fn reset_title(&mut self) {
+        let ui_config = &self.config.ui_config;
+        if !ui_config.window.dynamic_title {
+            return;
+        }
+
+        match self.terminal.working_directory() {
+            Some(working_directory) => {
+                self.display.window.set_title(&working_directory.display().to_string())
+            },
+            None => self.display.window.set_title(&ui_config.window.title),
+        }
+    }
+
     fn update_search(&mut self) {
         let regex = match self.search_state.regex() {
             Some(regex) => regex,
@@ -843,6 +894,60 @@ impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
         self.search_state.focused_match = None;
     }
 
+    /// Apply a window operation requested by the terminal application.
+    fn window_operation(&mut self, operation: WindowOperation) {
+        let size_info = self.display.size_info;
+        let window = &mut self.display.window;
+
+        match operation {
+            WindowOperation::Deiconify => window.set_minimized(false),
+            WindowOperation::Iconify => window.set_minimized(true),
+            WindowOperation::Move { x, y } => {
+                window.set_outer_position(PhysicalPosition::new(x, y))
+            },
+            WindowOperation::ResizePixels { width, height } => {
+                let padding_x = 2. * size_info.padding_x();
+                let padding_y = 2. * size_info.padding_y();
+                let width = width.map_or(size_info.width(), |width| width as f32 + padding_x);
+                let height = height.map_or(size_info.height(), |height| height as f32 + padding_y);
+                window.set_inner_size(PhysicalSize::new(width as u32, height as u32));
+            },
+            WindowOperation::ResizeCells { columns, lines } => {
+                let padding_x = 2. * size_info.padding_x();
+                let padding_y = 2. * size_info.padding_y();
+                let width = columns.map_or(size_info.width(), |columns| {
+                    columns as f32 * size_info.cell_width() + padding_x
+                });
+                let height = lines.map_or(size_info.height(), |lines| {
+                    lines as f32 * size_info.cell_height() + padding_y
+                });
+                window.set_inner_size(PhysicalSize::new(width as u32, height as u32));
+            },
+            WindowOperation::Maximize(maximized) => window.set_maximized(maximized),
+            WindowOperation::Fullscreen(fullscreen) => window.set_fullscreen(fullscreen),
+            WindowOperation::ToggleFullscreen => window.toggle_fullscreen(),
+            // The window is never iconified while applications can talk to it.
+            WindowOperation::ReportState => self.write_to_pty(&b"\x1b[1t"[..]),
+            WindowOperation::ReportPosition => {
+                let position = window.outer_position().unwrap_or_default();
+                let text = format!("\x1b[3;{};{}t", position.x, position.y);
+                self.write_to_pty(text.into_bytes());
+            },
+            WindowOperation::ReportScreenSize => {
+                if let Some(size) = window.monitor_size() {
+                    let columns = size.width as f32 / size_info.cell_width();
+                    let lines = size.height as f32 / size_info.cell_height();
+                    let text = format!("\x1b[9;{};{}t", lines as usize, columns as usize);
+                    self.write_to_pty(text.into_bytes());
+                }
+            },
+            // Cell size and title reports are handled by the terminal.
+            WindowOperation::ReportCellSize
+            | WindowOperation::ReportIconTitle
+            | WindowOperation::ReportTitle => (),
+        }
+    }
+
     /// Update the cursor blinking state.
     fn update_cursor_blinking(&mut self) {
         // Get config cursor style.
@@ -925,14 +1030,26 @@ impl Mouse {
     /// If the coordinates are outside of the terminal grid, like positions inside the padding, the
     /// coordinates will be clamped to the closest grid coordinates.
     #[inline]
-    pub fn point(&self, size: &SizeInfo, display_offset: usize) -> Point {
+    pub fn viewport_point(&self, size: &SizeInfo) -> Point<usize> {
         let col = self.x.saturating_sub(size.padding_x() as usize) / (size.cell_width() as usize);
         let col = min(Column(col), size.last_column());
 
         let line = self.y.saturating_sub(size.padding_y() as usize) / (size.cell_height() as usize);
         let line = min(line, size.bottommost_line().0 as usize);
 
-        display::viewport_to_point(display_offset, Point::new(line, col))
+        Point::new(line, col)
+    }
+
+    /// Convert mouse pixel coordinates to the point of the terminal cell below the mouse.
+    #[inline]
+    pub fn point<T>(&self, size: &SizeInfo, term: &Term<T>) -> Point {
+        let display_offset = term.grid().display_offset();
+        let mut point = display::viewport_to_point(display_offset, self.viewport_point(size));
+
+        let line_size = term.grid()[point.line].line_size();
+        point.column = display::viewport_to_cell_column(line_size, point.column);
+
+        point
     }
 }
 
@@ -945,6 +1062,8 @@ pub struct Processor<N> {
     mouse: Mouse,
     received_count: usize,
     suppress_chars: bool,
+    pressed_key: Option<u32>,
+    reported_keys: HashSet<u32>,
     modifiers: ModifiersState,
     config: Config,
     message_buffer: MessageBuffer,
@@ -976,6 +1095,8 @@ impl<N: Notify + OnResize> Processor<N> {
             config,
             received_count: Default::default(),
             suppress_chars: Default::default(),
+            pressed_key: Default::default(),
+            reported_keys: Default::default(),
             search_state: Default::default(),
             event_queue: Default::default(),
             modifiers: Default::default(),
@@ -1086,6 +1207,8 @@ impl<N: Notify + OnResize> Processor<N> {
                 clipboard: &mut clipboard,
                 received_count: &mut self.received_count,
                 suppress_chars: &mut self.suppress_chars,
+                pressed_key: &mut self.pressed_key,
+                reported_keys: &mut self.reported_keys,
                 modifiers: &mut self.modifiers,
                 message_buffer: &mut self.message_buffer,
                 display_update_pending: &mut display_update_pending,
@@ -1138,6 +1261,19 @@ impl<N: Notify + OnResize> Processor<N> {
 
                 // Redraw screen.
                 self.display.draw(terminal, &self.message_buffer, &self.config, &self.search_state);
+
+                // Only blink text while there is blinking text visible.
+                if !self.config.ui_config.blinking_text || !self.display.has_blinking_text {
+                    scheduler.unschedule(TimerId::BlinkText);
+                    self.display.blinking_text_hidden = false;
+                } else if !scheduler.scheduled(TimerId::BlinkText) {
+                    scheduler.schedule(
+                        GlutinEvent::UserEvent(Event::BlinkText),
+                        Duration::from_millis(self.config.cursor.blink_interval()),
+                        true,
+                        TimerId::BlinkText,
+                    );
+                }
             }
         });
 
@@ -1183,6 +1319,10 @@ impl<N: Notify + OnResize> Processor<N> {
                     processor.ctx.display.cursor_hidden ^= true;
                     *processor.ctx.dirty = true;
                 },
+                Event::BlinkText => {
+                    processor.ctx.display.blinking_text_hidden ^= true;
+                    *processor.ctx.dirty = true;
+                },
                 Event::Terminal(event) => match event {
                     TerminalEvent::Title(title) => {
                         let ui_config = &processor.ctx.config.ui_config;
@@ -1190,10 +1330,11 @@ impl<N: Notify + OnResize> Processor<N> {
                             processor.ctx.window().set_title(&title);
                         }
                     },
-                    TerminalEvent::ResetTitle => {
-                        let ui_config = &processor.ctx.config.ui_config;
-                        if ui_config.window.dynamic_title {
-                            processor.ctx.display.window.set_title(&ui_config.window.title);
+                    TerminalEvent::ResetTitle => processor.ctx.reset_title(),
+                    TerminalEvent::WorkingDirectory(_) => {
+                        // Only update the title when the application hasn't set one.
+                        if processor.ctx.terminal.title().is_none() {
+                            processor.ctx.reset_title();
                         }
                     },
                     TerminalEvent::Wakeup => *processor.ctx.dirty = true,
@@ -1209,7 +1350,23 @@ impl<N: Notify + OnResize> Processor<N> {
 
                         // Execute bell command.
                         if let Some(bell_command) = &processor.ctx.config.ui_config.bell.command {
-                            start_daemon(bell_command.program(), bell_command.args());
+                            start_daemon(bell_command.program(), bell_command.args(), None);
+                        }
+                    },
+                    TerminalEvent::Notification(title, body) => {
+                        let notifications = &processor.ctx.config.ui_config.notifications;
+                        let focused = processor.ctx.terminal.is_focused;
+
+                        // Execute notification command, passing title and body as arguments.
+                        match &notifications.command {
+                            Some(command) if !notifications.unfocused_only || !focused => {
+                                let mut args = command.args().to_vec();
+                                args.push(title.unwrap_or_else(|| String::from("Alacritty")));
+                                args.push(body);
+
+                                start_daemon(command.program(), &args, None);
+                            },
+                            _ => (),
                         }
                     },
                     TerminalEvent::ClipboardStore(clipboard_type, content) => {
@@ -1220,11 +1377,21 @@ impl<N: Notify + OnResize> Processor<N> {
                         processor.ctx.write_to_pty(text.into_bytes());
                     },
                     TerminalEvent::ColorRequest(index, format) => {
-                        let text = format(processor.ctx.display.colors[index]);
+                        // Colors modified by the terminal take precedence over the config.
+                        let color = processor.ctx.terminal.colors()[index]
+                            .unwrap_or(processor.ctx.display.colors[index]);
+                        let text = format(color);
                         processor.ctx.write_to_pty(text.into_bytes());
                     },
                     TerminalEvent::PtyWrite(text) => processor.ctx.write_to_pty(text.into_bytes()),
                     TerminalEvent::MouseCursorDirty => processor.reset_mouse_cursor(),
+                    TerminalEvent::WindowOperation(operation) => {
+                        processor.ctx.window_operation(operation);
+                    },
+                    TerminalEvent::MouseCursorShape(shape) => {
+                        processor.ctx.window().set_terminal_mouse_cursor(shape.as_deref());
+                        processor.reset_mouse_cursor();
+                    },
                     TerminalEvent::Exit => (),
                     TerminalEvent::CursorBlinkingChange(_) => {
                         processor.ctx.update_cursor_blinking();
@@ -7,22 +7,25 @@
 
 use std::borrow::Cow;
 use std::cmp::{max, min, Ordering};
+use std::collections::HashSet;
+use std::convert::TryFrom;
 use std::marker::PhantomData;
 use std::time::{Duration, Instant};
 
 use glutin::dpi::PhysicalPosition;
 use glutin::event::{
     ElementState, KeyboardInput, ModifiersState, MouseButton, MouseScrollDelta, TouchPhase,
+    VirtualKeyCode,
 };
 use glutin::event_loop::EventLoopWindowTarget;
 #[cfg(target_os = "macos")]
 use glutin::platform::macos::EventLoopWindowTargetExtMacOS;
 use glutin::window::CursorIcon;
 
-use alacritty_terminal::ansi::{ClearMode, Handler};
+use alacritty_terminal::ansi::{ClearMode, Handler, MouseHighlight};
 use alacritty_terminal::event::EventListener;
 use alacritty_terminal::grid::{Dimensions, Scroll};
-use alacritty_terminal::index::{Boundary, Column, Direction, Point, Side};
+use alacritty_terminal::index::{Boundary, Column, Direction, Line, Point, Side};
 use alacritty_terminal::selection::SelectionType;
 use alacritty_terminal::term::search::Match;
 use alacritty_terminal::term::{ClipboardType, SizeInfo, Term, TermMode};
@@ -73,6 +76,8 @@ pub trait ActionContext<T: EventListener> {
     fn mouse(&self) -> &Mouse;
     fn received_count(&mut self) -> &mut usize;
     fn suppress_chars(&mut self) -> &mut bool;
+    fn pressed_key(&mut self) -> &mut Option<u32>;
+    fn reported_keys(&mut self) -> &mut HashSet<u32>;
     fn modifiers(&mut self) -> &mut ModifiersState;
     fn scroll(&mut self, _scroll: Scroll) {}
     fn window(&mut self) -> &mut Window;
@@ -137,7 +142,7 @@ impl<T: EventListener> Execute<T> for Action {
                 ctx.scroll(Scroll::Bottom);
                 ctx.write_to_pty(s.clone().into_bytes())
             },
-            Action::Command(program) => start_daemon(program.program(), program.args()),
+            Action::Command(program) => start_daemon(program.program(), program.args(), None),
             Action::Hint(hint) => {
                 ctx.display().hint_state.start(hint.clone());
                 ctx.mark_dirty();
@@ -314,6 +319,43 @@ impl<T: EventListener> Execute<T> for Action {
                 term.vi_motion(ViMotion::FirstOccupied);
                 ctx.mark_dirty();
             },
+            Action::ScrollToPreviousPrompt | Action::ScrollToNextPrompt => {
+                let direction = match self {
+                    Action::ScrollToPreviousPrompt => Direction::Left,
+                    _ => Direction::Right,
+                };
+
+                // Search from the vi mode cursor, or the top of the viewport otherwise.
+                let term = ctx.terminal();
+                let display_offset = term.grid().display_offset() as i32;
+                let origin = if term.mode().contains(TermMode::VI) {
+                    term.vi_mode_cursor.point.line
+                } else {
+                    Line(-display_offset)
+                };
+
+                if let Some(line) = term.prompt_line(origin, direction) {
+                    // Move the prompt to the top of the viewport.
+                    ctx.scroll(Scroll::Delta(-line.0 - display_offset));
+
+                    // Move vi mode cursor.
+                    ctx.terminal_mut().vi_mode_cursor.point = Point::new(line, Column(0));
+                    ctx.mark_dirty();
+                }
+            },
+            Action::SelectLastCommandOutput => {
+                if let Some(output) = ctx.terminal().last_command_output() {
+                    ctx.start_selection(SelectionType::Lines, *output.start(), Side::Left);
+                    ctx.update_selection(*output.end(), Side::Right);
+                    ctx.copy_selection(ClipboardType::Selection);
+                }
+            },
+            Action::CopyLastCommandOutput => {
+                if let Some(output) = ctx.terminal().last_command_output() {
+                    let text = ctx.terminal().bounds_to_string(*output.start(), *output.end());
+                    ctx.clipboard_mut().store(ClipboardType::Clipboard, text);
+                }
+            },
             Action::ClearHistory => ctx.terminal_mut().clear_screen(ClearMode::Saved),
             Action::ClearLogNotice => ctx.pop_message(),
             Action::SpawnNewInstance => ctx.spawn_new_instance(),
@@ -339,8 +381,7 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
             self.update_selection_scrolling(y);
         }
 
-        let display_offset = self.ctx.terminal().grid().display_offset();
-        let old_point = self.ctx.mouse().point(&size_info, display_offset);
+        let old_point = self.ctx.mouse().point(&size_info, self.ctx.terminal());
 
         let x = min(max(x, 0), size_info.width() as i32 - 1) as usize;
         let y = min(max(y, 0), size_info.height() as i32 - 1) as usize;
@@ -348,13 +389,20 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
         self.ctx.mouse_mut().y = y;
 
         let inside_text_area = size_info.contains_point(x, y);
-        let cell_side = self.cell_side(x);
 
-        let point = self.ctx.mouse().point(&size_info, display_offset);
+        let point = self.ctx.mouse().point(&size_info, self.ctx.terminal());
+        let double_width = self.ctx.terminal().grid()[point.line].line_size().is_double_width();
+        let cell_side = self.cell_side(x, double_width);
+
         let cell_changed = old_point != point;
 
+        // Pixel reports include motion inside of a cell.
+        let pixel_changed = self.ctx.mouse_mode()
+            && self.ctx.terminal().mode().contains(TermMode::SGR_PIXELS_MOUSE);
+
         // If the mouse hasn't changed cells, do nothing.
         if !cell_changed
+            && !pixel_changed
             && self.ctx.mouse().cell_side == cell_side
             && self.ctx.mouse().inside_text_area == inside_text_area
         {
@@ -377,7 +425,9 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
         if (lmb_pressed || rmb_pressed) && (self.ctx.modifiers().shift() || !self.ctx.mouse_mode())
         {
             self.ctx.update_selection(point, cell_side);
-        } else if cell_changed
+        } else if lmb_pressed && self.ctx.terminal().mouse_highlight.is_some() {
+            self.update_mouse_highlight(point, cell_side);
+        } else if (cell_changed || pixel_changed)
             && self.ctx.terminal().mode().intersects(TermMode::MOUSE_MOTION | TermMode::MOUSE_DRAG)
         {
             if lmb_pressed {
@@ -393,12 +443,14 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
     }
 
     /// Check which side of a cell an X coordinate lies on.
-    fn cell_side(&self, x: usize) -> Side {
+    ///
+    /// Cells on double-width lines span the width of two viewport columns.
+    fn cell_side(&self, x: usize, double_width: bool) -> Side {
         let size_info = self.ctx.size_info();
 
-        let cell_x =
-            x.saturating_sub(size_info.padding_x() as usize) % size_info.cell_width() as usize;
-        let half_cell_width = (size_info.cell_width() / 2.0) as usize;
+        let cell_width = size_info.cell_width() * if double_width { 2. } else { 1. };
+        let cell_x = x.saturating_sub(size_info.padding_x() as usize) % cell_width as usize;
+        let half_cell_width = (cell_width / 2.0) as usize;
 
         let additional_padding =
             (size_info.width() - size_info.padding_x() * 2.) % size_info.cell_width();
@@ -415,8 +467,7 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
     }
 
     fn mouse_report(&mut self, button: u8, state: ElementState) {
-        let display_offset = self.ctx.terminal().grid().display_offset();
-        let point = self.ctx.mouse().point(&self.ctx.size_info(), display_offset);
+        let point = self.ctx.mouse().point(&self.ctx.size_info(), self.ctx.terminal());
 
         // Assure the mouse point is not in the scrollback.
         if point.line < 0 {
@@ -437,12 +488,30 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
         }
 
         // Report mouse events.
-        if self.ctx.terminal().mode().contains(TermMode::SGR_MOUSE) {
-            self.sgr_mouse_report(point, button + mods, state);
+        let mode = *self.ctx.terminal().mode();
+        if mode.contains(TermMode::SGR_PIXELS_MOUSE) {
+            let size_info = self.ctx.size_info();
+            let x = self.ctx.mouse().x.saturating_sub(size_info.padding_x() as usize);
+            let y = self.ctx.mouse().y.saturating_sub(size_info.padding_y() as usize);
+            let x = min(x, (size_info.columns() as f32 * size_info.cell_width()) as usize - 1);
+            let y =
+                min(y, (size_info.screen_lines() as f32 * size_info.cell_height()) as usize - 1);
+            self.sgr_mouse_report(x, y, button + mods, state);
+        } else if mode.contains(TermMode::SGR_MOUSE) {
+            self.sgr_mouse_report(point.column.0, point.line.0 as usize, button + mods, state);
         } else if let ElementState::Released = state {
-            self.normal_mouse_report(point, 3 + mods);
+            self.legacy_mouse_report(point, 3 + mods);
         } else {
-            self.normal_mouse_report(point, button + mods);
+            self.legacy_mouse_report(point, button + mods);
+        }
+    }
+
+    /// Report mouse events with one of the encodings which cannot distinguish button releases.
+    fn legacy_mouse_report(&mut self, point: Point, button: u8) {
+        if self.ctx.terminal().mode().contains(TermMode::URXVT_MOUSE) {
+            self.urxvt_mouse_report(point, button);
+        } else {
+            self.normal_mouse_report(point, button);
         }
     }
 
@@ -480,21 +549,84 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
         self.ctx.write_to_pty(msg);
     }
 
-    fn sgr_mouse_report(&mut self, point: Point, button: u8, state: ElementState) {
+    fn urxvt_mouse_report(&mut self, point: Point, button: u8) {
+        let msg = format!("\x1b[{};{};{}M", 32 + button, point.column + 1, point.line + 1);
+        self.ctx.write_to_pty(msg.into_bytes());
+    }
+
+    /// Report a mouse event in the SGR encoding, using either cell or pixel coordinates.
+    fn sgr_mouse_report(&mut self, x: usize, y: usize, button: u8, state: ElementState) {
         let c = match state {
             ElementState::Pressed => 'M',
             ElementState::Released => 'm',
         };
 
-        let msg = format!("\x1b[<{};{};{}{}", button, point.column + 1, point.line + 1, c);
+        let msg = format!("\x1b[<{};{};{}{}", button, x + 1, y + 1, c);
         self.ctx.write_to_pty(msg.into_bytes());
     }
 
+    /// Extend the text highlighted for highlight mouse tracking.
+    fn update_mouse_highlight(&mut self, mut point: Point, side: Side) {
+        let highlight = match self.ctx.terminal().mouse_highlight {
+            Some(highlight) => highlight,
+            None => return,
+        };
+
+        // Restrict the highlighted text to the lines requested by the application.
+        point.line = highlight_line(&highlight, point.line);
+
+        if self.ctx.terminal().selection.is_none() {
+            let mut start = highlight_start(&highlight);
+            start.line = min(start.line, self.ctx.terminal().bottommost_line());
+            start.column = min(start.column, self.ctx.terminal().last_column());
+            self.ctx.start_selection(SelectionType::Simple, start, Side::Left);
+        }
+
+        self.ctx.update_selection(point, side);
+    }
+
+    /// Report the end of highlight mouse tracking.
+    fn mouse_highlight_report(&mut self, highlight: MouseHighlight) {
+        let mouse = self.ctx.mouse().point(&self.ctx.size_info(), self.ctx.terminal());
+
+        let end = Point::new(highlight_line(&highlight, mouse.line), mouse.column);
+        let start = highlight_start(&highlight);
+
+        self.ctx.clear_selection();
+
+        // Coordinates use the same encoding as the normal mouse protocol.
+        let encode = |point: Point| -> Option<[u8; 2]> {
+            let column = u8::try_from(point.column.0 + 33).ok()?;
+            let line = u8::try_from(point.line.0 + 33).ok()?;
+            Some([column, line])
+        };
+
+        let msg = if start == end {
+            encode(end).map(|end| [&b"\x1b[t"[..], &end].concat())
+        } else {
+            let (start, end) = if start < end { (start, end) } else { (end, start) };
+            encode(start)
+                .zip(encode(end))
+                .zip(encode(mouse))
+                .map(|((start, end), mouse)| [&b"\x1b[T"[..], &start, &end, &mouse].concat())
+        };
+
+        if let Some(msg) = msg {
+            self.ctx.write_to_pty(msg);
+        }
+    }
+
     fn on_mouse_press(&mut self, button: MouseButton) {
         // Handle mouse mode.
         if !self.ctx.modifiers().shift() && self.ctx.mouse_mode() {
             self.ctx.mouse_mut().click_state = ClickState::None;
 
+            // Every press starts a new highlight mouse tracking request.
+            if self.ctx.terminal().mode().contains(TermMode::MOUSE_HIGHLIGHT) {
+                self.ctx.terminal_mut().mouse_highlight = None;
+                self.ctx.clear_selection();
+            }
+
             let code = match button {
                 MouseButton::Left => 0,
                 MouseButton::Middle => 1,
@@ -528,8 +660,7 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
             };
 
             // Load mouse point, treating message bar and padding as the closest cell.
-            let display_offset = self.ctx.terminal().grid().display_offset();
-            let point = self.ctx.mouse().point(&self.ctx.size_info(), display_offset);
+            let point = self.ctx.mouse().point(&self.ctx.size_info(), self.ctx.terminal());
 
             match button {
                 MouseButton::Left => self.on_left_click(point),
@@ -620,6 +751,15 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
                 // Can't properly report more than three buttons.
                 MouseButton::Other(_) => return,
             };
+
+            // Highlight tracking replaces the release report with the highlighted region.
+            if button == MouseButton::Left {
+                if let Some(highlight) = self.ctx.terminal_mut().mouse_highlight.take() {
+                    self.mouse_highlight_report(highlight);
+                    return;
+                }
+            }
+
             self.mouse_report(code, ElementState::Released);
             return;
         }
@@ -774,10 +914,26 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
 
         match input.state {
             ElementState::Pressed => {
+                // Keys which are pressed without being released first are repeats.
+                let repeat = self.ctx.pressed_key().replace(input.scancode) == Some(input.scancode);
+
                 *self.ctx.received_count() = 0;
-                self.process_key_bindings(input);
+                self.process_key_bindings(input, repeat);
+            },
+            ElementState::Released => {
+                if *self.ctx.pressed_key() == Some(input.scancode) {
+                    *self.ctx.pressed_key() = None;
+                }
+
+                *self.ctx.suppress_chars() = false;
+
+                // Only report the release if the press was reported too.
+                if self.ctx.reported_keys().remove(&input.scancode) {
+                    if let Some(sequence) = self.kitty_key_sequence(input, KeyEventType::Release) {
+                        self.ctx.write_to_pty(sequence.into_bytes());
+                    }
+                }
             },
-            ElementState::Released => *self.ctx.suppress_chars() = false,
         }
     }
 
@@ -849,11 +1005,14 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
     ///
     /// The provided mode, mods, and key must match what is allowed by a binding
     /// for its action to be executed.
-    fn process_key_bindings(&mut self, input: KeyboardInput) {
+    fn process_key_bindings(&mut self, input: KeyboardInput, repeat: bool) {
         let mode = BindingMode::new(self.ctx.terminal().mode(), self.ctx.search_active());
         let mods = *self.ctx.modifiers();
         let mut suppress_chars = None;
 
+        let event_type = if repeat { KeyEventType::Repeat } else { KeyEventType::Press };
+        let kitty_sequence = self.kitty_key_sequence(input, event_type);
+
         for i in 0..self.ctx.config().ui_config.key_bindings().len() {
             let binding = &self.ctx.config().ui_config.key_bindings()[i];
 
@@ -864,6 +1023,14 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
             };
 
             if binding.is_triggered_by(mode, mods, &key) {
+                // Default legacy escapes are replaced by the keyboard protocol's encoding.
+                let is_user_binding = self.ctx.config().ui_config.is_user_key_binding(i);
+                if let (Some(_), Action::Esc(_)) = (&kitty_sequence, &binding.action) {
+                    if !is_user_binding {
+                        continue;
+                    }
+                }
+
                 // Pass through the key if any of the bindings has the `ReceiveChar` action.
                 *suppress_chars.get_or_insert(true) &= binding.action != Action::ReceiveChar;
 
@@ -872,10 +1039,44 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
             }
         }
 
+        // Send the encoded key if it wasn't consumed by any binding.
+        match kitty_sequence {
+            Some(sequence) if suppress_chars != Some(true) => {
+                Action::Esc(sequence).execute(&mut self.ctx);
+                self.ctx.reported_keys().insert(input.scancode);
+                suppress_chars = Some(true);
+            },
+            _ if !repeat => {
+                self.ctx.reported_keys().remove(&input.scancode);
+            },
+            _ => (),
+        }
+
         // Don't suppress char if no bindings were triggered.
         *self.ctx.suppress_chars() = suppress_chars.unwrap_or(false);
     }
 
+    /// Encode a key using the kitty keyboard protocol.
+    ///
+    /// This will return `None` if the protocol is disabled or the key should use its legacy
+    /// encoding.
+    fn kitty_key_sequence(
+        &mut self,
+        input: KeyboardInput,
+        event_type: KeyEventType,
+    ) -> Option<String> {
+        let mode = *self.ctx.terminal().mode();
+        if !mode.intersects(TermMode::KITTY_KEYBOARD_PROTOCOL)
+            || mode.contains(TermMode::VI)
+            || self.ctx.search_active()
+        {
+            return None;
+        }
+
+        let mods = *self.ctx.modifiers();
+        kitty_key_sequence(mode, input.virtual_keycode?, mods, event_type)
+    }
+
     /// Attempt to find a binding and execute its action.
     ///
     /// The provided mode, mods, and key must match what is allowed by a binding
@@ -910,8 +1111,7 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
             + size.cell_height() as usize * (size.screen_lines() + search_height);
 
         let mouse = self.ctx.mouse();
-        let display_offset = self.ctx.terminal().grid().display_offset();
-        let point = self.ctx.mouse().point(&self.ctx.size_info(), display_offset);
+        let point = self.ctx.mouse().viewport_point(&size);
 
         if self.ctx.message().is_none() || (mouse.y <= terminal_end) {
             None
@@ -926,8 +1126,7 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
 
     /// Icon state of the cursor.
     fn cursor_state(&mut self) -> CursorIcon {
-        let display_offset = self.ctx.terminal().grid().display_offset();
-        let point = self.ctx.mouse().point(&self.ctx.size_info(), display_offset);
+        let point = self.ctx.mouse().point(&self.ctx.size_info(), self.ctx.terminal());
 
         // Function to check if mouse is on top of a hint.
         let hint_highlighted = |hint: &HintMatch| hint.bounds.contains(&point);
@@ -936,7 +1135,11 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
             mouse_state
         } else if self.ctx.display().highlighted_hint.as_ref().map_or(false, hint_highlighted) {
             CursorIcon::Hand
-        } else if !self.ctx.modifiers().shift() && self.ctx.mouse_mode() {
+        } else if self.ctx.modifiers().shift() {
+            CursorIcon::Text
+        } else if let Some(mouse_state) = self.ctx.window().terminal_mouse_cursor() {
+            mouse_state
+        } else if self.ctx.mouse_mode() {
             CursorIcon::Default
         } else {
             CursorIcon::Text
@@ -987,12 +1190,262 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
     }
 }
 
+/// Type of a key event reported through the kitty keyboard protocol.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum KeyEventType {
+    Press = 1,
+    Repeat = 2,
+    Release = 3,
+}
+
+/// Encode a key using the kitty keyboard protocol.
+///
+/// Keys which do not need to be disambiguated in the active mode will return `None`, since
+/// they should be sent using their legacy encoding.
+fn kitty_key_sequence(
+    mode: TermMode,
+    key: VirtualKeyCode,
+    mods: ModifiersState,
+    event_type: KeyEventType,
+) -> Option<String> {
+    let event_type = match event_type {
+        KeyEventType::Release if !mode.contains(TermMode::REPORT_EVENT_TYPES) => return None,
+        _ if !mode.contains(TermMode::REPORT_EVENT_TYPES) => KeyEventType::Press,
+        event_type => event_type,
+    };
+
+    let (code, terminator) = kitty_key_code(key)?;
+
+    let is_control = matches!(code, 9 | 13 | 127);
+    let is_keypad_text = matches!(code, 57399..=57413 | 57415 | 57416);
+    let is_text =
+        terminator == 'u' && code != 27 && !is_control && (code < 57344 || is_keypad_text);
+    let is_modifier = matches!(code, 57358..=57360 | 57441..=57450);
+
+    if !mode.contains(TermMode::REPORT_ALL_KEYS_AS_ESC) {
+        let legacy_text = is_text && !(mods.ctrl() || mods.alt() || mods.logo());
+        let legacy_control = is_control && (mods.is_empty() || event_type == KeyEventType::Release);
+        let legacy_functional =
+            terminator != 'u' && mods.is_empty() && event_type != KeyEventType::Release;
+
+        if is_modifier || legacy_text || legacy_control || legacy_functional {
+            return None;
+        }
+    }
+
+    let mut modifiers = 1;
+    if mods.shift() {
+        modifiers += 1;
+    }
+    if mods.alt() {
+        modifiers += 2;
+    }
+    if mods.ctrl() {
+        modifiers += 4;
+    }
+    if mods.logo() {
+        modifiers += 8;
+    }
+
+    let mut sequence = String::from("\x1b[");
+
+    let has_modifiers = modifiers != 1 || event_type != KeyEventType::Press;
+    if terminator == 'u' || terminator == '~' || has_modifiers {
+        sequence.push_str(&code.to_string());
+    }
+
+    if mode.contains(TermMode::REPORT_ALTERNATE_KEYS) && mods.shift() {
+        if let Some(shifted) = shifted_key_code(code) {
+            sequence.push_str(&format!(":{}", shifted));
+        }
+    }
+
+    if has_modifiers {
+        sequence.push_str(&format!(";{}", modifiers));
+
+        if event_type != KeyEventType::Press {
+            sequence.push_str(&format!(":{}", event_type as u8));
+        }
+    }
+
+    sequence.push(terminator);
+
+    Some(sequence)
+}
+
+/// Kitty keyboard protocol key code and the terminator used to report it.
+fn kitty_key_code(key: VirtualKeyCode) -> Option<(u32, char)> {
+    let key_code = match key {
+        VirtualKeyCode::A => 'a' as u32,
+        VirtualKeyCode::B => 'b' as u32,
+        VirtualKeyCode::C => 'c' as u32,
+        VirtualKeyCode::D => 'd' as u32,
+        VirtualKeyCode::E => 'e' as u32,
+        VirtualKeyCode::F => 'f' as u32,
+        VirtualKeyCode::G => 'g' as u32,
+        VirtualKeyCode::H => 'h' as u32,
+        VirtualKeyCode::I => 'i' as u32,
+        VirtualKeyCode::J => 'j' as u32,
+        VirtualKeyCode::K => 'k' as u32,
+        VirtualKeyCode::L => 'l' as u32,
+        VirtualKeyCode::M => 'm' as u32,
+        VirtualKeyCode::N => 'n' as u32,
+        VirtualKeyCode::O => 'o' as u32,
+        VirtualKeyCode::P => 'p' as u32,
+        VirtualKeyCode::Q => 'q' as u32,
+        VirtualKeyCode::R => 'r' as u32,
+        VirtualKeyCode::S => 's' as u32,
+        VirtualKeyCode::T => 't' as u32,
+        VirtualKeyCode::U => 'u' as u32,
+        VirtualKeyCode::V => 'v' as u32,
+        VirtualKeyCode::W => 'w' as u32,
+        VirtualKeyCode::X => 'x' as u32,
+        VirtualKeyCode::Y => 'y' as u32,
+        VirtualKeyCode::Z => 'z' as u32,
+        VirtualKeyCode::Key0 => '0' as u32,
+        VirtualKeyCode::Key1 => '1' as u32,
+        VirtualKeyCode::Key2 => '2' as u32,
+        VirtualKeyCode::Key3 => '3' as u32,
+        VirtualKeyCode::Key4 => '4' as u32,
+        VirtualKeyCode::Key5 => '5' as u32,
+        VirtualKeyCode::Key6 => '6' as u32,
+        VirtualKeyCode::Key7 => '7' as u32,
+        VirtualKeyCode::Key8 => '8' as u32,
+        VirtualKeyCode::Key9 => '9' as u32,
+        VirtualKeyCode::Space => ' ' as u32,
+        VirtualKeyCode::Apostrophe => '\'' as u32,
+        VirtualKeyCode::Asterisk => '*' as u32,
+        VirtualKeyCode::At => '@' as u32,
+        VirtualKeyCode::Backslash => '\\' as u32,
+        VirtualKeyCode::Caret => '^' as u32,
+        VirtualKeyCode::Colon => ':' as u32,
+        VirtualKeyCode::Comma => ',' as u32,
+        VirtualKeyCode::Equals => '=' as u32,
+        VirtualKeyCode::Grave => '`' as u32,
+        VirtualKeyCode::LBracket => '[' as u32,
+        VirtualKeyCode::Minus => '-' as u32,
+        VirtualKeyCode::Period => '.' as u32,
+        VirtualKeyCode::Plus => '+' as u32,
+        VirtualKeyCode::RBracket => ']' as u32,
+        VirtualKeyCode::Semicolon => ';' as u32,
+        VirtualKeyCode::Slash => '/' as u32,
+        VirtualKeyCode::Underline => '_' as u32,
+        VirtualKeyCode::Escape => 27,
+        VirtualKeyCode::Return => 13,
+        VirtualKeyCode::Tab => 9,
+        VirtualKeyCode::Back => 127,
+        VirtualKeyCode::Insert => return Some((2, '~')),
+        VirtualKeyCode::Delete => return Some((3, '~')),
+        VirtualKeyCode::PageUp => return Some((5, '~')),
+        VirtualKeyCode::PageDown => return Some((6, '~')),
+        VirtualKeyCode::Up => return Some((1, 'A')),
+        VirtualKeyCode::Down => return Some((1, 'B')),
+        VirtualKeyCode::Right => return Some((1, 'C')),
+        VirtualKeyCode::Left => return Some((1, 'D')),
+        VirtualKeyCode::End => return Some((1, 'F')),
+        VirtualKeyCode::Home => return Some((1, 'H')),
+        VirtualKeyCode::F1 => return Some((1, 'P')),
+        VirtualKeyCode::F2 => return Some((1, 'Q')),
+        VirtualKeyCode::F3 => return Some((13, '~')),
+        VirtualKeyCode::F4 => return Some((1, 'S')),
+        VirtualKeyCode::F5 => return Some((15, '~')),
+        VirtualKeyCode::F6 => return Some((17, '~')),
+        VirtualKeyCode::F7 => return Some((18, '~')),
+        VirtualKeyCode::F8 => return Some((19, '~')),
+        VirtualKeyCode::F9 => return Some((20, '~')),
+        VirtualKeyCode::F10 => return Some((21, '~')),
+        VirtualKeyCode::F11 => return Some((23, '~')),
+        VirtualKeyCode::F12 => return Some((24, '~')),
+        VirtualKeyCode::Capital => 57358,
+        VirtualKeyCode::Scroll => 57359,
+        VirtualKeyCode::Numlock => 57360,
+        VirtualKeyCode::Snapshot => 57361,
+        VirtualKeyCode::Pause => 57362,
+        VirtualKeyCode::Apps => 57363,
+        VirtualKeyCode::F13 => 57376,
+        VirtualKeyCode::F14 => 57377,
+        VirtualKeyCode::F15 => 57378,
+        VirtualKeyCode::F16 => 57379,
+        VirtualKeyCode::F17 => 57380,
+        VirtualKeyCode::F18 => 57381,
+        VirtualKeyCode::F19 => 57382,
+        VirtualKeyCode::F20 => 57383,
+        VirtualKeyCode::F21 => 57384,
+        VirtualKeyCode::F22 => 57385,
+        VirtualKeyCode::F23 => 57386,
+        VirtualKeyCode::F24 => 57387,
+        VirtualKeyCode::Numpad0 => 57399,
+        VirtualKeyCode::Numpad1 => 57400,
+        VirtualKeyCode::Numpad2 => 57401,
+        VirtualKeyCode::Numpad3 => 57402,
+        VirtualKeyCode::Numpad4 => 57403,
+        VirtualKeyCode::Numpad5 => 57404,
+        VirtualKeyCode::Numpad6 => 57405,
+        VirtualKeyCode::Numpad7 => 57406,
+        VirtualKeyCode::Numpad8 => 57407,
+        VirtualKeyCode::Numpad9 => 57408,
+        VirtualKeyCode::NumpadDecimal => 57409,
+        VirtualKeyCode::NumpadDivide => 57410,
+        VirtualKeyCode::NumpadMultiply => 57411,
+        VirtualKeyCode::NumpadSubtract => 57412,
+        VirtualKeyCode::NumpadAdd => 57413,
+        VirtualKeyCode::NumpadEnter => 57414,
+        VirtualKeyCode::NumpadEquals => 57415,
+        VirtualKeyCode::NumpadComma => 57416,
+        VirtualKeyCode::PlayPause => 57430,
+        VirtualKeyCode::MediaStop => 57432,
+        VirtualKeyCode::NextTrack => 57435,
+        VirtualKeyCode::PrevTrack => 57436,
+        VirtualKeyCode::VolumeDown => 57438,
+        VirtualKeyCode::VolumeUp => 57439,
+        VirtualKeyCode::Mute => 57440,
+        VirtualKeyCode::LShift => 57441,
+        VirtualKeyCode::LControl => 57442,
+        VirtualKeyCode::LAlt => 57443,
+        VirtualKeyCode::LWin => 57444,
+        VirtualKeyCode::RShift => 57447,
+        VirtualKeyCode::RControl => 57448,
+        VirtualKeyCode::RAlt => 57449,
+        VirtualKeyCode::RWin => 57450,
+        _ => return None,
+    };
+
+    Some((key_code, 'u'))
+}
+
+/// Key code of a text key with shift held.
+///
+/// The shifted key depends on the keyboard layout and the key is reported before the text it
+/// produces, so only letters are reported, since their shifted key is always the uppercase one.
+fn shifted_key_code(code: u32) -> Option<u32> {
+    match std::char::from_u32(code)? {
+        c @ 'a'..='z' => Some(c.to_ascii_uppercase() as u32),
+        _ => None,
+    }
+}
+
+/// Start of the text highlighted for highlight mouse tracking.
+fn highlight_start(highlight: &MouseHighlight) -> Point {
+    let line = Line(max(highlight.line, 1) as i32 - 1);
+    Point::new(highlight_line(highlight, line), Column(max(highlight.column, 1) - 1))
+}
+
+/// Restrict a line to the lines available for highlight mouse tracking.
+fn highlight_line(highlight: &MouseHighlight, line: Line) -> Line {
+    let first_line = Line(max(highlight.first_line, 1) as i32 - 1);
+    let last_line = Line(max(highlight.last_line, highlight.first_line) as i32 - 1);
+    max(min(line, last_line), first_line)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use std::cell::RefCell;
+
     use glutin::event::{Event as GlutinEvent, VirtualKeyCode, WindowEvent};
 
+    use alacritty_terminal::ansi::{Handler, KeyboardModes, LineSize};
     use alacritty_terminal::event::Event as TerminalEvent;
 
     use crate::config::Binding;
@@ -1011,11 +1464,18 @@ mod tests {
         pub message_buffer: &'a mut MessageBuffer,
         pub received_count: usize,
         pub suppress_chars: bool,
+        pub pressed_key: Option<u32>,
+        pub reported_keys: HashSet<u32>,
         pub modifiers: ModifiersState,
+        pub pty_writes: RefCell<Vec<u8>>,
         config: &'a Config,
     }
 
     impl<'a, T: EventListener> super::ActionContext<T> for ActionContext<'a, T> {
+        fn write_to_pty<B: Into<Cow<'static, [u8]>>>(&self, data: B) {
+            self.pty_writes.borrow_mut().extend_from_slice(&data.into());
+        }
+
         fn search_next(
             &mut self,
             _origin: Point,
@@ -1075,6 +1535,14 @@ mod tests {
             &mut self.suppress_chars
         }
 
+        fn pressed_key(&mut self) -> &mut Option<u32> {
+            &mut self.pressed_key
+        }
+
+        fn reported_keys(&mut self) -> &mut HashSet<u32> {
+            &mut self.reported_keys
+        }
+
         fn modifiers(&mut self) -> &mut ModifiersState {
             &mut self.modifiers
         }
@@ -1150,7 +1618,10 @@ mod tests {
                     clipboard: &mut clipboard,
                     received_count: 0,
                     suppress_chars: false,
+                    pressed_key: None,
+                    reported_keys: Default::default(),
                     modifiers: Default::default(),
+                    pty_writes: Default::default(),
                     message_buffer: &mut message_buffer,
                     config: &cfg,
                 };
@@ -1353,4 +1824,202 @@ mod tests {
         mode: BindingMode::empty(),
         mods: ModifiersState::ALT | ModifiersState::LOGO,
     }
+
+    #[test]
+    fn kitty_key_user_bindings() {
+        let mut clipboard = Clipboard::new_nop();
+        let mut cfg = Config::default();
+        let bindings = r#"key_bindings: [{ key: C, mods: Control, chars: "\x1b[custom" }]"#;
+        cfg.ui_config = serde_yaml::from_str(bindings).unwrap();
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0., 0., false);
+
+        let mut terminal = Term::new(&cfg, size, MockEventProxy);
+        terminal.push_keyboard_mode(KeyboardModes::REPORT_ALL_KEYS_AS_ESC);
+
+        let mut mouse = Mouse::default();
+        let mut message_buffer = MessageBuffer::new();
+
+        let context = ActionContext {
+            terminal: &mut terminal,
+            mouse: &mut mouse,
+            size_info: &size,
+            clipboard: &mut clipboard,
+            received_count: 0,
+            suppress_chars: false,
+            pressed_key: None,
+            reported_keys: Default::default(),
+            modifiers: ModifiersState::CTRL,
+            pty_writes: Default::default(),
+            message_buffer: &mut message_buffer,
+            config: &cfg,
+        };
+
+        let mut processor = Processor::new(context);
+
+        #[allow(deprecated)]
+        let mut input = KeyboardInput {
+            scancode: 46,
+            state: ElementState::Pressed,
+            virtual_keycode: Some(VirtualKeyCode::C),
+            modifiers: ModifiersState::CTRL,
+        };
+
+        // User bindings take precedence over the keyboard protocol.
+        processor.process_key_bindings(input, false);
+        assert_eq!(processor.ctx.pty_writes.take(), b"\x1b[custom");
+        assert!(processor.ctx.reported_keys.is_empty());
+
+        // Default bindings are replaced by the keyboard protocol.
+        processor.ctx.modifiers = ModifiersState::empty();
+        input.scancode = 59;
+        input.virtual_keycode = Some(VirtualKeyCode::F1);
+        processor.process_key_bindings(input, false);
+        assert_eq!(processor.ctx.pty_writes.take(), b"\x1b[P");
+        assert!(processor.ctx.reported_keys.contains(&59));
+    }
+
+    #[test]
+    fn mouse_point_double_width_line() {
+        let mut clipboard = Clipboard::new_nop();
+        let cfg = Config::default();
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0., 0., false);
+
+        let mut terminal = Term::new(&cfg, size, MockEventProxy);
+        terminal.set_line_size(LineSize::DoubleWidth);
+
+        let mut mouse = Mouse { x: 10, y: 1, ..Mouse::default() };
+        let mut message_buffer = MessageBuffer::new();
+
+        let context = ActionContext {
+            terminal: &mut terminal,
+            mouse: &mut mouse,
+            size_info: &size,
+            clipboard: &mut clipboard,
+            received_count: 0,
+            suppress_chars: false,
+            pressed_key: None,
+            reported_keys: Default::default(),
+            modifiers: Default::default(),
+            pty_writes: Default::default(),
+            message_buffer: &mut message_buffer,
+            config: &cfg,
+        };
+
+        let mut processor = Processor::new(context);
+
+        // Cells on double-width lines span two viewport columns.
+        let point = processor.ctx.mouse.point(&size, processor.ctx.terminal);
+        assert_eq!(point, Point::new(Line(0), Column(1)));
+        assert_eq!(processor.cell_side(10, true), Side::Right);
+
+        // Normal lines map viewport columns directly.
+        processor.ctx.mouse.y = 4;
+        let point = processor.ctx.mouse.point(&size, processor.ctx.terminal);
+        assert_eq!(point, Point::new(Line(1), Column(3)));
+        assert_eq!(processor.cell_side(10, false), Side::Left);
+    }
+
+    #[test]
+    fn kitty_key_disambiguate() {
+        let mode = TermMode::DISAMBIGUATE_ESC_CODES;
+        let press = KeyEventType::Press;
+
+        // Plain text and legacy keys are left to the legacy encoding.
+        assert_eq!(
+            kitty_key_sequence(mode, VirtualKeyCode::A, ModifiersState::empty(), press),
+            None
+        );
+        assert_eq!(kitty_key_sequence(mode, VirtualKeyCode::A, ModifiersState::SHIFT, press), None);
+        assert_eq!(
+            kitty_key_sequence(mode, VirtualKeyCode::Return, ModifiersState::empty(), press),
+            None
+        );
+        assert_eq!(
+            kitty_key_sequence(mode, VirtualKeyCode::Up, ModifiersState::empty(), press),
+            None
+        );
+
+        let seq = kitty_key_sequence(mode, VirtualKeyCode::Escape, ModifiersState::empty(), press);
+        assert_eq!(seq.as_deref(), Some("\x1b[27u"));
+
+        let seq = kitty_key_sequence(mode, VirtualKeyCode::C, ModifiersState::CTRL, press);
+        assert_eq!(seq.as_deref(), Some("\x1b[99;5u"));
+
+        let mods = ModifiersState::CTRL | ModifiersState::SHIFT;
+        let seq = kitty_key_sequence(mode, VirtualKeyCode::Up, mods, press);
+        assert_eq!(seq.as_deref(), Some("\x1b[1;6A"));
+
+        let seq = kitty_key_sequence(mode, VirtualKeyCode::F5, ModifiersState::ALT, press);
+        assert_eq!(seq.as_deref(), Some("\x1b[15;3~"));
+
+        // Keypad keys producing text are sent as text.
+        assert_eq!(
+            kitty_key_sequence(mode, VirtualKeyCode::Numpad5, ModifiersState::empty(), press),
+            None
+        );
+        assert_eq!(
+            kitty_key_sequence(mode, VirtualKeyCode::NumpadAdd, ModifiersState::empty(), press),
+            None
+        );
+
+        let seq =
+            kitty_key_sequence(mode, VirtualKeyCode::NumpadEnter, ModifiersState::empty(), press);
+        assert_eq!(seq.as_deref(), Some("\x1b[57414u"));
+    }
+
+    #[test]
+    fn kitty_key_report_all() {
+        let mode = TermMode::REPORT_ALL_KEYS_AS_ESC
+            | TermMode::REPORT_EVENT_TYPES
+            | TermMode::REPORT_ALTERNATE_KEYS;
+
+        let seq = kitty_key_sequence(
+            mode,
+            VirtualKeyCode::A,
+            ModifiersState::empty(),
+            KeyEventType::Press,
+        );
+        assert_eq!(seq.as_deref(), Some("\x1b[97u"));
+
+        let seq = kitty_key_sequence(
+            mode,
+            VirtualKeyCode::A,
+            ModifiersState::SHIFT,
+            KeyEventType::Repeat,
+        );
+        assert_eq!(seq.as_deref(), Some("\x1b[97:65;2:2u"));
+
+        // Shifted keys which depend on the keyboard layout are omitted.
+        let seq = kitty_key_sequence(
+            mode,
+            VirtualKeyCode::Key7,
+            ModifiersState::SHIFT,
+            KeyEventType::Press,
+        );
+        assert_eq!(seq.as_deref(), Some("\x1b[55;2u"));
+
+        let seq = kitty_key_sequence(
+            mode,
+            VirtualKeyCode::Up,
+            ModifiersState::empty(),
+            KeyEventType::Release,
+        );
+        assert_eq!(seq.as_deref(), Some("\x1b[1;1:3A"));
+
+        let seq = kitty_key_sequence(
+            mode,
+            VirtualKeyCode::LShift,
+            ModifiersState::SHIFT,
+            KeyEventType::Press,
+        );
+        assert_eq!(seq.as_deref(), Some("\x1b[57441;2u"));
+
+        let seq = kitty_key_sequence(
+            mode,
+            VirtualKeyCode::Numpad5,
+            ModifiersState::empty(),
+            KeyEventType::Press,
+        );
+        assert_eq!(seq.as_deref(), Some("\x1b[57404u"));
+    }
 }
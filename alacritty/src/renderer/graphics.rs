@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+use std::mem;
+
+use alacritty_terminal::graphics::{GraphicData, GraphicId, UpdateQueues};
+use alacritty_terminal::term::SizeInfo;
+
+use crate::display::content::RenderableCell;
+use crate::gl;
+use crate::gl::types::*;
+use crate::renderer;
+
+/// Shader sources for graphics drawing program.
+static GRAPHICS_SHADER_F: &str = include_str!("../../res/graphics.f.glsl");
+static GRAPHICS_SHADER_V: &str = include_str!("../../res/graphics.v.glsl");
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    // Normalized screen coordinates.
+    x: f32,
+    y: f32,
+
+    // Texture coordinates.
+    u: f32,
+    v: f32,
+}
+
+/// Renderer for the graphics stored in grid cells.
+#[derive(Debug)]
+pub struct GraphicsRenderer {
+    // GL buffer objects.
+    vao: GLuint,
+    vbo: GLuint,
+
+    program: GraphicsShaderProgram,
+
+    /// Uploaded textures of all graphics in the terminal.
+    textures: HashMap<GraphicId, GLuint>,
+
+    vertices: Vec<Vertex>,
+}
+
+impl GraphicsRenderer {
+    pub fn new() -> Result<Self, renderer::Error> {
+        let mut vao: GLuint = 0;
+        let mut vbo: GLuint = 0;
+        let program = GraphicsShaderProgram::new()?;
+
+        unsafe {
+            // Allocate buffers.
+            gl::GenVertexArrays(1, &mut vao);
+            gl::GenBuffers(1, &mut vbo);
+
+            gl::BindVertexArray(vao);
+
+            // VBO binding is not part of VAO itself, but VBO binding is stored in attributes.
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+
+            // Position.
+            gl::VertexAttribPointer(
+                0,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                mem::size_of::<Vertex>() as i32,
+                0 as *const _,
+            );
+            gl::EnableVertexAttribArray(0);
+
+            // Texture coordinates.
+            gl::VertexAttribPointer(
+                1,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                mem::size_of::<Vertex>() as i32,
+                (mem::size_of::<f32>() * 2) as *const _,
+            );
+            gl::EnableVertexAttribArray(1);
+
+            // Reset buffer bindings.
+            gl::BindVertexArray(0);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        }
+
+        Ok(Self { vao, vbo, program, textures: HashMap::new(), vertices: Vec::new() })
+    }
+
+    /// Upload new graphics and delete the textures which are no longer used.
+    pub fn run_updates(&mut self, queues: UpdateQueues) {
+        for graphic in queues.pending {
+            let texture = upload_texture(&graphic);
+            self.textures.insert(graphic.id, texture);
+        }
+
+        for id in queues.remove_queue {
+            if let Some(texture) = self.textures.remove(&id) {
+                unsafe {
+                    gl::DeleteTextures(1, &texture);
+                }
+            }
+        }
+    }
+
+    /// Draw the graphic tiles of all cells.
+    pub fn draw(&mut self, size_info: &SizeInfo, cells: &[RenderableCell]) {
+        let mut tiles: Vec<_> =
+            cells.iter().filter_map(|cell| Some((cell.point, cell.graphic.as_ref()?))).collect();
+        if tiles.is_empty() {
+            return;
+        }
+
+        // Group tiles by their texture to minimize draw calls.
+        tiles.sort_by_key(|(_, graphic)| graphic.texture.id);
+
+        let half_width = size_info.width() / 2.;
+        let half_height = size_info.height() / 2.;
+        let cell_width = size_info.cell_width();
+        let cell_height = size_info.cell_height();
+
+        // Build tile vertices, remembering the vertex range of every texture.
+        let mut batches: Vec<(GLuint, usize, usize)> = Vec::new();
+        self.vertices.clear();
+        for (point, graphic) in tiles {
+            let texture = match self.textures.get(&graphic.texture.id) {
+                Some(texture) => *texture,
+                None => continue,
+            };
+
+            let start = self.vertices.len();
+            match batches.last_mut() {
+                Some((last_texture, _, len)) if *last_texture == texture => *len += 6,
+                _ => batches.push((texture, start, 6)),
+            }
+
+            // Calculate tile vertices positions in normalized device coordinates.
+            // NDC range from -1 to +1, with Y pointing up.
+            let x = size_info.padding_x() + point.column.0 as f32 * cell_width;
+            let y = size_info.padding_y() + point.line as f32 * cell_height;
+            let x = x / half_width - 1.0;
+            let y = -y / half_height + 1.0;
+            let width = cell_width / half_width;
+            let height = cell_height / half_height;
+
+            // Map the cell to its part of the graphic, scaled to the current cell size.
+            let texture = &graphic.texture;
+            let u = f32::from(graphic.offset_x) / texture.width as f32;
+            let v = f32::from(graphic.offset_y) / texture.height as f32;
+            let u_end = u + f32::from(graphic.width) / texture.width as f32;
+            let v_end = v + f32::from(graphic.height) / texture.height as f32;
+
+            let quad = [
+                Vertex { x, y, u, v },
+                Vertex { x, y: y - height, u, v: v_end },
+                Vertex { x: x + width, y, u: u_end, v },
+                Vertex { x: x + width, y: y - height, u: u_end, v: v_end },
+            ];
+
+            // Append the vertices to form two triangles.
+            self.vertices.push(quad[0]);
+            self.vertices.push(quad[1]);
+            self.vertices.push(quad[2]);
+            self.vertices.push(quad[2]);
+            self.vertices.push(quad[3]);
+            self.vertices.push(quad[1]);
+        }
+
+        unsafe {
+            // Bind VAO to enable vertex attribute slots.
+            gl::BindVertexArray(self.vao);
+
+            // Bind VBO only once for buffer data upload only.
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+
+            gl::UseProgram(self.program.id);
+            gl::ActiveTexture(gl::TEXTURE0);
+
+            // Upload accumulated vertices.
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (self.vertices.len() * mem::size_of::<Vertex>()) as isize,
+                self.vertices.as_ptr() as *const _,
+                gl::STREAM_DRAW,
+            );
+
+            // Draw the tiles of every texture.
+            for (texture, start, len) in batches {
+                gl::BindTexture(gl::TEXTURE_2D, texture);
+                gl::DrawArrays(gl::TRIANGLES, start as i32, len as i32);
+            }
+
+            // Disable program.
+            gl::UseProgram(0);
+
+            // Reset buffer bindings to nothing.
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            gl::BindVertexArray(0);
+        }
+    }
+}
+
+impl Drop for GraphicsRenderer {
+    fn drop(&mut self) {
+        unsafe {
+            for texture in self.textures.values() {
+                gl::DeleteTextures(1, texture);
+            }
+
+            gl::DeleteBuffers(1, &self.vbo);
+            gl::DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
+
+/// Upload a graphic to a new texture.
+fn upload_texture(graphic: &GraphicData) -> GLuint {
+    let mut texture: GLuint = 0;
+
+    unsafe {
+        gl::GenTextures(1, &mut texture);
+        gl::BindTexture(gl::TEXTURE_2D, texture);
+
+        gl::TexImage2D(
+            gl::TEXTURE_2D,
+            0,
+            gl::RGBA as i32,
+            graphic.width as i32,
+            graphic.height as i32,
+            0,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            graphic.pixels.as_ptr() as *const _,
+        );
+
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+
+        gl::BindTexture(gl::TEXTURE_2D, 0);
+    }
+
+    texture
+}
+
+/// Graphics drawing program.
+#[derive(Debug)]
+pub struct GraphicsShaderProgram {
+    /// Program id.
+    id: GLuint,
+}
+
+impl GraphicsShaderProgram {
+    pub fn new() -> Result<Self, renderer::ShaderCreationError> {
+        let vertex_shader = renderer::create_shader(gl::VERTEX_SHADER, GRAPHICS_SHADER_V)?;
+        let fragment_shader = renderer::create_shader(gl::FRAGMENT_SHADER, GRAPHICS_SHADER_F)?;
+        let program = renderer::create_program(vertex_shader, fragment_shader)?;
+
+        unsafe {
+            gl::DeleteShader(fragment_shader);
+            gl::DeleteShader(vertex_shader);
+        }
+
+        Ok(Self { id: program })
+    }
+}
+
+impl Drop for GraphicsShaderProgram {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.id);
+        }
+    }
+}
@@ -14,6 +14,8 @@ use fnv::FnvHasher;
 use log::{error, info};
 use unicode_width::UnicodeWidthChar;
 
+use alacritty_terminal::ansi::LineSize;
+use alacritty_terminal::graphics::UpdateQueues;
 use alacritty_terminal::index::Point;
 use alacritty_terminal::term::cell::Flags;
 use alacritty_terminal::term::color::Rgb;
@@ -24,8 +26,10 @@ use crate::config::ui_config::{Delta, UiConfig};
 use crate::display::content::RenderableCell;
 use crate::gl;
 use crate::gl::types::*;
+use crate::renderer::graphics::GraphicsRenderer;
 use crate::renderer::rects::{RectRenderer, RenderRect};
 
+pub mod graphics;
 pub mod rects;
 
 // Shader source.
@@ -384,8 +388,13 @@ impl GlyphCache {
 bitflags! {
     #[repr(C)]
     struct RenderingGlyphFlags: u8 {
-        const WIDE_CHAR = 0b0000_0001;
-        const COLORED   = 0b0000_0010;
+        const WIDE_CHAR             = 0b0000_0001;
+        const COLORED               = 0b0000_0010;
+        const DOUBLE_WIDTH          = 0b0000_0100;
+        const DOUBLE_HEIGHT_TOP     = 0b0000_1000;
+        const DOUBLE_HEIGHT_BOTTOM  = 0b0001_0000;
+        const SUPERSCRIPT           = 0b0010_0000;
+        const SUBSCRIPT             = 0b0100_0000;
     }
 }
 
@@ -439,6 +448,7 @@ pub struct QuadRenderer {
     batch: Batch,
 
     rect_renderer: RectRenderer,
+    graphics_renderer: GraphicsRenderer,
 }
 
 #[derive(Debug)]
@@ -478,6 +488,17 @@ impl Batch {
         let mut cell_flags = RenderingGlyphFlags::empty();
         cell_flags.set(RenderingGlyphFlags::COLORED, glyph.multicolor);
         cell_flags.set(RenderingGlyphFlags::WIDE_CHAR, cell.flags.contains(Flags::WIDE_CHAR));
+        cell_flags.set(RenderingGlyphFlags::DOUBLE_WIDTH, cell.line_size.is_double_width());
+        cell_flags.set(
+            RenderingGlyphFlags::DOUBLE_HEIGHT_TOP,
+            cell.line_size == LineSize::DoubleHeightTop,
+        );
+        cell_flags.set(
+            RenderingGlyphFlags::DOUBLE_HEIGHT_BOTTOM,
+            cell.line_size == LineSize::DoubleHeightBottom,
+        );
+        cell_flags.set(RenderingGlyphFlags::SUPERSCRIPT, cell.flags.contains(Flags::SUPERSCRIPT));
+        cell_flags.set(RenderingGlyphFlags::SUBSCRIPT, cell.flags.contains(Flags::SUBSCRIPT));
 
         self.instances.push(InstanceData {
             col: cell.point.column.0 as u16,
@@ -638,6 +659,7 @@ impl QuadRenderer {
         let mut renderer = Self {
             program,
             rect_renderer: RectRenderer::new()?,
+            graphics_renderer: GraphicsRenderer::new()?,
             vao,
             ebo,
             vbo_instance,
@@ -682,6 +704,46 @@ impl QuadRenderer {
         }
     }
 
+    /// Upload new graphics and delete unused graphic textures.
+    pub fn graphics_run_updates(&mut self, queues: UpdateQueues) {
+        self.graphics_renderer.run_updates(queues);
+
+        // Uploads change the bound texture.
+        self.active_tex = 0;
+    }
+
+    /// Draw the graphics attached to the cells, below the text.
+    pub fn draw_graphics(&mut self, size_info: &SizeInfo, cells: &[RenderableCell]) {
+        if cells.iter().all(|cell| cell.graphic.is_none()) {
+            return;
+        }
+
+        // Prepare graphics rendering state.
+        unsafe {
+            // Remove padding from viewport.
+            gl::Viewport(0, 0, size_info.width() as i32, size_info.height() as i32);
+            gl::BlendFuncSeparate(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA, gl::SRC_ALPHA, gl::ONE);
+        }
+
+        self.graphics_renderer.draw(size_info, cells);
+
+        // Graphic textures replaced the glyph atlas binding.
+        self.active_tex = 0;
+
+        // Activate regular state again.
+        unsafe {
+            // Reset blending strategy.
+            gl::BlendFunc(gl::SRC1_COLOR, gl::ONE_MINUS_SRC1_COLOR);
+
+            // Restore viewport with padding.
+            let padding_x = size_info.padding_x() as i32;
+            let padding_y = size_info.padding_y() as i32;
+            let width = size_info.width() as i32;
+            let height = size_info.height() as i32;
+            gl::Viewport(padding_x, padding_y, width - 2 * padding_x, height - 2 * padding_y);
+        }
+    }
+
     pub fn with_api<F, T>(&mut self, config: &UiConfig, props: &SizeInfo, func: F) -> T
     where
         F: FnOnce(RenderApi<'_>) -> T,
@@ -842,8 +904,12 @@ impl<'a> RenderApi<'a> {
                 point: Point::new(point.line, point.column + i),
                 character,
                 zerowidth: None,
+                hyperlink: None,
+                graphic: None,
                 flags: Flags::empty(),
+                line_size: LineSize::Normal,
                 bg_alpha: 1.0,
+                underline: fg,
                 fg,
                 bg,
             })
@@ -3,6 +3,7 @@ use std::mem;
 
 use crossfont::Metrics;
 
+use alacritty_terminal::ansi::LineSize;
 use alacritty_terminal::grid::Dimensions;
 use alacritty_terminal::index::{Column, Point};
 use alacritty_terminal::term::cell::Flags;
@@ -80,20 +81,89 @@ impl RenderLine {
 
                 (bottom_pos, metrics.underline_thickness)
             },
-            Flags::UNDERLINE => (metrics.underline_position, metrics.underline_thickness),
+            Flags::UNDERLINE
+            | Flags::UNDERCURL
+            | Flags::DOTTED_UNDERLINE
+            | Flags::DASHED_UNDERLINE => (metrics.underline_position, metrics.underline_thickness),
             Flags::STRIKEOUT => (metrics.strikeout_position, metrics.strikeout_thickness),
+            Flags::OVERLINE => {
+                // Position overlines at the top of the line.
+                let position = size.cell_height() + metrics.descent;
+                (position - metrics.underline_thickness / 2., metrics.underline_thickness)
+            },
             _ => unimplemented!("Invalid flag for cell line drawing specified"),
         };
 
-        rects.push(Self::create_rect(
-            size,
-            metrics.descent,
-            start,
-            end,
-            position,
-            thickness,
-            color,
-        ));
+        let rect = Self::create_rect(size, metrics.descent, start, end, position, thickness, color);
+
+        match flag {
+            Flags::UNDERCURL => Self::push_undercurl(rects, metrics, size, rect),
+            Flags::DOTTED_UNDERLINE => {
+                // Square dots, separated by gaps of the same width.
+                let dot = rect.height;
+                Self::push_dashes(rects, size, rect, 0., dot, 2. * dot);
+            },
+            Flags::DASHED_UNDERLINE => {
+                // One dash centered in every cell, covering half of its width.
+                let offset = (size.cell_width() / 4.).round();
+                let dash = (size.cell_width() / 2.).round();
+                Self::push_dashes(rects, size, rect, offset, dash, size.cell_width());
+            },
+            _ => rects.push(rect),
+        }
+    }
+
+    /// Split a line's rect into dashes.
+    ///
+    /// The dash pattern is aligned to the grid, so the dashes of adjacent lines line up.
+    fn push_dashes(
+        rects: &mut Vec<RenderRect>,
+        size: &SizeInfo,
+        rect: RenderRect,
+        offset: f32,
+        dash: f32,
+        period: f32,
+    ) {
+        let end = rect.x + rect.width;
+
+        // Start at the first dash which ends within the rect.
+        let line_offset = (rect.x - size.padding_x()) % period;
+        let mut x = rect.x - line_offset + offset;
+        if x + dash <= rect.x {
+            x += period;
+        }
+
+        while x < end {
+            let start = x.max(rect.x);
+            let width = (x + dash).min(end) - start;
+            rects.push(RenderRect::new(start, rect.y, width, rect.height, rect.color, rect.alpha));
+            x += period;
+        }
+    }
+
+    /// Approximate a wave below the baseline using one pixel wide rects.
+    ///
+    /// Each cell contains one period of the wave.
+    fn push_undercurl(
+        rects: &mut Vec<RenderRect>,
+        metrics: &Metrics,
+        size: &SizeInfo,
+        rect: RenderRect,
+    ) {
+        let amplitude = (metrics.descent.abs() / 2.).round().max(2.);
+
+        // Keep the wave within the bottom of the line.
+        let line = ((rect.y - size.padding_y()) / size.cell_height()).floor();
+        let line_bottom = (line + 1.) * size.cell_height() + size.padding_y();
+        let top = rect.y.min(line_bottom - rect.height - amplitude);
+
+        let width = rect.width.round() as usize;
+        for i in 0..width {
+            let x = rect.x + i as f32;
+            let phase = (x - size.padding_x()) / size.cell_width() * 2. * std::f32::consts::PI;
+            let y = top + (amplitude * (1. - phase.cos()) / 2.).round();
+            rects.push(RenderRect::new(x, y, 1., rect.height, rect.color, rect.alpha));
+        }
     }
 
     /// Create a line's rect at a position relative to the baseline.
@@ -133,7 +203,7 @@ impl RenderLine {
     }
 }
 
-/// Lines for underline and strikeout.
+/// Lines for underlines and strikeout.
 #[derive(Default)]
 pub struct RenderLines {
     inner: HashMap<Flags, Vec<RenderLine>>,
@@ -160,7 +230,11 @@ impl RenderLines {
     pub fn update(&mut self, cell: &RenderableCell) {
         self.update_flag(cell, Flags::UNDERLINE);
         self.update_flag(cell, Flags::DOUBLE_UNDERLINE);
+        self.update_flag(cell, Flags::UNDERCURL);
+        self.update_flag(cell, Flags::DOTTED_UNDERLINE);
+        self.update_flag(cell, Flags::DASHED_UNDERLINE);
         self.update_flag(cell, Flags::STRIKEOUT);
+        self.update_flag(cell, Flags::OVERLINE);
     }
 
     /// Update the lines for a specific flag.
@@ -169,15 +243,30 @@ impl RenderLines {
             return;
         }
 
+        // Double-height lines draw underlines on the bottom and overlines on the top half.
+        let skipped = match cell.line_size {
+            LineSize::DoubleHeightTop => Flags::ALL_UNDERLINES,
+            LineSize::DoubleHeightBottom => Flags::OVERLINE,
+            _ => Flags::empty(),
+        };
+        if flag.intersects(skipped) {
+            return;
+        }
+
         // Include wide char spacer if the current cell is a wide char.
-        let mut end = cell.point;
-        if cell.flags.contains(Flags::WIDE_CHAR) {
-            end.column += 1;
+        let mut width = if cell.flags.contains(Flags::WIDE_CHAR) { 2 } else { 1 };
+        if cell.line_size.is_double_width() {
+            width *= 2;
         }
+        let mut end = cell.point;
+        end.column += width - 1;
+
+        // Underlines can have a color separate from the foreground.
+        let color = if flag.intersects(Flags::ALL_UNDERLINES) { cell.underline } else { cell.fg };
 
         // Check if there's an active line.
         if let Some(line) = self.inner.get_mut(&flag).and_then(|lines| lines.last_mut()) {
-            if cell.fg == line.color
+            if color == line.color
                 && cell.point.column == line.end.column + 1
                 && cell.point.line == line.end.line
             {
@@ -188,7 +277,7 @@ impl RenderLines {
         }
 
         // Start new line if there currently is none.
-        let line = RenderLine { start: cell.point, end, color: cell.fg };
+        let line = RenderLine { start: cell.point, end, color };
         match self.inner.get_mut(&flag) {
             Some(lines) => lines.push(line),
             None => {
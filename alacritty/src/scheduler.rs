@@ -15,6 +15,7 @@ pub enum TimerId {
     SelectionScrolling,
     DelayedSearch,
     BlinkCursor,
+    BlinkText,
 }
 
 /// Event scheduled to be emitted at a specific time.
@@ -1,15 +1,26 @@
 //! ANSI Terminal Stream Parsing.
 
+use std::cmp::max;
 use std::convert::TryFrom;
+#[cfg(unix)]
+use std::ffi::OsString;
+#[cfg(unix)]
+use std::os::unix::ffi::OsStringExt;
+use std::path::PathBuf;
 use std::time::{Duration, Instant};
 use std::{iter, str};
 
+use bitflags::bitflags;
 use log::{debug, trace};
 use serde::{Deserialize, Serialize};
 use vte::{Params, ParamsIter};
 
 use alacritty_config_derive::ConfigDeserialize;
 
+use crate::graphics::kitty;
+#[cfg(feature = "sixel")]
+use crate::graphics::sixel;
+use crate::graphics::GraphicData;
 use crate::index::{Column, Line};
 use crate::term::color::Rgb;
 
@@ -28,6 +39,24 @@ const SYNC_START_ESCAPE_START: [u8; SYNC_ESCAPE_START_LEN] = [b'\x1b', b'P', b'=
 /// Start of the DCS sequence for terminating synchronized updates.
 const SYNC_END_ESCAPE_START: [u8; SYNC_ESCAPE_START_LEN] = [b'\x1b', b'P', b'=', b'2', b's'];
 
+/// Number of bytes in the synchronized update mode sequences.
+const SYNC_CSI_LEN: usize = 8;
+
+/// CSI sequence for beginning synchronized updates.
+const SYNC_START_CSI: [u8; SYNC_CSI_LEN] = *b"\x1b[?2026h";
+
+/// CSI sequence for terminating synchronized updates.
+const SYNC_END_CSI: [u8; SYNC_CSI_LEN] = *b"\x1b[?2026l";
+
+/// DECRQM sequence for querying the synchronized update mode.
+const SYNC_QUERY_CSI: [u8; SYNC_CSI_LEN + 1] = *b"\x1b[?2026$p";
+
+/// Maximum number of bytes collected for DCS queries.
+const MAX_DCS_QUERY_LEN: usize = 1024;
+
+/// Maximum number of bytes collected for APC strings (5MiB).
+const MAX_APC_LEN: usize = 0x50_0000;
+
 /// Parse colors in XParseColor format.
 fn xparse_color(color: &[u8]) -> Option<Rgb> {
     if !color.is_empty() && color[0] == b'#' {
@@ -78,6 +107,29 @@ fn parse_legacy_color(color: &[u8]) -> Option<Rgb> {
     })
 }
 
+/// Color index of a dynamic color, identified by its OSC code.
+fn dynamic_color_index(code: u8) -> Option<usize> {
+    let color = match code {
+        10 => NamedColor::Foreground,
+        11 => NamedColor::Background,
+        12 => NamedColor::Cursor,
+        17 => NamedColor::SelectionBackground,
+        19 => NamedColor::SelectionForeground,
+        _ => return None,
+    };
+    Some(color as usize)
+}
+
+/// Color index of a special color set through OSC 5.
+///
+/// Only the bold color is supported, which is used for the foreground of bold text.
+fn special_color_index(special: u8) -> Option<usize> {
+    match special {
+        0 => Some(NamedColor::BrightForeground as usize),
+        _ => None,
+    }
+}
+
 fn parse_number(input: &[u8]) -> Option<u8> {
     if input.is_empty() {
         return None;
@@ -97,6 +149,58 @@ fn parse_number(input: &[u8]) -> Option<u8> {
     Some(num)
 }
 
+/// Parse a `file://host/path` URI into its host and percent-decoded path.
+fn parse_file_uri(uri: &[u8]) -> Option<(&str, PathBuf)> {
+    if !uri.starts_with(b"file://") {
+        return None;
+    }
+
+    let uri = &uri[7..];
+    let path_start = uri.iter().position(|&b| b == b'/')?;
+    let host = str::from_utf8(&uri[..path_start]).ok()?;
+
+    let mut path = Vec::with_capacity(uri.len() - path_start);
+    let mut bytes = uri[path_start..].iter();
+    while let Some(&byte) = bytes.next() {
+        if byte == b'%' {
+            let hex = bytes.as_slice().get(..2).and_then(|hex| str::from_utf8(hex).ok());
+            if let Some(decoded) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                path.push(decoded);
+                bytes.nth(1);
+                continue;
+            }
+        }
+
+        path.push(byte);
+    }
+
+    #[cfg(unix)]
+    let path = PathBuf::from(OsString::from_vec(path));
+    #[cfg(not(unix))]
+    let path = PathBuf::from(String::from_utf8(path).ok()?);
+
+    Some((host, path))
+}
+
+/// Join OSC parameters which were split at semicolons.
+fn join_params(params: &[&[u8]]) -> String {
+    String::from_utf8_lossy(&params.join(&b';')).into_owned()
+}
+
+/// Parse a hex encoded string, as used by XTGETTCAP.
+fn parse_hex_string(hex: &[u8]) -> Option<String> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+
+    let bytes = hex
+        .chunks(2)
+        .map(|byte| u8::from_str_radix(str::from_utf8(byte).ok()?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+
+    String::from_utf8(bytes).ok()
+}
+
 /// Internal state for VTE processor.
 #[derive(Debug, Default)]
 struct ProcessorState {
@@ -106,8 +210,34 @@ struct ProcessorState {
     /// DCS sequence waiting for termination.
     dcs: Option<Dcs>,
 
+    /// Data received by a DCS query.
+    dcs_buffer: Vec<u8>,
+
     /// State for synchronized terminal updates.
     sync_state: SyncState,
+
+    /// State of the APC string parser.
+    apc_state: ApcState,
+
+    /// Data received in an APC string.
+    apc_buffer: Vec<u8>,
+}
+
+/// Progress of the APC string parser.
+///
+/// APC strings are ignored by the VTE parser, so they are collected separately.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum ApcState {
+    Ground,
+    Escape,
+    String,
+    StringEscape,
+}
+
+impl Default for ApcState {
+    fn default() -> Self {
+        ApcState::Ground
+    }
 }
 
 #[derive(Debug)]
@@ -136,6 +266,16 @@ enum Dcs {
 
     /// End of the synchronized update.
     SyncEnd,
+
+    /// DECRQSS - Request status string.
+    RequestStatusString,
+
+    /// XTGETTCAP - Request terminfo capabilities.
+    RequestTermcap,
+
+    /// Sixel graphic.
+    #[cfg(feature = "sixel")]
+    Sixel(Box<sixel::Parser>),
 }
 
 /// The processor wraps a `vte::Parser` to ultimately call methods on a Handler.
@@ -158,6 +298,7 @@ impl Processor {
         H: Handler,
     {
         if self.state.sync_state.timeout.is_none() {
+            self.advance_apc(handler, byte);
             let mut performer = Performer::new(&mut self.state, handler);
             self.parser.advance(&mut performer, byte);
         } else {
@@ -173,6 +314,7 @@ impl Processor {
         // Process all synchronized bytes.
         for i in 0..self.state.sync_state.buffer.len() {
             let byte = self.state.sync_state.buffer[i];
+            self.advance_apc(handler, byte);
             let mut performer = Performer::new(&mut self.state, handler);
             self.parser.advance(&mut performer, byte);
         }
@@ -194,6 +336,46 @@ impl Processor {
         self.state.sync_state.buffer.len()
     }
 
+    /// Collect APC strings.
+    #[inline]
+    fn advance_apc<H>(&mut self, handler: &mut H, byte: u8)
+    where
+        H: Handler,
+    {
+        let state = &mut self.state;
+        state.apc_state = match (state.apc_state, byte) {
+            (ApcState::Ground, 0x1b) => ApcState::Escape,
+            (ApcState::Ground, _) => return,
+            (ApcState::Escape, b'_') => {
+                state.apc_buffer.clear();
+                ApcState::String
+            },
+            (ApcState::Escape, 0x1b) => ApcState::Escape,
+            (ApcState::Escape, _) => ApcState::Ground,
+            (ApcState::String, 0x1b) => ApcState::StringEscape,
+            // Cancel the APC string.
+            (ApcState::String, 0x18) | (ApcState::String, 0x1a) => ApcState::Ground,
+            (ApcState::String, _) => {
+                // Keep one byte more than the limit to detect overflows.
+                if state.apc_buffer.len() <= MAX_APC_LEN {
+                    state.apc_buffer.push(byte);
+                }
+                ApcState::String
+            },
+            (ApcState::StringEscape, b'\\') => {
+                apc_dispatch(handler, &state.apc_buffer);
+                ApcState::Ground
+            },
+            // Any other escape aborts the APC string.
+            (ApcState::StringEscape, b'_') => {
+                state.apc_buffer.clear();
+                ApcState::String
+            },
+            (ApcState::StringEscape, 0x1b) => ApcState::Escape,
+            (ApcState::StringEscape, _) => ApcState::Ground,
+        };
+    }
+
     /// Process a new byte during a synchronized update.
     #[cold]
     fn advance_sync<H>(&mut self, handler: &mut H, byte: u8)
@@ -202,6 +384,11 @@ impl Processor {
     {
         self.state.sync_state.buffer.push(byte);
 
+        // Handle sync CSI escape sequences.
+        if self.advance_sync_csi(handler) {
+            return;
+        }
+
         // Handle sync DCS escape sequences.
         match self.state.sync_state.pending_dcs {
             Some(_) => self.advance_sync_dcs_end(handler, byte),
@@ -209,6 +396,37 @@ impl Processor {
         }
     }
 
+    /// Handle the synchronized update mode sequences.
+    ///
+    /// Only the exact `CSI ? 2026 h`, `CSI ? 2026 l` and `CSI ? 2026 $ p` sequences are
+    /// recognized, to avoid running the full parser on the synchronized bytes.
+    ///
+    /// Returns `true` if a sequence was handled.
+    fn advance_sync_csi<H>(&mut self, handler: &mut H) -> bool
+    where
+        H: Handler,
+    {
+        // Get the last few bytes for comparison.
+        let len = self.state.sync_state.buffer.len();
+        let offset = len.saturating_sub(SYNC_CSI_LEN);
+        let end = &self.state.sync_state.buffer[offset..];
+
+        if end == SYNC_START_CSI {
+            self.state.sync_state.timeout = Some(Instant::now() + SYNC_UPDATE_TIMEOUT);
+            true
+        } else if end == SYNC_END_CSI {
+            self.stop_sync(handler);
+            true
+        } else if self.state.sync_state.buffer.ends_with(&SYNC_QUERY_CSI) {
+            // Answer immediately, since the application might wait for it before ending the sync.
+            self.state.sync_state.buffer.truncate(len - SYNC_QUERY_CSI.len());
+            handler.report_sync_update(true);
+            true
+        } else {
+            false
+        }
+    }
+
     /// Find the start of sync DCS sequences.
     fn advance_sync_dcs_start(&mut self) {
         // Get the last few bytes for comparison.
@@ -240,12 +458,31 @@ impl Processor {
                     self.state.sync_state.timeout = Some(Instant::now() + SYNC_UPDATE_TIMEOUT);
                 },
                 Some(Dcs::SyncEnd) => self.stop_sync(handler),
-                None => (),
+                _ => (),
             },
         }
     }
 }
 
+/// Dispatch a complete APC string.
+fn apc_dispatch<H>(handler: &mut H, buffer: &[u8])
+where
+    H: Handler,
+{
+    if buffer.len() > MAX_APC_LEN {
+        debug!("[unhandled apc] APC string exceeds {} bytes", MAX_APC_LEN);
+        return;
+    }
+
+    match buffer.first() {
+        Some(b'G') => match kitty::Command::parse(&buffer[1..]) {
+            Some(command) => handler.kitty_graphics(command),
+            None => debug!("[unhandled apc] invalid graphics command"),
+        },
+        _ => debug!("[unhandled apc] {:?}", String::from_utf8_lossy(buffer)),
+    }
+}
+
 /// Helper type that implements `vte::Perform`.
 ///
 /// Processor creates a Performer when running advance and passes the Performer
@@ -301,9 +538,30 @@ pub trait Handler {
     /// Identify the terminal (should write back to the pty stream).
     fn identify_terminal(&mut self, _intermediate: Option<char>) {}
 
+    /// Report the terminal name and version (XTVERSION).
+    fn report_version(&mut self) {}
+
     /// Report device status.
     fn device_status(&mut self, _: usize) {}
 
+    /// DECRQM - Report the state of an ANSI or private mode.
+    fn report_mode(&mut self, _private: bool, _mode: u16) {}
+
+    /// DECRQM - Report the state of the synchronized update mode, which is tracked by the parser.
+    fn report_sync_update(&mut self, _active: bool) {}
+
+    /// DECRQSS - Report the state of a setting, `None` if the request is invalid.
+    fn report_status_string(&mut self, _: Option<StatusStringQuery>) {}
+
+    /// XTGETTCAP - Report a terminfo capability.
+    fn report_termcap(&mut self, _name: &str) {}
+
+    /// Insert a graphic at the cursor position.
+    fn insert_graphic(&mut self, _graphic: GraphicData) {}
+
+    /// Handle a kitty graphics protocol command.
+    fn kitty_graphics(&mut self, _command: kitty::Command) {}
+
     /// Move cursor forward `cols`.
     fn move_forward(&mut self, _: Column) {}
 
@@ -390,6 +648,9 @@ pub trait Handler {
     /// Reset terminal state.
     fn reset_state(&mut self) {}
 
+    /// Reset modes, attributes and margins, while keeping the screen content.
+    fn soft_reset(&mut self) {}
+
     /// Reverse Index.
     ///
     /// Move the active position to the same horizontal position on the
@@ -409,6 +670,13 @@ pub trait Handler {
     /// DECSTBM - Set the terminal scrolling region.
     fn set_scrolling_region(&mut self, _top: usize, _bottom: Option<usize>) {}
 
+    /// DECSLRM - Set the left and right margins.
+    ///
+    /// This saves the cursor position instead while left/right margin mode is disabled.
+    fn set_left_right_margins(&mut self, _left: usize, _right: Option<usize>) {
+        self.save_cursor_position();
+    }
+
     /// DECKPAM - Set keypad to applications mode (ESCape instead of digits).
     fn set_keypad_application_mode(&mut self) {}
 
@@ -421,6 +689,11 @@ pub trait Handler {
     /// shift out and locking shift depending on the set being activated.
     fn set_active_charset(&mut self, _: CharsetIndex) {}
 
+    /// Invoke G2 or G3 in the GL area for the next character only.
+    ///
+    /// Also referred to as single shift two and three (SS2/SS3).
+    fn set_single_shift_charset(&mut self, _: CharsetIndex) {}
+
     /// Assign a graphic character set to G0, G1, G2 or G3.
     ///
     /// 'Designate' a graphic character set as one of G0 to G3, so that it can
@@ -433,6 +706,9 @@ pub trait Handler {
     /// Write a foreground/background color escape sequence with the current color.
     fn dynamic_color_sequence(&mut self, _: u8, _: usize, _: &str) {}
 
+    /// Write a palette color escape sequence with the current color, starting with the `prefix`.
+    fn indexed_color_sequence(&mut self, _prefix: String, _index: usize, _terminator: &str) {}
+
     /// Reset an indexed color to original value.
     fn reset_color(&mut self, _: usize) {}
 
@@ -445,6 +721,27 @@ pub trait Handler {
     /// Run the decaln routine.
     fn decaln(&mut self) {}
 
+    /// Set the character size of the cursor line.
+    fn set_line_size(&mut self, _size: LineSize) {}
+
+    /// Copy a rectangular area, with its top-left corner moved to the destination.
+    fn copy_area(&mut self, _area: Rectangle, _top: usize, _left: usize) {}
+
+    /// Fill a rectangular area with a character.
+    fn fill_area(&mut self, _c: char, _area: Rectangle) {}
+
+    /// Erase a rectangular area.
+    fn erase_area(&mut self, _area: Rectangle) {}
+
+    /// Change the attributes of all cells in an area.
+    fn change_area_attributes(&mut self, _area: Rectangle, _attrs: &[Attr]) {}
+
+    /// Select whether attribute changes apply to a rectangle, or the stream of cells in between.
+    fn set_attribute_change_extent(&mut self, _rectangular: bool) {}
+
+    /// Report the checksum of a rectangular area.
+    fn report_area_checksum(&mut self, _id: u16, _area: Rectangle) {}
+
     /// Push a title onto the stack.
     fn push_title(&mut self) {}
 
@@ -456,6 +753,174 @@ pub trait Handler {
 
     /// Report text area size in characters.
     fn text_area_size_chars(&mut self) {}
+
+    /// Manipulate the window or report its state.
+    fn window_operation(&mut self, _: WindowOperation) {}
+
+    /// Start highlight mouse tracking, or abort it with `None`.
+    fn start_mouse_highlight(&mut self, _highlight: Option<MouseHighlight>) {}
+
+    /// Set hyperlink.
+    fn set_hyperlink(&mut self, _: Option<Hyperlink>) {}
+
+    /// Mark the start of a shell integration section at the cursor position.
+    fn prompt_mark(&mut self, _: PromptMark) {}
+
+    /// Set the working directory reported by the shell.
+    fn set_working_directory(&mut self, _host: &str, _path: PathBuf) {}
+
+    /// Show a desktop notification.
+    fn desktop_notification(&mut self, _title: Option<String>, _body: String) {}
+
+    /// Set the mouse cursor shape, `None` restores the default shape.
+    fn set_mouse_cursor_shape(&mut self, _shape: Option<String>) {}
+
+    /// Push keyboard protocol modes onto the stack.
+    fn push_keyboard_mode(&mut self, _: KeyboardModes) {}
+
+    /// Pop the given number of keyboard protocol modes from the stack.
+    fn pop_keyboard_modes(&mut self, _to_pop: u16) {}
+
+    /// Change the active keyboard protocol modes.
+    fn set_keyboard_mode(&mut self, _: KeyboardModes, _: KeyboardModesApplyBehavior) {}
+
+    /// Report the active keyboard protocol modes.
+    fn report_keyboard_mode(&mut self) {}
+}
+
+bitflags! {
+    /// Progressive enhancements of the kitty keyboard protocol.
+    pub struct KeyboardModes: u8 {
+        /// No enhancements, using the legacy key encoding.
+        const NO_MODE                 = 0b0000_0000;
+        /// Report keys which would otherwise be ambiguous as escape sequences.
+        const DISAMBIGUATE_ESC_CODES  = 0b0000_0001;
+        /// Report key repeat and release events.
+        const REPORT_EVENT_TYPES      = 0b0000_0010;
+        /// Report the shifted and base layout keys.
+        const REPORT_ALTERNATE_KEYS   = 0b0000_0100;
+        /// Report all keys as escape sequences, including text input.
+        const REPORT_ALL_KEYS_AS_ESC  = 0b0000_1000;
+        /// Report the text associated with a key.
+        const REPORT_ASSOCIATED_TEXT  = 0b0001_0000;
+    }
+}
+
+/// How new keyboard protocol modes are combined with the active ones.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum KeyboardModesApplyBehavior {
+    /// Replace the active modes.
+    Replace,
+    /// Add the modes to the active ones.
+    Union,
+    /// Remove the modes from the active ones.
+    Difference,
+}
+
+/// Setting requested through DECRQSS.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StatusStringQuery {
+    /// SGR - Character attributes.
+    GraphicRendition,
+    /// DECSCUSR - Cursor style.
+    CursorStyle,
+    /// DECSTBM - Scrolling region.
+    ScrollingRegion,
+    /// DECSLRM - Left and right margins.
+    LeftRightMargins,
+}
+
+/// Shell integration mark as received through OSC 133.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PromptMark {
+    /// Start of the prompt.
+    PromptStart,
+    /// Start of the command input.
+    CommandStart,
+    /// Start of the command output.
+    OutputStart,
+    /// End of the command.
+    CommandEnd,
+}
+
+/// Window manipulation or report requested through XTWINOPS.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WindowOperation {
+    /// Restore the window from its iconified state.
+    Deiconify,
+    /// Iconify the window.
+    Iconify,
+    /// Move the window to a position in pixels.
+    Move { x: i32, y: i32 },
+    /// Resize the text area in pixels, `None` keeps the current size.
+    ResizePixels { width: Option<usize>, height: Option<usize> },
+    /// Resize the text area in cells, `None` keeps the current size.
+    ResizeCells { columns: Option<usize>, lines: Option<usize> },
+    /// Maximize or restore the window.
+    Maximize(bool),
+    /// Enter or leave fullscreen.
+    Fullscreen(bool),
+    /// Toggle fullscreen.
+    ToggleFullscreen,
+    /// Report whether the window is iconified.
+    ReportState,
+    /// Report the window position in pixels.
+    ReportPosition,
+    /// Report the cell size in pixels.
+    ReportCellSize,
+    /// Report the screen size in cells.
+    ReportScreenSize,
+    /// Report the icon title.
+    ReportIconTitle,
+    /// Report the window title.
+    ReportTitle,
+}
+
+/// Region for highlight mouse tracking, sent by the application in response to a mouse press.
+///
+/// Coordinates are one-based, the highlighted text is restricted to the lines between `first_line`
+/// and `last_line`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MouseHighlight {
+    pub line: usize,
+    pub column: usize,
+    pub first_line: usize,
+    pub last_line: usize,
+}
+
+/// Area of the screen used by the rectangular area operations.
+///
+/// Coordinates are one-based and inclusive, a missing bottom or right edge extends the area to the
+/// end of the screen.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Rectangle {
+    pub top: usize,
+    pub left: usize,
+    pub bottom: Option<usize>,
+    pub right: Option<usize>,
+}
+
+impl Rectangle {
+    pub fn new(top: usize, left: usize, bottom: Option<usize>, right: Option<usize>) -> Self {
+        Self { top: max(top, 1), left: max(left, 1), bottom, right }
+    }
+
+    /// Create a rectangle from its escape parameters, using defaults for parameters set to `0`.
+    fn from_params(top: u16, left: u16, bottom: u16, right: u16) -> Self {
+        let bottom = Some(bottom as usize).filter(|&bottom| bottom != 0);
+        let right = Some(right as usize).filter(|&right| right != 0);
+        Self::new(top as usize, left as usize, bottom, right)
+    }
+}
+
+/// Hyperlink as received through OSC 8.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hyperlink {
+    /// Identifier used to group cells of the same hyperlink.
+    pub id: Option<String>,
+
+    /// Target of the hyperlink.
+    pub uri: String,
 }
 
 /// Terminal cursor configuration.
@@ -492,6 +957,36 @@ impl Default for CursorShape {
     }
 }
 
+/// Size of the characters on a line.
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Copy, Clone)]
+pub enum LineSize {
+    /// Single-width, single-height characters (DECSWL).
+    Normal,
+
+    /// Double-width, single-height characters (DECDWL).
+    DoubleWidth,
+
+    /// Top half of double-width, double-height characters (DECDHL).
+    DoubleHeightTop,
+
+    /// Bottom half of double-width, double-height characters (DECDHL).
+    DoubleHeightBottom,
+}
+
+impl LineSize {
+    /// Check if the characters on the line occupy two columns.
+    #[inline]
+    pub fn is_double_width(self) -> bool {
+        self != LineSize::Normal
+    }
+}
+
+impl Default for LineSize {
+    fn default() -> LineSize {
+        LineSize::Normal
+    }
+}
+
 /// Terminal modes.
 #[derive(Debug, Eq, PartialEq)]
 pub enum Mode {
@@ -529,8 +1024,12 @@ pub enum Mode {
     LineFeedNewLine = 20,
     /// ?25
     ShowCursor = 25,
+    /// ?69
+    LeftRightMargin = 69,
     /// ?1000
     ReportMouseClicks = 1000,
+    /// ?1001
+    ReportMouseHighlight = 1001,
     /// ?1002
     ReportCellMouseMotion = 1002,
     /// ?1003
@@ -543,12 +1042,20 @@ pub enum Mode {
     SgrMouse = 1006,
     /// ?1007
     AlternateScroll = 1007,
+    /// ?1015
+    UrxvtMouse = 1015,
+    /// ?1016
+    SgrPixelsMouse = 1016,
     /// ?1042
     UrgencyHints = 1042,
     /// ?1049
     SwapScreenAndSetRestoreCursor = 1049,
     /// ?2004
     BracketedPaste = 2004,
+    /// ?2026
+    SyncUpdate = 2026,
+    /// ?2027
+    GraphemeClustering = 2027,
 }
 
 impl Mode {
@@ -568,16 +1075,22 @@ impl Mode {
                 7 => Mode::LineWrap,
                 12 => Mode::BlinkingCursor,
                 25 => Mode::ShowCursor,
+                69 => Mode::LeftRightMargin,
                 1000 => Mode::ReportMouseClicks,
+                1001 => Mode::ReportMouseHighlight,
                 1002 => Mode::ReportCellMouseMotion,
                 1003 => Mode::ReportAllMouseMotion,
                 1004 => Mode::ReportFocusInOut,
                 1005 => Mode::Utf8Mouse,
                 1006 => Mode::SgrMouse,
                 1007 => Mode::AlternateScroll,
+                1015 => Mode::UrxvtMouse,
+                1016 => Mode::SgrPixelsMouse,
                 1042 => Mode::UrgencyHints,
                 1049 => Mode::SwapScreenAndSetRestoreCursor,
                 2004 => Mode::BracketedPaste,
+                2026 => Mode::SyncUpdate,
+                2027 => Mode::GraphemeClustering,
                 _ => {
                     trace!("[unimplemented] primitive mode: {}", num);
                     return None;
@@ -694,6 +1207,10 @@ pub enum NamedColor {
     BrightForeground,
     /// Dim foreground.
     DimForeground,
+    /// Foreground color of selected text.
+    SelectionForeground,
+    /// Background color of selected text.
+    SelectionBackground,
 }
 
 impl NamedColor {
@@ -768,6 +1285,12 @@ pub enum Attr {
     Underline,
     /// Underlined twice.
     DoubleUnderline,
+    /// Undercurled text.
+    Undercurl,
+    /// Dotted underlined text.
+    DottedUnderline,
+    /// Dashed underlined text.
+    DashedUnderline,
     /// Blink cursor slowly.
     BlinkSlow,
     /// Blink cursor fast.
@@ -794,10 +1317,22 @@ pub enum Attr {
     CancelHidden,
     /// Cancel strikeout.
     CancelStrike,
+    /// Overlined text.
+    Overline,
+    /// Cancel overline.
+    CancelOverline,
+    /// Superscript text.
+    Superscript,
+    /// Subscript text.
+    Subscript,
+    /// Cancel superscript and subscript.
+    CancelSuperscriptSubscript,
     /// Set indexed foreground color.
     Foreground(Color),
     /// Set indexed background color.
     Background(Color),
+    /// Set underline color, `None` uses the foreground color.
+    UnderlineColor(Option<Color>),
 }
 
 /// Identifiers which can be assigned to a graphic character set.
@@ -821,6 +1356,14 @@ impl Default for CharsetIndex {
 pub enum StandardCharset {
     Ascii,
     SpecialCharacterAndLineDrawing,
+    /// United Kingdom national character set.
+    British,
+    /// DEC Supplemental Graphic, the DEC multinational characters.
+    DecSupplemental,
+    /// DEC Technical, mathematical and Greek characters.
+    DecTechnical,
+    /// ISO Latin-1 Supplemental, a 96-character set.
+    IsoLatin1Supplemental,
 }
 
 impl Default for StandardCharset {
@@ -870,6 +1413,112 @@ impl StandardCharset {
                 '~' => '·',
                 _ => c,
             },
+            StandardCharset::British => match c {
+                '#' => '£',
+                _ => c,
+            },
+            StandardCharset::DecSupplemental => match c {
+                '(' => '¤',
+                'W' => 'Œ',
+                ']' => 'Ÿ',
+                'w' => 'œ',
+                '}' => 'ÿ',
+                // All other characters match the ISO Latin-1 supplement.
+                '!'..='~' => char::from(c as u8 + 0x80),
+                _ => c,
+            },
+            StandardCharset::DecTechnical => match c {
+                '!' => '⎷',
+                '"' => '┌',
+                '#' => '─',
+                '$' => '⌠',
+                '%' => '⌡',
+                '&' => '│',
+                '\'' => '⎡',
+                '(' => '⎣',
+                ')' => '⎤',
+                '*' => '⎦',
+                '+' => '⎛',
+                ',' => '⎝',
+                '-' => '⎞',
+                '.' => '⎠',
+                '/' => '⎨',
+                '0' => '⎬',
+                '1' => '⎲',
+                '2' => '⎳',
+                '3' => '╲',
+                '4' => '╱',
+                '5' => '⌝',
+                '6' => '⌟',
+                '7' => '⟩',
+                '<' => '≤',
+                '=' => '≠',
+                '>' => '≥',
+                '?' => '∫',
+                '@' => '∴',
+                'A' => '∝',
+                'B' => '∞',
+                'C' => '÷',
+                'D' => 'Δ',
+                'E' => '∇',
+                'F' => 'Φ',
+                'G' => 'Γ',
+                'H' => '∼',
+                'I' => '≃',
+                'J' => 'Θ',
+                'K' => '×',
+                'L' => 'Λ',
+                'M' => '⇔',
+                'N' => '⇒',
+                'O' => '≡',
+                'P' => 'Π',
+                'Q' => 'Ψ',
+                'S' => 'Σ',
+                'V' => '√',
+                'W' => 'Ω',
+                'X' => 'Ξ',
+                'Y' => 'Υ',
+                'Z' => '⊂',
+                '[' => '⊃',
+                '\\' => '∩',
+                ']' => '∪',
+                '^' => '∧',
+                '_' => '∨',
+                '`' => '¬',
+                'a' => 'α',
+                'b' => 'β',
+                'c' => 'χ',
+                'd' => 'δ',
+                'e' => 'ε',
+                'f' => 'φ',
+                'g' => 'γ',
+                'h' => 'η',
+                'i' => 'ι',
+                'j' => 'θ',
+                'k' => 'κ',
+                'l' => 'λ',
+                'n' => 'ν',
+                'o' => '∂',
+                'p' => 'π',
+                'q' => 'ψ',
+                'r' => 'ρ',
+                's' => 'σ',
+                't' => 'τ',
+                'v' => 'ƒ',
+                'w' => 'ω',
+                'x' => 'ξ',
+                'y' => 'υ',
+                'z' => 'ζ',
+                '{' => '←',
+                '|' => '↑',
+                '}' => '→',
+                '~' => '↓',
+                _ => c,
+            },
+            StandardCharset::IsoLatin1Supplemental => match c {
+                '!'..='~' => char::from(c as u8 + 0x80),
+                _ => c,
+            },
         }
     }
 }
@@ -901,6 +1550,8 @@ where
 
     #[inline]
     fn hook(&mut self, params: &Params, intermediates: &[u8], ignore: bool, action: char) {
+        self.state.dcs = None;
+
         match (action, intermediates) {
             ('s', [b'=']) => {
                 // Start a synchronized update. The end is handled with a separate parser.
@@ -908,6 +1559,20 @@ where
                     self.state.dcs = Some(Dcs::SyncStart);
                 }
             },
+            ('q', [b'$']) => {
+                self.state.dcs_buffer.clear();
+                self.state.dcs = Some(Dcs::RequestStatusString);
+            },
+            ('q', [b'+']) => {
+                self.state.dcs_buffer.clear();
+                self.state.dcs = Some(Dcs::RequestTermcap);
+            },
+            #[cfg(feature = "sixel")]
+            ('q', []) => {
+                // Pixels which are not drawn stay transparent if the second parameter is 1.
+                let transparent = params.iter().nth(1).map_or(false, |param| param[0] == 1);
+                self.state.dcs = Some(Dcs::Sixel(Box::new(sixel::Parser::new(transparent))));
+            },
             _ => debug!(
                 "[unhandled hook] params={:?}, ints: {:?}, ignore: {:?}, action: {:?}",
                 params, intermediates, ignore, action
@@ -917,17 +1582,49 @@ where
 
     #[inline]
     fn put(&mut self, byte: u8) {
-        debug!("[unhandled put] byte={:?}", byte);
+        match &mut self.state.dcs {
+            Some(Dcs::RequestStatusString) | Some(Dcs::RequestTermcap) => {
+                if self.state.dcs_buffer.len() < MAX_DCS_QUERY_LEN {
+                    self.state.dcs_buffer.push(byte);
+                }
+            },
+            #[cfg(feature = "sixel")]
+            Some(Dcs::Sixel(parser)) => parser.put(byte),
+            _ => debug!("[unhandled put] byte={:?}", byte),
+        }
     }
 
     #[inline]
     fn unhook(&mut self) {
-        match self.state.dcs {
+        match self.state.dcs.take() {
             Some(Dcs::SyncStart) => {
                 self.state.sync_state.timeout = Some(Instant::now() + SYNC_UPDATE_TIMEOUT);
             },
             Some(Dcs::SyncEnd) => (),
-            _ => debug!("[unhandled unhook]"),
+            Some(Dcs::RequestStatusString) => {
+                let query = match self.state.dcs_buffer.as_slice() {
+                    b"m" => Some(StatusStringQuery::GraphicRendition),
+                    b" q" => Some(StatusStringQuery::CursorStyle),
+                    b"r" => Some(StatusStringQuery::ScrollingRegion),
+                    b"s" => Some(StatusStringQuery::LeftRightMargins),
+                    _ => None,
+                };
+                self.handler.report_status_string(query);
+            },
+            Some(Dcs::RequestTermcap) => {
+                for name in self.state.dcs_buffer.split(|&byte| byte == b';') {
+                    match parse_hex_string(name) {
+                        Some(name) => self.handler.report_termcap(&name),
+                        None => debug!("[unhandled XTGETTCAP] name={:?}", name),
+                    }
+                }
+            },
+            #[cfg(feature = "sixel")]
+            Some(Dcs::Sixel(parser)) => match parser.finish() {
+                Some(graphic) => self.handler.insert_graphic(graphic),
+                None => debug!("[unhandled sixel] empty image"),
+            },
+            None => debug!("[unhandled unhook]"),
         }
     }
 
@@ -968,35 +1665,73 @@ where
                 unhandled(params);
             },
 
-            // Set color index.
+            // Get/set color index.
             b"4" => {
-                if params.len() > 1 && params.len() % 2 != 0 {
-                    for chunk in params[1..].chunks(2) {
-                        let index = parse_number(chunk[0]);
-                        let color = xparse_color(chunk[1]);
-                        if let (Some(i), Some(c)) = (index, color) {
-                            self.handler.set_color(i as usize, c);
-                            return;
-                        }
+                if params.len() <= 1 || params.len() % 2 == 0 {
+                    unhandled(params);
+                    return;
+                }
+
+                for chunk in params[1..].chunks(2) {
+                    let index = match parse_number(chunk[0]) {
+                        Some(index) => index,
+                        None => {
+                            unhandled(params);
+                            continue;
+                        },
+                    };
+
+                    if let Some(color) = xparse_color(chunk[1]) {
+                        self.handler.set_color(index as usize, color);
+                    } else if chunk[1] == b"?" {
+                        let prefix = format!("4;{}", index);
+                        self.handler.indexed_color_sequence(prefix, index as usize, terminator);
+                    } else {
+                        unhandled(params);
+                    }
+                }
+            },
+
+            // Get/set special colors.
+            b"5" => {
+                if params.len() <= 1 || params.len() % 2 == 0 {
+                    unhandled(params);
+                    return;
+                }
+
+                for chunk in params[1..].chunks(2) {
+                    let index = match parse_number(chunk[0]).and_then(special_color_index) {
+                        Some(index) => index,
+                        None => {
+                            unhandled(params);
+                            continue;
+                        },
+                    };
+
+                    if let Some(color) = xparse_color(chunk[1]) {
+                        self.handler.set_color(index, color);
+                    } else if chunk[1] == b"?" {
+                        let prefix = format!("5;{}", String::from_utf8_lossy(chunk[0]));
+                        self.handler.indexed_color_sequence(prefix, index, terminator);
+                    } else {
+                        unhandled(params);
                     }
                 }
-                unhandled(params);
             },
 
-            // Get/set Foreground, Background, Cursor colors.
-            b"10" | b"11" | b"12" => {
+            // Get/set Foreground, Background, Cursor and selection colors.
+            b"10" | b"11" | b"12" | b"17" | b"19" => {
                 if params.len() >= 2 {
                     if let Some(mut dynamic_code) = parse_number(params[0]) {
                         for param in &params[1..] {
-                            // 10 is the first dynamic color, also the foreground.
-                            let offset = dynamic_code as usize - 10;
-                            let index = NamedColor::Foreground as usize + offset;
-
                             // End of setting dynamic colors.
-                            if index > NamedColor::Cursor as usize {
-                                unhandled(params);
-                                break;
-                            }
+                            let index = match dynamic_color_index(dynamic_code) {
+                                Some(index) => index,
+                                None => {
+                                    unhandled(params);
+                                    break;
+                                },
+                            };
 
                             if let Some(color) = xparse_color(param) {
                                 self.handler.set_color(index, color);
@@ -1017,6 +1752,45 @@ where
                 unhandled(params);
             },
 
+            // Set working directory.
+            b"7" if params.len() > 1 => {
+                // Rebuild the URI, since `;` is a valid path character.
+                let uri = params[1..].join(&b';');
+                match parse_file_uri(&uri) {
+                    Some((host, path)) => self.handler.set_working_directory(host, path),
+                    None => unhandled(params),
+                }
+            },
+
+            // Create a hyperlink to uri using params.
+            b"8" if params.len() > 2 => {
+                let link_params = params[1];
+
+                // NOTE: The escape sequence is of form 'OSC 8 ; params ; URI ST', where
+                // URI is URL-encoded. However `;` is a special character and might be
+                // passed as is, thus we need to rebuild the URI.
+                let mut uri = str::from_utf8(params[2]).unwrap_or_default().to_string();
+                for param in params[3..].iter() {
+                    uri.push(';');
+                    uri.push_str(str::from_utf8(param).unwrap_or_default());
+                }
+
+                // The OSC 8 escape sequence must be stopped when getting an empty `uri`.
+                if uri.is_empty() {
+                    self.handler.set_hyperlink(None);
+                    return;
+                }
+
+                // Link parameters are in format of `key1=value1:key2=value2`. Currently only key
+                // `id` is defined.
+                let id = link_params
+                    .split(|&b| b == b':')
+                    .find(|kv| kv.starts_with(b"id="))
+                    .and_then(|kv| str::from_utf8(&kv[3..]).ok().map(|id| id.to_owned()));
+
+                self.handler.set_hyperlink(Some(Hyperlink { id, uri }));
+            },
+
             // Set cursor style.
             b"50" => {
                 if params.len() >= 2
@@ -1035,6 +1809,32 @@ where
                 unhandled(params);
             },
 
+            // Set mouse cursor shape.
+            b"22" if params.len() > 1 => {
+                let shape = Some(params[1])
+                    .filter(|shape| !shape.is_empty())
+                    .map(|shape| String::from_utf8_lossy(shape).into_owned());
+                self.handler.set_mouse_cursor_shape(shape);
+            },
+
+            // Desktop notification.
+            b"9" if params.len() > 1 => {
+                // Ignore ConEmu's progress reports, which share the same OSC.
+                if params[1] == b"4" && params.len() > 2 {
+                    return unhandled(params);
+                }
+
+                let body = join_params(&params[1..]);
+                self.handler.desktop_notification(None, body);
+            },
+
+            // Desktop notification with title.
+            b"777" if params.len() > 2 && params[1] == b"notify" => {
+                let title = String::from_utf8_lossy(params[2]).into_owned();
+                let body = join_params(&params[3..]);
+                self.handler.desktop_notification(Some(title), body);
+            },
+
             // Set clipboard.
             b"52" => {
                 if params.len() < 3 {
@@ -1048,6 +1848,18 @@ where
                 }
             },
 
+            // Shell integration marks.
+            b"133" if params.len() > 1 => {
+                let mark = match params[1] {
+                    b"A" => PromptMark::PromptStart,
+                    b"B" => PromptMark::CommandStart,
+                    b"C" => PromptMark::OutputStart,
+                    b"D" => PromptMark::CommandEnd,
+                    _ => return unhandled(params),
+                };
+                self.handler.prompt_mark(mark);
+            },
+
             // Reset color index.
             b"104" => {
                 // Reset all color indexes when no parameters are given.
@@ -1073,9 +1885,34 @@ where
             // Reset background color.
             b"111" => self.handler.reset_color(NamedColor::Background as usize),
 
+            // Reset special colors.
+            b"105" => {
+                // Reset all special colors when no parameters are given.
+                if params.len() == 1 {
+                    for index in (0..=u8::MAX).filter_map(special_color_index) {
+                        self.handler.reset_color(index);
+                    }
+                    return;
+                }
+
+                // Reset special colors given as parameters.
+                for param in &params[1..] {
+                    match parse_number(param).and_then(special_color_index) {
+                        Some(index) => self.handler.reset_color(index),
+                        None => unhandled(params),
+                    }
+                }
+            },
+
             // Reset text cursor color.
             b"112" => self.handler.reset_color(NamedColor::Cursor as usize),
 
+            // Reset selection background color.
+            b"117" => self.handler.reset_color(NamedColor::SelectionBackground as usize),
+
+            // Reset selection foreground color.
+            b"119" => self.handler.reset_color(NamedColor::SelectionForeground as usize),
+
             _ => unhandled(params),
         }
     }
@@ -1098,7 +1935,8 @@ where
             }};
         }
 
-        if has_ignored_intermediates || intermediates.len() > 1 {
+        // Private mode queries are the only sequences with two intermediates.
+        if has_ignored_intermediates || (intermediates.len() > 1 && intermediates != b"?$") {
             unhandled!();
             return;
         }
@@ -1152,6 +1990,12 @@ where
             ('h', intermediates) => {
                 for param in params_iter.map(|param| param[0]) {
                     match Mode::from_primitive(intermediates.get(0), param) {
+                        Some(Mode::SyncUpdate) => {
+                            // Buffer all following bytes until the update is terminated.
+                            let timeout = Instant::now() + SYNC_UPDATE_TIMEOUT;
+                            self.state.sync_state.timeout = Some(timeout);
+                            handler.set_mode(Mode::SyncUpdate);
+                        },
                         Some(mode) => handler.set_mode(mode),
                         None => unhandled!(),
                     }
@@ -1209,6 +2053,13 @@ where
             },
             ('n', []) => handler.device_status(next_param_or(0) as usize),
             ('P', []) => handler.delete_chars(next_param_or(1) as usize),
+            ('p', [b'!']) => handler.soft_reset(),
+            ('p', [b'$']) => handler.report_mode(false, next_param_or(0)),
+            ('p', [b'?', b'$']) => match next_param_or(0) {
+                2026 => handler.report_sync_update(self.state.sync_state.timeout.is_some()),
+                mode => handler.report_mode(true, mode),
+            },
+            ('q', [b'>']) if next_param_or(0) == 0 => handler.report_version(),
             ('q', [b' ']) => {
                 // DECSCUSR (CSI Ps SP q) -- Set Cursor Style.
                 let cursor_style_id = next_param_or(0);
@@ -1234,25 +2085,176 @@ where
 
                 handler.set_scrolling_region(top, bottom);
             },
+            ('r', [b'$']) => {
+                let area = Rectangle::from_params(
+                    next_param_or(0),
+                    next_param_or(0),
+                    next_param_or(0),
+                    next_param_or(0),
+                );
+
+                // Only attributes which can be changed by DECCARA are allowed.
+                let mut attrs = Vec::new();
+                for param in params_iter.map(|param| param[0]) {
+                    let attr = match param {
+                        0 => Attr::Reset,
+                        1 => Attr::Bold,
+                        4 => Attr::Underline,
+                        5 => Attr::BlinkSlow,
+                        7 => Attr::Reverse,
+                        22 => Attr::CancelBold,
+                        24 => Attr::CancelUnderline,
+                        25 => Attr::CancelBlink,
+                        27 => Attr::CancelReverse,
+                        _ => continue,
+                    };
+                    attrs.push(attr);
+                }
+
+                if attrs.is_empty() {
+                    attrs.push(Attr::Reset);
+                }
+
+                handler.change_area_attributes(area, &attrs);
+            },
             ('S', []) => handler.scroll_up(next_param_or(1) as usize),
-            ('s', []) => handler.save_cursor_position(),
+            ('s', []) => {
+                let left = next_param_or(1) as usize;
+                let right =
+                    params_iter.next().map(|param| param[0] as usize).filter(|&param| param != 0);
+
+                handler.set_left_right_margins(left, right);
+            },
+            ('T', []) if params.len() >= 5 => {
+                let enabled = next_param_or(0) != 0;
+                let column = next_param_or(1) as usize;
+                let line = next_param_or(1) as usize;
+                let first_line = next_param_or(1) as usize;
+                let last_line = next_param_or(1) as usize;
+
+                let highlight = MouseHighlight { line, column, first_line, last_line };
+                handler.start_mouse_highlight(Some(highlight).filter(|_| enabled));
+            },
             ('T', []) => handler.scroll_down(next_param_or(1) as usize),
             ('t', []) => match next_param_or(1) as usize {
+                1 => handler.window_operation(WindowOperation::Deiconify),
+                2 => handler.window_operation(WindowOperation::Iconify),
+                3 => {
+                    let x = i32::from(next_param_or(0));
+                    let y = i32::from(next_param_or(0));
+                    handler.window_operation(WindowOperation::Move { x, y });
+                },
+                4 => {
+                    let height = Some(next_param_or(0) as usize).filter(|&height| height != 0);
+                    let width = Some(next_param_or(0) as usize).filter(|&width| width != 0);
+                    handler.window_operation(WindowOperation::ResizePixels { width, height });
+                },
+                // Content is redrawn after every update, so refreshing is not necessary.
+                7 => (),
+                8 => {
+                    let lines = Some(next_param_or(0) as usize).filter(|&lines| lines != 0);
+                    let columns = Some(next_param_or(0) as usize).filter(|&columns| columns != 0);
+                    handler.window_operation(WindowOperation::ResizeCells { columns, lines });
+                },
+                9 => match next_param_or(0) {
+                    0 => handler.window_operation(WindowOperation::Maximize(false)),
+                    1..=3 => handler.window_operation(WindowOperation::Maximize(true)),
+                    _ => unhandled!(),
+                },
+                10 => match next_param_or(0) {
+                    0 => handler.window_operation(WindowOperation::Fullscreen(false)),
+                    1 => handler.window_operation(WindowOperation::Fullscreen(true)),
+                    2 => handler.window_operation(WindowOperation::ToggleFullscreen),
+                    _ => unhandled!(),
+                },
+                11 => handler.window_operation(WindowOperation::ReportState),
+                13 => handler.window_operation(WindowOperation::ReportPosition),
                 14 => handler.text_area_size_pixels(),
+                16 => handler.window_operation(WindowOperation::ReportCellSize),
                 18 => handler.text_area_size_chars(),
+                19 => handler.window_operation(WindowOperation::ReportScreenSize),
+                20 => handler.window_operation(WindowOperation::ReportIconTitle),
+                21 => handler.window_operation(WindowOperation::ReportTitle),
                 22 => handler.push_title(),
                 23 => handler.pop_title(),
                 _ => unhandled!(),
             },
             ('u', []) => handler.restore_cursor_position(),
-            ('X', []) => handler.erase_chars(Column(next_param_or(1) as usize)),
-            ('Z', []) => handler.move_backward_tabs(next_param_or(1)),
-            _ => unhandled!(),
-        }
-    }
+            ('u', [b'?']) => handler.report_keyboard_mode(),
+            ('u', [b'=']) => {
+                let mode = KeyboardModes::from_bits_truncate(next_param_or(0) as u8);
+                let behavior = match next_param_or(1) {
+                    3 => KeyboardModesApplyBehavior::Difference,
+                    2 => KeyboardModesApplyBehavior::Union,
+                    // Default mode is replace.
+                    _ => KeyboardModesApplyBehavior::Replace,
+                };
+                handler.set_keyboard_mode(mode, behavior);
+            },
+            ('u', [b'>']) => {
+                let mode = KeyboardModes::from_bits_truncate(next_param_or(0) as u8);
+                handler.push_keyboard_mode(mode);
+            },
+            ('u', [b'<']) => handler.pop_keyboard_modes(next_param_or(1)),
+            ('v', [b'$']) => {
+                let area = Rectangle::from_params(
+                    next_param_or(0),
+                    next_param_or(0),
+                    next_param_or(0),
+                    next_param_or(0),
+                );
 
-    #[inline]
-    fn esc_dispatch(&mut self, intermediates: &[u8], _ignore: bool, byte: u8) {
+                // Pages are not supported, so the source and destination page are ignored.
+                let _source_page = next_param_or(1);
+                let top = next_param_or(1) as usize;
+                let left = next_param_or(1) as usize;
+
+                handler.copy_area(area, top, left);
+            },
+            ('X', []) => handler.erase_chars(Column(next_param_or(1) as usize)),
+            ('x', [b'$']) => match char::from_u32(u32::from(next_param_or(0))) {
+                Some(c) if !c.is_control() => {
+                    let area = Rectangle::from_params(
+                        next_param_or(0),
+                        next_param_or(0),
+                        next_param_or(0),
+                        next_param_or(0),
+                    );
+
+                    handler.fill_area(c, area);
+                },
+                _ => unhandled!(),
+            },
+            ('x', [b'*']) => handler.set_attribute_change_extent(next_param_or(0) == 2),
+            ('y', [b'*']) => {
+                let id = next_param_or(0);
+                let _page = next_param_or(1);
+                let area = Rectangle::from_params(
+                    next_param_or(0),
+                    next_param_or(0),
+                    next_param_or(0),
+                    next_param_or(0),
+                );
+
+                handler.report_area_checksum(id, area);
+            },
+            ('z', [b'$']) => {
+                let area = Rectangle::from_params(
+                    next_param_or(0),
+                    next_param_or(0),
+                    next_param_or(0),
+                    next_param_or(0),
+                );
+
+                handler.erase_area(area);
+            },
+            ('Z', []) => handler.move_backward_tabs(next_param_or(1)),
+            _ => unhandled!(),
+        }
+    }
+
+    #[inline]
+    fn esc_dispatch(&mut self, intermediates: &[u8], _ignore: bool, byte: u8) {
         macro_rules! unhandled {
             () => {{
                 debug!(
@@ -1265,10 +2267,10 @@ where
         macro_rules! configure_charset {
             ($charset:path, $intermediates:expr) => {{
                 let index: CharsetIndex = match $intermediates {
-                    [b'('] => CharsetIndex::G0,
-                    [b')'] => CharsetIndex::G1,
-                    [b'*'] => CharsetIndex::G2,
-                    [b'+'] => CharsetIndex::G3,
+                    [b'(', ..] => CharsetIndex::G0,
+                    [b')', ..] | [b'-', ..] => CharsetIndex::G1,
+                    [b'*', ..] | [b'.', ..] => CharsetIndex::G2,
+                    [b'+', ..] | [b'/', ..] => CharsetIndex::G3,
                     _ => {
                         unhandled!();
                         return;
@@ -1279,6 +2281,10 @@ where
         }
 
         match (byte, intermediates) {
+            (b'A', [b'-']) | (b'A', [b'.']) | (b'A', [b'/']) => {
+                configure_charset!(StandardCharset::IsoLatin1Supplemental, intermediates)
+            },
+            (b'A', intermediates) => configure_charset!(StandardCharset::British, intermediates),
             (b'B', intermediates) => configure_charset!(StandardCharset::Ascii, intermediates),
             (b'D', []) => self.handler.linefeed(),
             (b'E', []) => {
@@ -1287,14 +2293,29 @@ where
             },
             (b'H', []) => self.handler.set_horizontal_tabstop(),
             (b'M', []) => self.handler.reverse_index(),
+            (b'N', []) => self.handler.set_single_shift_charset(CharsetIndex::G2),
+            (b'O', []) => self.handler.set_single_shift_charset(CharsetIndex::G3),
             (b'Z', []) => self.handler.identify_terminal(None),
             (b'c', []) => self.handler.reset_state(),
+            (b'n', []) => self.handler.set_active_charset(CharsetIndex::G2),
+            (b'o', []) => self.handler.set_active_charset(CharsetIndex::G3),
             (b'0', intermediates) => {
                 configure_charset!(StandardCharset::SpecialCharacterAndLineDrawing, intermediates)
             },
             (b'7', []) => self.handler.save_cursor_position(),
+            (b'3', [b'#']) => self.handler.set_line_size(LineSize::DoubleHeightTop),
+            (b'4', [b'#']) => self.handler.set_line_size(LineSize::DoubleHeightBottom),
+            (b'5', [b'#']) => self.handler.set_line_size(LineSize::Normal),
+            (b'6', [b'#']) => self.handler.set_line_size(LineSize::DoubleWidth),
             (b'8', [b'#']) => self.handler.decaln(),
             (b'8', []) => self.handler.restore_cursor_position(),
+            (b'5', [_, b'%']) => {
+                configure_charset!(StandardCharset::DecSupplemental, intermediates)
+            },
+            (b'<', intermediates) => {
+                configure_charset!(StandardCharset::DecSupplemental, intermediates)
+            },
+            (b'>', [_]) => configure_charset!(StandardCharset::DecTechnical, intermediates),
             (b'=', []) => self.handler.set_keypad_application_mode(),
             (b'>', []) => self.handler.unset_keypad_application_mode(),
             // String terminator, do nothing (parser handles as string terminator).
@@ -1316,13 +2337,16 @@ fn attrs_from_sgr_parameters(params: &mut ParamsIter<'_>) -> Vec<Option<Attr>> {
             [3] => Some(Attr::Italic),
             [4, 0] => Some(Attr::CancelUnderline),
             [4, 2] => Some(Attr::DoubleUnderline),
+            [4, 3] => Some(Attr::Undercurl),
+            [4, 4] => Some(Attr::DottedUnderline),
+            [4, 5] => Some(Attr::DashedUnderline),
             [4, ..] => Some(Attr::Underline),
             [5] => Some(Attr::BlinkSlow),
             [6] => Some(Attr::BlinkFast),
             [7] => Some(Attr::Reverse),
             [8] => Some(Attr::Hidden),
             [9] => Some(Attr::Strike),
-            [21] => Some(Attr::CancelBold),
+            [21] => Some(Attr::DoubleUnderline),
             [22] => Some(Attr::CancelBoldDim),
             [23] => Some(Attr::CancelItalic),
             [24] => Some(Attr::CancelUnderline),
@@ -1370,6 +2394,23 @@ fn attrs_from_sgr_parameters(params: &mut ParamsIter<'_>) -> Vec<Option<Attr>> {
                 parse_sgr_color(&mut iter).map(Attr::Background)
             },
             [49] => Some(Attr::Background(Color::Named(NamedColor::Background))),
+            [53] => Some(Attr::Overline),
+            [55] => Some(Attr::CancelOverline),
+            [58] => {
+                let mut iter = params.map(|param| param[0]);
+                parse_sgr_color(&mut iter).map(|color| Attr::UnderlineColor(Some(color)))
+            },
+            [58, params @ ..] => {
+                let rgb_start = if params.len() > 4 { 2 } else { 1 };
+                let rgb_iter = params[rgb_start..].iter().copied();
+                let mut iter = iter::once(params[0]).chain(rgb_iter);
+
+                parse_sgr_color(&mut iter).map(|color| Attr::UnderlineColor(Some(color)))
+            },
+            [59] => Some(Attr::UnderlineColor(None)),
+            [73] => Some(Attr::Superscript),
+            [74] => Some(Attr::Subscript),
+            [75] => Some(Attr::CancelSuperscriptSubscript),
             [90] => Some(Attr::Foreground(Color::Named(NamedColor::BrightBlack))),
             [91] => Some(Attr::Foreground(Color::Named(NamedColor::BrightRed))),
             [92] => Some(Attr::Foreground(Color::Named(NamedColor::BrightGreen))),
@@ -1490,8 +2531,25 @@ mod tests {
     struct MockHandler {
         index: CharsetIndex,
         charset: StandardCharset,
+        single_shift: Option<CharsetIndex>,
         attr: Option<Attr>,
         identity_reported: bool,
+        version_reported: bool,
+        mode_reports: Vec<(bool, u16)>,
+        sync_update_reports: Vec<bool>,
+        hyperlink: Option<Hyperlink>,
+        status_string_query: Option<StatusStringQuery>,
+        termcap_names: Vec<String>,
+        graphic: Option<GraphicData>,
+        kitty_commands: Vec<kitty::Command>,
+        notifications: Vec<(Option<String>, String)>,
+        mouse_cursor_shapes: Vec<Option<String>>,
+        window_operations: Vec<WindowOperation>,
+        mouse_highlights: Vec<Option<MouseHighlight>>,
+        area_checksums: Vec<(u16, Rectangle)>,
+        colors: Vec<(usize, Rgb)>,
+        color_requests: Vec<(String, usize)>,
+        reset_colors: Vec<usize>,
     }
 
     impl Handler for MockHandler {
@@ -1508,13 +2566,85 @@ mod tests {
             self.index = index;
         }
 
+        fn set_single_shift_charset(&mut self, index: CharsetIndex) {
+            self.single_shift = Some(index);
+        }
+
         fn identify_terminal(&mut self, _intermediate: Option<char>) {
             self.identity_reported = true;
         }
 
+        fn report_version(&mut self) {
+            self.version_reported = true;
+        }
+
+        fn report_mode(&mut self, private: bool, mode: u16) {
+            self.mode_reports.push((private, mode));
+        }
+
+        fn report_sync_update(&mut self, active: bool) {
+            self.sync_update_reports.push(active);
+        }
+
         fn reset_state(&mut self) {
             *self = Self::default();
         }
+
+        fn set_hyperlink(&mut self, hyperlink: Option<Hyperlink>) {
+            self.hyperlink = hyperlink;
+        }
+
+        fn report_status_string(&mut self, query: Option<StatusStringQuery>) {
+            self.status_string_query = query;
+        }
+
+        fn report_termcap(&mut self, name: &str) {
+            self.termcap_names.push(name.into());
+        }
+
+        fn insert_graphic(&mut self, graphic: GraphicData) {
+            self.graphic = Some(graphic);
+        }
+
+        fn kitty_graphics(&mut self, command: kitty::Command) {
+            self.kitty_commands.push(command);
+        }
+
+        fn desktop_notification(&mut self, title: Option<String>, body: String) {
+            self.notifications.push((title, body));
+        }
+
+        fn set_mouse_cursor_shape(&mut self, shape: Option<String>) {
+            self.mouse_cursor_shapes.push(shape);
+        }
+
+        fn window_operation(&mut self, operation: WindowOperation) {
+            self.window_operations.push(operation);
+        }
+
+        fn start_mouse_highlight(&mut self, highlight: Option<MouseHighlight>) {
+            self.mouse_highlights.push(highlight);
+        }
+
+        fn report_area_checksum(&mut self, id: u16, area: Rectangle) {
+            self.area_checksums.push((id, area));
+        }
+
+        fn set_color(&mut self, index: usize, color: Rgb) {
+            self.colors.push((index, color));
+        }
+
+        fn dynamic_color_sequence(&mut self, code: u8, index: usize, _terminator: &str) {
+            self.color_requests.push((code.to_string(), index));
+        }
+
+        fn indexed_color_sequence(&mut self, prefix: String, index: usize, _terminator: &str) {
+            self.color_requests.push((prefix, index));
+        }
+
+        fn reset_color(&mut self, index: usize) {
+            self.reset_colors.push(index);
+        }
     }
 
     impl Default for MockHandler {
@@ -1522,8 +2652,25 @@ mod tests {
             MockHandler {
                 index: CharsetIndex::G0,
                 charset: StandardCharset::Ascii,
+                single_shift: None,
                 attr: None,
                 identity_reported: false,
+                version_reported: false,
+                mode_reports: Vec::new(),
+                sync_update_reports: Vec::new(),
+                hyperlink: None,
+                status_string_query: None,
+                termcap_names: Vec::new(),
+                graphic: None,
+                kitty_commands: Vec::new(),
+                notifications: Vec::new(),
+                mouse_cursor_shapes: Vec::new(),
+                window_operations: Vec::new(),
+                mouse_highlights: Vec::new(),
+                area_checksums: Vec::new(),
+                colors: Vec::new(),
+                color_requests: Vec::new(),
+                reset_colors: Vec::new(),
             }
         }
     }
@@ -1574,6 +2721,80 @@ mod tests {
         assert!(handler.identity_reported);
     }
 
+    #[test]
+    fn parse_sync_update_mode() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b[?2026h" {
+            parser.advance(&mut handler, *byte);
+        }
+        assert!(parser.sync_timeout().is_some());
+
+        // Bytes are buffered until the update is terminated.
+        for byte in b"\x1b[4mtext\x1b[?2026h" {
+            parser.advance(&mut handler, *byte);
+        }
+        assert_eq!(parser.sync_bytes_count(), 16);
+        assert_eq!(handler.attr, None);
+
+        for byte in b"\x1b[?2026l" {
+            parser.advance(&mut handler, *byte);
+        }
+        assert!(parser.sync_timeout().is_none());
+        assert_eq!(parser.sync_bytes_count(), 0);
+        assert_eq!(handler.attr, Some(Attr::Underline));
+    }
+
+    #[test]
+    fn parse_sync_update_query() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        // Queries are answered immediately during synchronized updates.
+        for byte in b"\x1b[?2026htext\x1b[?2026$p" {
+            parser.advance(&mut handler, *byte);
+        }
+        assert_eq!(handler.sync_update_reports, vec![true]);
+        assert_eq!(parser.sync_bytes_count(), 4);
+
+        for byte in b"\x1b[?2026l\x1b[?2026$p" {
+            parser.advance(&mut handler, *byte);
+        }
+        assert_eq!(handler.sync_update_reports, vec![true, false]);
+    }
+
+    #[test]
+    fn parse_mode_reports() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b[4$p\x1b[?2026$p" {
+            parser.advance(&mut handler, *byte);
+        }
+
+        assert_eq!(handler.mode_reports, vec![(false, 4)]);
+        assert_eq!(handler.sync_update_reports, vec![false]);
+    }
+
+    #[test]
+    fn parse_terminal_version() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b[>1q" {
+            parser.advance(&mut handler, *byte);
+        }
+
+        assert!(!handler.version_reported);
+
+        for byte in b"\x1b[>q" {
+            parser.advance(&mut handler, *byte);
+        }
+
+        assert!(handler.version_reported);
+    }
+
     #[test]
     fn parse_terminal_identity_esc() {
         let bytes: &[u8] = &[0x1b, b'Z'];
@@ -1601,6 +2822,224 @@ mod tests {
         handler.reset_state();
     }
 
+    #[test]
+    fn parse_osc8_hyperlink() {
+        static BYTES: &[u8] = b"\x1b]8;foo=bar:id=link;https://example.org/a;b\x1b\\";
+
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in BYTES {
+            parser.advance(&mut handler, *byte);
+        }
+
+        let expected =
+            Hyperlink { id: Some("link".into()), uri: String::from("https://example.org/a;b") };
+        assert_eq!(handler.hyperlink, Some(expected));
+
+        for byte in b"\x1b]8;;\x07" {
+            parser.advance(&mut handler, *byte);
+        }
+
+        assert_eq!(handler.hyperlink, None);
+    }
+
+    #[test]
+    fn parse_desktop_notifications() {
+        static BYTES: &[u8] =
+            b"\x1b]9;Build finished; 0 errors\x07\x1b]777;notify;make;done; ok\x1b\\\
+                                \x1b]9;4;1;50\x07\x1b]777;other;x\x07";
+
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in BYTES {
+            parser.advance(&mut handler, *byte);
+        }
+
+        assert_eq!(
+            handler.notifications,
+            vec![
+                (None, String::from("Build finished; 0 errors")),
+                (Some(String::from("make")), String::from("done; ok")),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_mouse_cursor_shape() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b]22;pointer\x07\x1b]22;\x07" {
+            parser.advance(&mut handler, *byte);
+        }
+
+        assert_eq!(handler.mouse_cursor_shapes, vec![Some(String::from("pointer")), None]);
+    }
+
+    #[test]
+    fn parse_mouse_highlight() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b[1;5;3;2;10T\x1b[0;5;3;2;10T\x1b[3T" {
+            parser.advance(&mut handler, *byte);
+        }
+
+        let highlight = MouseHighlight { line: 3, column: 5, first_line: 2, last_line: 10 };
+        assert_eq!(handler.mouse_highlights, vec![Some(highlight), None]);
+    }
+
+    #[test]
+    fn parse_window_operations() {
+        static BYTES: &[u8] = b"\x1b[2t\x1b[3;10;20t\x1b[4;;640t\x1b[8;24;80t\x1b[9;1t\x1b[10;2t\
+                                \x1b[11t\x1b[16t\x1b[21t\x1b[10;7t";
+
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in BYTES {
+            parser.advance(&mut handler, *byte);
+        }
+
+        assert_eq!(
+            handler.window_operations,
+            vec![
+                WindowOperation::Iconify,
+                WindowOperation::Move { x: 10, y: 20 },
+                WindowOperation::ResizePixels { width: Some(640), height: None },
+                WindowOperation::ResizeCells { columns: Some(80), lines: Some(24) },
+                WindowOperation::Maximize(true),
+                WindowOperation::ToggleFullscreen,
+                WindowOperation::ReportState,
+                WindowOperation::ReportCellSize,
+                WindowOperation::ReportTitle,
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_area_checksum() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b[7;1;2;3;4;5*y\x1b[8*y" {
+            parser.advance(&mut handler, *byte);
+        }
+
+        assert_eq!(
+            handler.area_checksums,
+            vec![
+                (7, Rectangle::new(2, 3, Some(4), Some(5))),
+                (8, Rectangle::new(1, 1, None, None)),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_color_queries() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b]4;1;?;2;#ff0000;255;?\x07\x1b]5;0;?\x07\x1b]17;?\x07\x1b]19;#00ff00\x07"
+        {
+            parser.advance(&mut handler, *byte);
+        }
+
+        let bright_foreground = NamedColor::BrightForeground as usize;
+        let selection_background = NamedColor::SelectionBackground as usize;
+        let selection_foreground = NamedColor::SelectionForeground as usize;
+
+        assert_eq!(
+            handler.color_requests,
+            vec![
+                (String::from("4;1"), 1),
+                (String::from("4;255"), 255),
+                (String::from("5;0"), bright_foreground),
+                (String::from("17"), selection_background),
+            ]
+        );
+        assert_eq!(
+            handler.colors,
+            vec![
+                (2, Rgb { r: 0xff, g: 0, b: 0 }),
+                (selection_foreground, Rgb { r: 0, g: 0xff, b: 0 }),
+            ]
+        );
+
+        for byte in b"\x1b]105\x07\x1b]117\x07\x1b]119\x07" {
+            parser.advance(&mut handler, *byte);
+        }
+
+        assert_eq!(
+            handler.reset_colors,
+            vec![bright_foreground, selection_background, selection_foreground,]
+        );
+    }
+
+    #[test]
+    fn parse_osc7_file_uri() {
+        let (host, path) = parse_file_uri(b"file://host/tmp/with%20space/%E2%9C%93").unwrap();
+        assert_eq!(host, "host");
+        assert_eq!(path, PathBuf::from("/tmp/with space/✓"));
+
+        let (host, path) = parse_file_uri(b"file:///home/%zz").unwrap();
+        assert_eq!(host, "");
+        assert_eq!(path, PathBuf::from("/home/%zz"));
+
+        assert_eq!(parse_file_uri(b"https://host/tmp"), None);
+        assert_eq!(parse_file_uri(b"file://host"), None);
+    }
+
+    #[test]
+    fn parse_dcs_queries() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1bP$q q\x1b\\" {
+            parser.advance(&mut handler, *byte);
+        }
+        assert_eq!(handler.status_string_query, Some(StatusStringQuery::CursorStyle));
+
+        for byte in b"\x1bP+q544E;6B6631\x1b\\" {
+            parser.advance(&mut handler, *byte);
+        }
+        assert_eq!(handler.termcap_names, vec![String::from("TN"), String::from("kf1")]);
+    }
+
+    #[cfg(feature = "sixel")]
+    #[test]
+    fn parse_sixel() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1bP0;1q\"1;1;3;6#1;2;100;0;0#1!3~\x1b\\" {
+            parser.advance(&mut handler, *byte);
+        }
+
+        let graphic = handler.graphic.unwrap();
+        assert_eq!((graphic.width, graphic.height), (3, 6));
+        assert_eq!(&graphic.pixels[..4], &[255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn parse_kitty_graphics() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        // Unknown APC strings and aborted graphics commands are ignored.
+        for byte in b"\x1b_Xabc\x1b\\\x1b_Gi=1\x1b[m\x1b_Ga=p,i=3,c=2;\x1b\\" {
+            parser.advance(&mut handler, *byte);
+        }
+
+        assert_eq!(handler.kitty_commands.len(), 1);
+        let command = &handler.kitty_commands[0];
+        assert_eq!(command.action, kitty::Action::Put);
+        assert_eq!((command.image_id, command.columns), (3, 2));
+        assert_eq!(handler.attr, Some(Attr::Reset));
+    }
+
     #[test]
     fn parse_truecolor_attr() {
         static BYTES: &[u8] = &[
@@ -1620,6 +3059,54 @@ mod tests {
         assert_eq!(handler.attr, Some(Attr::Foreground(Color::Spec(spec))));
     }
 
+    #[test]
+    fn parse_styled_underline_attr() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b[4:3m" {
+            parser.advance(&mut handler, *byte);
+        }
+        assert_eq!(handler.attr, Some(Attr::Undercurl));
+
+        for byte in b"\x1b[58:2::255:0:128m" {
+            parser.advance(&mut handler, *byte);
+        }
+        let spec = Rgb { r: 255, g: 0, b: 128 };
+        assert_eq!(handler.attr, Some(Attr::UnderlineColor(Some(Color::Spec(spec)))));
+
+        for byte in b"\x1b[58;5;1m" {
+            parser.advance(&mut handler, *byte);
+        }
+        assert_eq!(handler.attr, Some(Attr::UnderlineColor(Some(Color::Indexed(1)))));
+
+        for byte in b"\x1b[59m" {
+            parser.advance(&mut handler, *byte);
+        }
+        assert_eq!(handler.attr, Some(Attr::UnderlineColor(None)));
+    }
+
+    #[test]
+    fn parse_ecma48_text_attrs() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        let attrs = vec![
+            (&b"\x1b[21m"[..], Attr::DoubleUnderline),
+            (b"\x1b[53m", Attr::Overline),
+            (b"\x1b[55m", Attr::CancelOverline),
+            (b"\x1b[73m", Attr::Superscript),
+            (b"\x1b[74m", Attr::Subscript),
+            (b"\x1b[75m", Attr::CancelSuperscriptSubscript),
+        ];
+        for (bytes, attr) in attrs {
+            for byte in bytes {
+                parser.advance(&mut handler, *byte);
+            }
+            assert_eq!(handler.attr, Some(attr));
+        }
+    }
+
     /// No exactly a test; useful for debugging.
     #[test]
     fn parse_zsh_startup() {
@@ -1680,6 +3167,46 @@ mod tests {
         assert_eq!(handler.index, CharsetIndex::G1);
     }
 
+    #[test]
+    fn parse_designate_vt220_charsets() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b*A" {
+            parser.advance(&mut handler, *byte);
+        }
+        assert_eq!(handler.index, CharsetIndex::G2);
+        assert_eq!(handler.charset, StandardCharset::British);
+
+        for byte in b"\x1b+%5" {
+            parser.advance(&mut handler, *byte);
+        }
+        assert_eq!(handler.index, CharsetIndex::G3);
+        assert_eq!(handler.charset, StandardCharset::DecSupplemental);
+
+        for byte in b"\x1b)>" {
+            parser.advance(&mut handler, *byte);
+        }
+        assert_eq!(handler.index, CharsetIndex::G1);
+        assert_eq!(handler.charset, StandardCharset::DecTechnical);
+
+        for byte in b"\x1b.A" {
+            parser.advance(&mut handler, *byte);
+        }
+        assert_eq!(handler.index, CharsetIndex::G2);
+        assert_eq!(handler.charset, StandardCharset::IsoLatin1Supplemental);
+
+        for byte in b"\x1bo" {
+            parser.advance(&mut handler, *byte);
+        }
+        assert_eq!(handler.index, CharsetIndex::G3);
+
+        for byte in b"\x1bN" {
+            parser.advance(&mut handler, *byte);
+        }
+        assert_eq!(handler.single_shift, Some(CharsetIndex::G2));
+    }
+
     #[test]
     fn parse_valid_rgb_colors() {
         assert_eq!(xparse_color(b"rgb:f/e/d"), Some(Rgb { r: 0xff, g: 0xee, b: 0xdd }));
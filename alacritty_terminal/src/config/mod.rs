@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 
 use serde::Deserialize;
+use unicode_width::UnicodeWidthChar;
 
 use alacritty_config_derive::ConfigDeserialize;
 
@@ -37,6 +38,12 @@ pub struct Config<T> {
     /// Shell startup directory.
     pub working_directory: Option<PathBuf>,
 
+    /// Window operations applications are allowed to request.
+    pub window_operations: WindowOperations,
+
+    /// Width of East Asian ambiguous characters.
+    pub ambiguous_width: AmbiguousWidth,
+
     /// Additional configuration options not directly required by the terminal.
     #[config(flatten)]
     pub ui_config: T,
@@ -174,6 +181,68 @@ impl From<CursorBlinking> for bool {
     }
 }
 
+/// Allowlist of window operations requested through XTWINOPS.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WindowOperations(Vec<WindowOperationKind>);
+
+impl Default for WindowOperations {
+    fn default() -> Self {
+        Self(vec![WindowOperationKind::ReportWindow])
+    }
+}
+
+impl WindowOperations {
+    /// Check if applications may request an operation.
+    pub fn allows(&self, kind: WindowOperationKind) -> bool {
+        self.0.contains(&kind)
+    }
+}
+
+/// Groups of window operations which can be allowed.
+#[derive(ConfigDeserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WindowOperationKind {
+    /// Iconify and de-iconify the window.
+    Iconify,
+    /// Move the window.
+    Move,
+    /// Resize the window.
+    Resize,
+    /// Maximize and restore the window.
+    Maximize,
+    /// Enter and leave fullscreen.
+    Fullscreen,
+    /// Report the window state, position and sizes.
+    ReportWindow,
+    /// Report the window title.
+    ReportTitle,
+}
+
+/// Number of columns occupied by East Asian ambiguous characters.
+#[derive(ConfigDeserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AmbiguousWidth {
+    /// Ambiguous characters occupy a single column.
+    Narrow,
+    /// Ambiguous characters occupy two columns, like in CJK contexts.
+    Wide,
+}
+
+impl Default for AmbiguousWidth {
+    fn default() -> Self {
+        AmbiguousWidth::Narrow
+    }
+}
+
+impl AmbiguousWidth {
+    /// Number of columns occupied by a character, `None` for control characters.
+    #[inline]
+    pub fn char_width(self, c: char) -> Option<usize> {
+        match self {
+            Self::Narrow => c.width(),
+            Self::Wide => c.width_cjk(),
+        }
+    }
+}
+
 #[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(untagged)]
 pub enum Program {
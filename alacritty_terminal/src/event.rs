@@ -1,7 +1,9 @@
 use std::borrow::Cow;
 use std::fmt::{self, Debug, Formatter};
+use std::path::PathBuf;
 use std::sync::Arc;
 
+use crate::ansi::WindowOperation;
 use crate::term::color::Rgb;
 use crate::term::{ClipboardType, SizeInfo};
 
@@ -41,12 +43,24 @@ pub enum Event {
     /// Cursor blinking state has changed.
     CursorBlinkingChange(bool),
 
+    /// Working directory reported by the shell has changed.
+    WorkingDirectory(Option<PathBuf>),
+
     /// New terminal content available.
     Wakeup,
 
     /// Terminal bell ring.
     Bell,
 
+    /// Window manipulation or report requested by the application.
+    WindowOperation(WindowOperation),
+
+    /// Mouse cursor shape requested by the application, `None` restores the default shape.
+    MouseCursorShape(Option<String>),
+
+    /// Desktop notification with an optional title and its body.
+    Notification(Option<String>, String),
+
     /// Shutdown request.
     Exit,
 }
@@ -63,8 +77,12 @@ impl Debug for Event {
             Event::PtyWrite(text) => write!(f, "PtyWrite({})", text),
             Event::Wakeup => write!(f, "Wakeup"),
             Event::Bell => write!(f, "Bell"),
+            Event::WindowOperation(operation) => write!(f, "WindowOperation({:?})", operation),
+            Event::MouseCursorShape(shape) => write!(f, "MouseCursorShape({:?})", shape),
+            Event::Notification(title, body) => write!(f, "Notification({:?}, {})", title, body),
             Event::Exit => write!(f, "Exit"),
             Event::CursorBlinkingChange(blinking) => write!(f, "CursorBlinking({})", blinking),
+            Event::WorkingDirectory(path) => write!(f, "WorkingDirectory({:?})", path),
         }
     }
 }
@@ -0,0 +1,635 @@
+//! Kitty graphics protocol.
+//!
+//! Graphics commands are sent in APC sequences starting with `G`, followed by comma separated
+//! `key=value` control data and the base64 encoded payload:
+//!
+//! ```text
+//! ESC _ G <control data> ; <payload> ESC \
+//! ```
+//!
+//! Only direct transmission of the image data is supported.
+
+use std::cmp::{max, min};
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::io::Cursor;
+use std::sync::Arc;
+use std::{mem, str};
+
+use miniz_oxide::inflate::core::inflate_flags;
+use miniz_oxide::inflate::core::{self as inflate, DecompressorOxide};
+use miniz_oxide::inflate::TINFLStatus;
+
+use crate::graphics::{GraphicData, GraphicId, TextureRef, MAX_GRAPHIC_DIMENSIONS};
+
+/// Maximum size of the data transmitted for a single image.
+const MAX_DATA_SIZE: usize = MAX_GRAPHIC_DIMENSIONS * MAX_GRAPHIC_DIMENSIONS * 4;
+
+/// Action requested by a graphics command.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// `t` - Transmit image data.
+    Transmit,
+
+    /// `T` - Transmit image data and display the image.
+    TransmitAndDisplay,
+
+    /// `p` - Display a previously transmitted image.
+    Put,
+
+    /// `d` - Delete placements and images.
+    Delete,
+
+    /// `q` - Check if the image data could be loaded, without storing it.
+    Query,
+}
+
+/// Format of the transmitted image data.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    /// 24-bit RGB pixels.
+    Rgb,
+
+    /// 32-bit RGBA pixels.
+    Rgba,
+
+    /// PNG image.
+    Png,
+}
+
+/// Graphics command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Command {
+    /// Requested action (`a`).
+    pub action: Action,
+
+    /// Suppress responses, `1` for success and `2` for all responses (`q`).
+    pub quiet: u32,
+
+    /// Format of the image data (`f`).
+    pub format: Format,
+
+    /// Transmission medium (`t`).
+    pub medium: u8,
+
+    /// Image data is compressed with zlib (`o`).
+    pub compressed: bool,
+
+    /// Width of the image data in pixels (`s`).
+    pub width: u32,
+
+    /// Height of the image data in pixels (`v`).
+    pub height: u32,
+
+    /// Image identifier (`i`).
+    pub image_id: u32,
+
+    /// Image number (`I`).
+    pub image_number: u32,
+
+    /// Placement identifier (`p`).
+    pub placement_id: u32,
+
+    /// More chunks of image data follow (`m`).
+    pub more: bool,
+
+    /// Left edge of the displayed image area in pixels (`x`).
+    pub source_x: u32,
+
+    /// Top edge of the displayed image area in pixels (`y`).
+    pub source_y: u32,
+
+    /// Width of the displayed image area in pixels (`w`).
+    pub source_width: u32,
+
+    /// Height of the displayed image area in pixels (`h`).
+    pub source_height: u32,
+
+    /// Number of columns the image is displayed over (`c`).
+    pub columns: u32,
+
+    /// Number of lines the image is displayed over (`r`).
+    pub rows: u32,
+
+    /// Move the cursor after displaying the image (`C`).
+    pub move_cursor: bool,
+
+    /// Which placements to delete (`d`).
+    pub delete: u8,
+
+    /// Decoded payload.
+    pub payload: Vec<u8>,
+}
+
+impl Default for Command {
+    fn default() -> Self {
+        Self {
+            action: Action::Transmit,
+            format: Format::Rgba,
+            medium: b'd',
+            move_cursor: true,
+            delete: b'a',
+            compressed: false,
+            more: false,
+            quiet: 0,
+            width: 0,
+            height: 0,
+            image_id: 0,
+            image_number: 0,
+            placement_id: 0,
+            source_x: 0,
+            source_y: 0,
+            source_width: 0,
+            source_height: 0,
+            columns: 0,
+            rows: 0,
+            payload: Vec::new(),
+        }
+    }
+}
+
+impl Command {
+    /// Parse a graphics command from its APC data, without the leading `G`.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let mut split = data.splitn(2, |&byte| byte == b';');
+        let control = split.next()?;
+        let payload = split.next().unwrap_or_default();
+
+        let mut command = Command::default();
+
+        for param in control.split(|&byte| byte == b',').filter(|param| !param.is_empty()) {
+            let (key, value) = match param {
+                [key, b'=', value @ ..] if !value.is_empty() => (*key, value),
+                _ => return None,
+            };
+
+            let number = || str::from_utf8(value).ok()?.parse::<u32>().ok();
+            let character = || if value.len() == 1 { Some(value[0]) } else { None };
+
+            match key {
+                b'a' => {
+                    command.action = match character()? {
+                        b't' => Action::Transmit,
+                        b'T' => Action::TransmitAndDisplay,
+                        b'p' => Action::Put,
+                        b'd' => Action::Delete,
+                        b'q' => Action::Query,
+                        _ => return None,
+                    }
+                },
+                b'f' => {
+                    command.format = match number()? {
+                        24 => Format::Rgb,
+                        32 => Format::Rgba,
+                        100 => Format::Png,
+                        _ => return None,
+                    }
+                },
+                b'q' => command.quiet = number()?,
+                b't' => command.medium = character()?,
+                b'o' => command.compressed = character()? == b'z',
+                b's' => command.width = number()?,
+                b'v' => command.height = number()?,
+                b'i' => command.image_id = number()?,
+                b'I' => command.image_number = number()?,
+                b'p' => command.placement_id = number()?,
+                b'm' => command.more = number()? == 1,
+                b'x' => command.source_x = number()?,
+                b'y' => command.source_y = number()?,
+                b'w' => command.source_width = number()?,
+                b'h' => command.source_height = number()?,
+                b'c' => command.columns = number()?,
+                b'r' => command.rows = number()?,
+                b'C' => command.move_cursor = number()? != 1,
+                b'd' => command.delete = character()?,
+                // Ignore unsupported keys, like the z-index or animation controls.
+                _ => (),
+            }
+        }
+
+        command.payload = base64::decode(payload).ok()?;
+
+        Some(command)
+    }
+
+    /// Decode the transmitted image data.
+    pub fn decode(&mut self) -> Result<GraphicData, Error> {
+        if self.medium != b'd' {
+            return Err(Error::new("EINVAL", "unsupported transmission medium"));
+        }
+
+        if self.payload.len() > MAX_DATA_SIZE {
+            return Err(Error::new("EFBIG", "image data too large"));
+        }
+
+        let (width, height) = (self.width as usize, self.height as usize);
+        let expected_size = match self.format {
+            Format::Rgb => Some(pixel_data_size(width, height, 3)?),
+            Format::Rgba => Some(pixel_data_size(width, height, 4)?),
+            Format::Png => None,
+        };
+
+        let data = if self.compressed {
+            inflate_zlib(&self.payload, expected_size.unwrap_or(MAX_DATA_SIZE))?
+        } else {
+            mem::take(&mut self.payload)
+        };
+
+        match self.format {
+            Format::Png => decode_png(&data),
+            _ if width == 0 || height == 0 => Err(Error::new("EINVAL", "missing image size")),
+            _ if data.len() < expected_size.unwrap_or_default() => {
+                Err(Error::new("ENODATA", "insufficient image data"))
+            },
+            Format::Rgb => {
+                let pixels = rgba_pixels(&data[..width * height * 3], 3);
+                Ok(GraphicData { id: GraphicId(0), width, height, pixels })
+            },
+            Format::Rgba => {
+                let mut pixels = data;
+                pixels.truncate(width * height * 4);
+                Ok(GraphicData { id: GraphicId(0), width, height, pixels })
+            },
+        }
+    }
+}
+
+/// Failure of a graphics command, reported back to the client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    /// Error code, named after the closest POSIX error.
+    code: &'static str,
+
+    /// Description of the error.
+    message: &'static str,
+}
+
+impl Error {
+    pub fn new(code: &'static str, message: &'static str) -> Self {
+        Self { code, message }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.code, self.message)
+    }
+}
+
+/// Size in bytes of the raw pixel data for an image.
+fn pixel_data_size(width: usize, height: usize, bytes_per_pixel: usize) -> Result<usize, Error> {
+    let too_large = || Error::new("EFBIG", "image dimensions too large");
+
+    if width > MAX_GRAPHIC_DIMENSIONS || height > MAX_GRAPHIC_DIMENSIONS {
+        return Err(too_large());
+    }
+
+    width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(bytes_per_pixel))
+        .filter(|&size| size <= MAX_DATA_SIZE)
+        .ok_or_else(too_large)
+}
+
+/// Decompress zlib data, failing if it exceeds `max_size` bytes.
+fn inflate_zlib(data: &[u8], max_size: usize) -> Result<Vec<u8>, Error> {
+    let mut output = vec![0; min(max(data.len() * 2, 64), max_size)];
+    let mut decompressor = DecompressorOxide::new();
+    let flags = inflate_flags::TINFL_FLAG_PARSE_ZLIB_HEADER
+        | inflate_flags::TINFL_FLAG_USING_NON_WRAPPING_OUTPUT_BUF;
+
+    let (mut input_pos, mut output_pos) = (0, 0);
+    loop {
+        // The whole buffer is passed every time, since matches may refer to earlier output.
+        let mut cursor = Cursor::new(output.as_mut_slice());
+        cursor.set_position(output_pos as u64);
+
+        let (status, consumed, written) =
+            inflate::decompress(&mut decompressor, &data[input_pos..], &mut cursor, flags);
+        input_pos += consumed;
+        output_pos += written;
+
+        match status {
+            TINFLStatus::Done => {
+                output.truncate(output_pos);
+                return Ok(output);
+            },
+            // Grow the buffer until the size limit is reached.
+            TINFLStatus::HasMoreOutput if output.len() < max_size => {
+                let len = min(output.len() * 2, max_size);
+                output.resize(len, 0);
+            },
+            TINFLStatus::HasMoreOutput => {
+                return Err(Error::new("EFBIG", "decompressed data too large"));
+            },
+            _ => return Err(Error::new("EINVAL", "invalid compressed data")),
+        }
+    }
+}
+
+/// Decode a PNG image to RGBA pixels.
+fn decode_png(data: &[u8]) -> Result<GraphicData, Error> {
+    let invalid = |_| Error::new("EBADPNG", "invalid PNG data");
+
+    let limits = png::Limits { bytes: MAX_DATA_SIZE };
+    let mut decoder = png::Decoder::new_with_limits(data, limits);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let (info, mut reader) = decoder.read_info().map_err(invalid)?;
+
+    let (width, height) = (info.width as usize, info.height as usize);
+    if width > MAX_GRAPHIC_DIMENSIONS || height > MAX_GRAPHIC_DIMENSIONS {
+        return Err(Error::new("EFBIG", "image dimensions too large"));
+    }
+
+    let mut buffer = vec![0; info.buffer_size()];
+    reader.next_frame(&mut buffer).map_err(invalid)?;
+
+    let pixels = match reader.output_color_type().0 {
+        png::ColorType::RGBA => buffer,
+        png::ColorType::RGB => rgba_pixels(&buffer, 3),
+        png::ColorType::GrayscaleAlpha => rgba_pixels(&buffer, 2),
+        png::ColorType::Grayscale => rgba_pixels(&buffer, 1),
+        png::ColorType::Indexed => return Err(Error::new("EBADPNG", "unsupported color type")),
+    };
+
+    Ok(GraphicData { id: GraphicId(0), width, height, pixels })
+}
+
+/// Convert RGB, grayscale or grayscale with alpha pixels to RGBA.
+fn rgba_pixels(data: &[u8], channels: usize) -> Vec<u8> {
+    let mut pixels = Vec::with_capacity(data.len() / channels * 4);
+    for pixel in data.chunks_exact(channels) {
+        match *pixel {
+            [r, g, b] => pixels.extend_from_slice(&[r, g, b, 255]),
+            [gray, alpha] => pixels.extend_from_slice(&[gray, gray, gray, alpha]),
+            _ => pixels.extend_from_slice(&[pixel[0], pixel[0], pixel[0], 255]),
+        }
+    }
+    pixels
+}
+
+/// Image transmitted by the client.
+#[derive(Debug)]
+pub struct Image {
+    /// Texture of the image.
+    pub texture: Arc<TextureRef>,
+
+    /// Image number assigned by the client.
+    pub number: u32,
+
+    /// Transmission order, used to find the newest image for a number.
+    serial: u64,
+}
+
+/// Storage for images transmitted with the kitty graphics protocol.
+#[derive(Debug, Default)]
+pub struct ImageStore {
+    /// Transmitted images, by their identifier.
+    images: HashMap<u32, Image>,
+
+    /// Chunked transmission waiting for more data.
+    pending: Option<Command>,
+
+    /// Last assigned transmission order.
+    last_serial: u64,
+
+    /// Last identifier assigned to images without an explicit ID.
+    last_id: u32,
+}
+
+impl ImageStore {
+    /// Collect chunked transmissions.
+    ///
+    /// Returns the command once all of its chunks have been received.
+    pub fn add_chunk(&mut self, command: Command) -> Option<Command> {
+        let mut command = match self.pending.take() {
+            // Continuation chunks only carry the payload and chunk state.
+            Some(mut pending) => {
+                if pending.payload.len() <= MAX_DATA_SIZE {
+                    pending.payload.extend_from_slice(&command.payload);
+                }
+                pending.more = command.more;
+                pending.quiet = command.quiet.max(pending.quiet);
+                pending
+            },
+            None => command,
+        };
+
+        if command.more {
+            // Don't keep image data around for commands which have no use for it.
+            if command.action == Action::Put || command.action == Action::Delete {
+                command.payload.clear();
+            }
+
+            self.pending = Some(command);
+            None
+        } else {
+            Some(command)
+        }
+    }
+
+    /// Store a new image, replacing images with the same ID.
+    pub fn insert(&mut self, id: u32, number: u32, texture: Arc<TextureRef>) {
+        self.last_serial += 1;
+        self.images.insert(id, Image { texture, number, serial: self.last_serial });
+    }
+
+    /// Get an image by its ID.
+    pub fn get(&self, id: u32) -> Option<&Image> {
+        self.images.get(&id)
+    }
+
+    /// Find the ID of the newest image with the specified number.
+    pub fn find_number(&self, number: u32) -> Option<u32> {
+        self.images
+            .iter()
+            .filter(|(_, image)| image.number == number)
+            .max_by_key(|(_, image)| image.serial)
+            .map(|(id, _)| *id)
+    }
+
+    /// Generate an ID which is not used by any image.
+    pub fn next_free_id(&mut self) -> u32 {
+        loop {
+            self.last_id = self.last_id.wrapping_add(1).max(1);
+            if !self.images.contains_key(&self.last_id) {
+                return self.last_id;
+            }
+        }
+    }
+
+    /// Remove an image.
+    pub fn remove(&mut self, id: u32) {
+        self.images.remove(&id);
+    }
+
+    /// Remove images which are not displayed anywhere, if they are part of `textures`.
+    pub fn remove_unused(&mut self, textures: &[GraphicId]) {
+        self.images.retain(|_, image| {
+            Arc::strong_count(&image.texture) > 1 || !textures.contains(&image.texture.id)
+        });
+    }
+
+    /// Remove the oldest image which is not displayed anywhere.
+    ///
+    /// Returns `false` if there was no image to remove.
+    pub fn evict_unused(&mut self) -> bool {
+        let oldest = self
+            .images
+            .iter()
+            .filter(|(_, image)| Arc::strong_count(&image.texture) == 1)
+            .min_by_key(|(_, image)| image.serial)
+            .map(|(id, _)| *id);
+
+        match oldest {
+            Some(id) => {
+                self.images.remove(&id);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Check if a texture belongs to an image of this store.
+    pub fn contains_texture(&self, texture: GraphicId) -> bool {
+        self.images.values().any(|image| image.texture.id == texture)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::graphics::Graphics;
+
+    /// 2x1 RGB PNG with a red and a blue pixel.
+    const PNG_IMAGE: &[u8] = b"iVBORw0KGgoAAAANSUhEUgAAAAIAAAABCAIAAAB7QOjdAAAADUlEQVR4nGP4zwAE/wEHAAH/4iOeWQAAAABJRU5ErkJggg==";
+
+    fn command(control: &str, payload: &[u8]) -> Command {
+        let mut data = control.as_bytes().to_vec();
+        data.push(b';');
+        data.extend_from_slice(payload);
+        Command::parse(&data).unwrap()
+    }
+
+    #[test]
+    fn parse_control_data() {
+        let command = command("a=T,f=24,s=2,v=1,i=31,p=7,c=4,r=2,C=1,q=2,m=1", b"AQIDBAUG");
+
+        assert_eq!(command.action, Action::TransmitAndDisplay);
+        assert_eq!(command.format, Format::Rgb);
+        assert_eq!((command.width, command.height), (2, 1));
+        assert_eq!((command.image_id, command.placement_id), (31, 7));
+        assert_eq!((command.columns, command.rows), (4, 2));
+        assert!(!command.move_cursor);
+        assert!(command.more);
+        assert_eq!(command.quiet, 2);
+        assert_eq!(command.payload, vec![1, 2, 3, 4, 5, 6]);
+
+        let command = Command::parse(b"a=d,d=I,i=3").unwrap();
+        assert_eq!(command.action, Action::Delete);
+        assert_eq!(command.delete, b'I');
+        assert!(command.payload.is_empty());
+
+        assert_eq!(Command::parse(b"a=f,i=3"), None);
+        assert_eq!(Command::parse(b"i=x"), None);
+        assert_eq!(Command::parse(b"i=1;!!!"), None);
+    }
+
+    #[test]
+    fn decode_pixels() {
+        let mut rgb = command("f=24,s=2,v=1", b"AQIDBAUG");
+        let graphic = rgb.decode().unwrap();
+        assert_eq!(graphic.pixels, vec![1, 2, 3, 255, 4, 5, 6, 255]);
+
+        let mut rgba = command("s=1,v=2", b"AQIDBAUGBwg=");
+        let graphic = rgba.decode().unwrap();
+        assert_eq!((graphic.width, graphic.height), (1, 2));
+        assert_eq!(graphic.pixels, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+
+        let mut compressed = command("f=24,s=2,v=1,o=z", b"eJxjZGJmYWUDAAA+ABY=");
+        let graphic = compressed.decode().unwrap();
+        assert_eq!(graphic.pixels, vec![1, 2, 3, 255, 4, 5, 6, 255]);
+
+        let mut png = command("f=100", PNG_IMAGE);
+        let graphic = png.decode().unwrap();
+        assert_eq!((graphic.width, graphic.height), (2, 1));
+        assert_eq!(graphic.pixels, vec![255, 0, 0, 255, 0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn decode_errors() {
+        let mut missing_data = command("f=24,s=2,v=2", b"AQIDBAUG");
+        assert_eq!(missing_data.decode().unwrap_err().code, "ENODATA");
+
+        let mut missing_size = command("f=32", b"AQIDBAUGBwg=");
+        assert_eq!(missing_size.decode().unwrap_err().code, "EINVAL");
+
+        let mut file = command("t=f,f=100", b"L3RtcC9pbWFnZS5wbmc=");
+        assert_eq!(file.decode().unwrap_err().code, "EINVAL");
+
+        // Compressed data exceeding the announced image size.
+        let mut too_large = command("f=24,s=1,v=1,o=z", b"eJxjZGJmYWUDAAA+ABY=");
+        assert_eq!(too_large.decode().unwrap_err().code, "EFBIG");
+
+        let mut png = command("f=100", b"AQIDBAUG");
+        assert_eq!(png.decode().unwrap_err().code, "EBADPNG");
+    }
+
+    #[test]
+    fn decode_oversized_dimensions() {
+        let mut huge = command("s=4294967295,v=4294967295", b"AQIDBAUGBwg=");
+        assert_eq!(huge.decode().unwrap_err().code, "EFBIG");
+
+        let mut compressed = command("f=24,s=65535,v=65535,o=z", b"eJxjZGJmYWUDAAA+ABY=");
+        assert_eq!(compressed.decode().unwrap_err().code, "EFBIG");
+
+        let mut wide = command("f=24,s=4097,v=1", b"AQIDBAUG");
+        assert_eq!(wide.decode().unwrap_err().code, "EFBIG");
+    }
+
+    #[test]
+    fn chunked_transmission() {
+        let mut store = ImageStore::default();
+
+        assert_eq!(store.add_chunk(command("a=T,f=24,s=2,v=1,i=1,m=1", b"AQID")), None);
+        let complete = store.add_chunk(command("m=0", b"BAUG")).unwrap();
+
+        assert_eq!(complete.action, Action::TransmitAndDisplay);
+        assert_eq!(complete.image_id, 1);
+        assert_eq!(complete.payload, vec![1, 2, 3, 4, 5, 6]);
+        assert!(!complete.more);
+    }
+
+    #[test]
+    fn store_images() {
+        let mut graphics = Graphics::default();
+        let mut store = ImageStore::default();
+        let mut graphic = || {
+            let graphic = GraphicData { id: GraphicId(0), width: 1, height: 1, pixels: vec![0; 4] };
+            graphics.insert(graphic).unwrap()
+        };
+
+        store.insert(1, 5, graphic());
+        store.insert(2, 5, graphic());
+        store.insert(3, 0, graphic());
+        assert_eq!(store.find_number(5), Some(2));
+
+        let id = store.next_free_id();
+        assert!(id > 3);
+
+        // Displayed images are never evicted.
+        let displayed = store.get(1).unwrap().texture.clone();
+        assert!(store.evict_unused());
+        assert!(store.get(1).is_some());
+        assert!(store.get(2).is_none());
+
+        store.remove_unused(&[displayed.id]);
+        assert!(store.get(1).is_some());
+        drop(displayed);
+        let texture = store.get(1).unwrap().texture.id;
+        store.remove_unused(&[texture]);
+        assert!(store.get(1).is_none());
+        assert!(store.get(3).is_some());
+    }
+}
@@ -0,0 +1,321 @@
+//! Graphics attached to the terminal grid.
+//!
+//! Decoded images are split into cell-sized tiles, which are stored in the grid cells. This
+//! allows images to scroll with the text and to be dropped with the lines holding them.
+
+use std::cmp::min;
+use std::mem;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+pub mod kitty;
+#[cfg(feature = "sixel")]
+pub mod sixel;
+
+/// Maximum width and height of a single graphic in pixels.
+pub const MAX_GRAPHIC_DIMENSIONS: usize = 4096;
+
+/// Maximum number of bytes used by all graphics in the grid.
+///
+/// New graphics are rejected once this limit is reached.
+pub const MAX_GRAPHICS_MEMORY: usize = 256 * 1024 * 1024;
+
+/// Unique identifier for every graphic added to a terminal.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Hash, Copy, Clone, Debug)]
+pub struct GraphicId(pub u64);
+
+/// Decoded graphic, ready to be uploaded to the GPU.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct GraphicData {
+    /// Graphic identifier.
+    pub id: GraphicId,
+
+    /// Width in pixels.
+    pub width: usize,
+
+    /// Height in pixels.
+    pub height: usize,
+
+    /// Pixels in RGBA format, line by line.
+    pub pixels: Vec<u8>,
+}
+
+/// Texture of a graphic referenced by grid cells.
+///
+/// The texture is queued for deletion once the last cell referencing it is dropped.
+#[derive(Debug)]
+pub struct TextureRef {
+    /// Graphic identifier.
+    pub id: GraphicId,
+
+    /// Width of the graphic in pixels.
+    pub width: usize,
+
+    /// Height of the graphic in pixels.
+    pub height: usize,
+
+    /// Shared state for releasing the graphic.
+    tracker: Arc<Mutex<Tracker>>,
+}
+
+impl TextureRef {
+    /// Memory used by the graphic's pixels.
+    fn size(&self) -> usize {
+        self.width * self.height * 4
+    }
+}
+
+impl Drop for TextureRef {
+    fn drop(&mut self) {
+        let mut tracker = self.tracker.lock();
+        tracker.used_memory = tracker.used_memory.saturating_sub(self.size());
+        tracker.removed.push(self.id);
+    }
+}
+
+/// Graphic tile stored in a grid cell.
+#[derive(Clone, Debug)]
+pub struct GraphicCell {
+    /// Texture of the graphic.
+    pub texture: Arc<TextureRef>,
+
+    /// Identifier of the placement this cell belongs to, `0` if it has none.
+    pub placement_id: u32,
+
+    /// Horizontal position of the cell in the graphic, in pixels.
+    pub offset_x: u16,
+
+    /// Vertical position of the cell in the graphic, in pixels.
+    pub offset_y: u16,
+
+    /// Width of the graphic area displayed in the cell, in pixels.
+    pub width: u16,
+
+    /// Height of the graphic area displayed in the cell, in pixels.
+    pub height: u16,
+}
+
+impl PartialEq for GraphicCell {
+    fn eq(&self, other: &Self) -> bool {
+        self.texture.id == other.texture.id
+            && self.placement_id == other.placement_id
+            && self.offset_x == other.offset_x
+            && self.offset_y == other.offset_y
+            && self.width == other.width
+            && self.height == other.height
+    }
+}
+
+impl Eq for GraphicCell {}
+
+/// Area of a graphic displayed in the grid.
+#[derive(Clone, Debug)]
+pub struct Placement {
+    /// Texture of the graphic.
+    pub texture: Arc<TextureRef>,
+
+    /// Identifier of the placement, `0` if it has none.
+    pub id: u32,
+
+    /// Horizontal start of the displayed area, in pixels.
+    pub x: usize,
+
+    /// Vertical start of the displayed area, in pixels.
+    pub y: usize,
+
+    /// Width of the displayed area in pixels, this may exceed the graphic.
+    pub width: usize,
+
+    /// Height of the displayed area in pixels, this may exceed the graphic.
+    pub height: usize,
+
+    /// Number of columns the displayed area is scaled to.
+    pub columns: usize,
+
+    /// Number of lines the displayed area is scaled to.
+    pub lines: usize,
+}
+
+impl Placement {
+    /// Graphic tile for a cell of the placement.
+    pub fn cell(&self, column: usize, line: usize) -> GraphicCell {
+        let x = self.x + column * self.width / self.columns;
+        let y = self.y + line * self.height / self.lines;
+        let x_end = self.x + (column + 1) * self.width / self.columns;
+        let y_end = self.y + (line + 1) * self.height / self.lines;
+
+        let clamp = |value: usize| min(value, usize::from(u16::MAX)) as u16;
+        GraphicCell {
+            texture: self.texture.clone(),
+            placement_id: self.id,
+            offset_x: clamp(x),
+            offset_y: clamp(y),
+            width: clamp(x_end - x),
+            height: clamp(y_end - y),
+        }
+    }
+}
+
+/// Changes to the graphics which need to be applied by the renderer.
+#[derive(Debug, Default)]
+pub struct UpdateQueues {
+    /// Graphics which need to be uploaded.
+    pub pending: Vec<GraphicData>,
+
+    /// Graphics which are no longer referenced by any cell.
+    pub remove_queue: Vec<GraphicId>,
+}
+
+/// State shared between the terminal and its graphic textures.
+#[derive(Debug, Default)]
+struct Tracker {
+    /// Graphics which have been dropped since the last update.
+    removed: Vec<GraphicId>,
+
+    /// Memory used by all graphics which are still referenced.
+    used_memory: usize,
+}
+
+/// Graphics of a terminal.
+#[derive(Debug, Default)]
+pub struct Graphics {
+    /// Last generated identifier.
+    last_id: u64,
+
+    /// Graphics waiting to be uploaded by the renderer.
+    pending: Vec<GraphicData>,
+
+    /// Shared state for releasing graphics.
+    tracker: Arc<Mutex<Tracker>>,
+}
+
+impl Graphics {
+    /// Register a new graphic.
+    ///
+    /// Returns the texture reference which should be stored in the grid, or `None` if the graphic
+    /// would exceed the memory limit.
+    pub fn insert(&mut self, mut graphic: GraphicData) -> Option<Arc<TextureRef>> {
+        let size = graphic.width * graphic.height * 4;
+
+        {
+            let mut tracker = self.tracker.lock();
+            if tracker.used_memory + size > MAX_GRAPHICS_MEMORY {
+                return None;
+            }
+            tracker.used_memory += size;
+        }
+
+        self.last_id += 1;
+        graphic.id = GraphicId(self.last_id);
+
+        let texture = Arc::new(TextureRef {
+            id: graphic.id,
+            width: graphic.width,
+            height: graphic.height,
+            tracker: self.tracker.clone(),
+        });
+
+        self.pending.push(graphic);
+
+        Some(texture)
+    }
+
+    /// Memory used by all graphics which are still referenced.
+    pub fn used_memory(&self) -> usize {
+        self.tracker.lock().used_memory
+    }
+
+    /// Take all changes which need to be applied by the renderer.
+    pub fn take_queues(&mut self) -> Option<UpdateQueues> {
+        let removed = mem::take(&mut self.tracker.lock().removed);
+        let mut pending = mem::take(&mut self.pending);
+
+        // Skip graphics which have been removed before they were ever uploaded.
+        let (skipped, remove_queue): (Vec<_>, Vec<_>) =
+            removed.into_iter().partition(|id| pending.iter().any(|graphic| graphic.id == *id));
+        pending.retain(|graphic| !skipped.contains(&graphic.id));
+
+        if pending.is_empty() && remove_queue.is_empty() {
+            None
+        } else {
+            Some(UpdateQueues { pending, remove_queue })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graphic(width: usize, height: usize) -> GraphicData {
+        GraphicData { id: GraphicId(0), width, height, pixels: Vec::new() }
+    }
+
+    #[test]
+    fn release_dropped_graphics() {
+        let mut graphics = Graphics::default();
+
+        let first = graphics.insert(graphic(10, 10)).unwrap();
+        let second = graphics.insert(graphic(20, 10)).unwrap();
+        assert_ne!(first.id, second.id);
+        assert_eq!(graphics.used_memory(), 1200);
+
+        let queues = graphics.take_queues().unwrap();
+        assert_eq!(queues.pending.len(), 2);
+        assert!(queues.remove_queue.is_empty());
+        assert!(graphics.take_queues().is_none());
+
+        let first_id = first.id;
+        drop(first);
+        assert_eq!(graphics.used_memory(), 800);
+
+        let queues = graphics.take_queues().unwrap();
+        assert!(queues.pending.is_empty());
+        assert_eq!(queues.remove_queue, vec![first_id]);
+    }
+
+    #[test]
+    fn skip_removed_pending_graphics() {
+        let mut graphics = Graphics::default();
+
+        drop(graphics.insert(graphic(10, 10)));
+
+        assert!(graphics.take_queues().is_none());
+        assert_eq!(graphics.used_memory(), 0);
+    }
+
+    #[test]
+    fn graphics_memory_limit() {
+        let mut graphics = Graphics::default();
+        let width = MAX_GRAPHIC_DIMENSIONS;
+
+        let mut textures = Vec::new();
+        while let Some(texture) = graphics.insert(graphic(width, width)) {
+            textures.push(texture);
+        }
+
+        assert_eq!(textures.len(), MAX_GRAPHICS_MEMORY / (width * width * 4));
+
+        textures.pop();
+        assert!(graphics.insert(graphic(width, width)).is_some());
+    }
+
+    #[test]
+    fn placement_cells() {
+        let mut graphics = Graphics::default();
+        let texture = graphics.insert(graphic(10, 10)).unwrap();
+
+        // Area of 10x5 pixels, scaled to three columns and two lines.
+        let placement =
+            Placement { texture, id: 3, x: 0, y: 5, width: 10, height: 5, columns: 3, lines: 2 };
+
+        let cell = placement.cell(0, 0);
+        assert_eq!((cell.offset_x, cell.offset_y, cell.width, cell.height), (0, 5, 3, 2));
+        let cell = placement.cell(1, 1);
+        assert_eq!((cell.offset_x, cell.offset_y, cell.width, cell.height), (3, 7, 3, 3));
+        let cell = placement.cell(2, 1);
+        assert_eq!((cell.offset_x, cell.offset_y, cell.width, cell.height), (6, 7, 4, 3));
+        assert_eq!(cell.placement_id, 3);
+    }
+}
@@ -0,0 +1,404 @@
+//! Decoder for sixel graphics.
+//!
+//! Sixel images are transmitted in a DCS sequence with the `q` final byte. Every data byte
+//! encodes a column of six vertical pixels, which are drawn with the active color register.
+//!
+//! The pixel aspect ratio requested by the sequence parameters is ignored, since current
+//! encoders only generate images with square pixels.
+
+use std::cmp::{max, min};
+use std::mem;
+
+use crate::graphics::{GraphicData, GraphicId, MAX_GRAPHIC_DIMENSIONS};
+use crate::term::color::Rgb;
+
+/// Number of color registers available to an image.
+const MAX_COLOR_REGISTERS: usize = 1024;
+
+/// Maximum number of parameters for a sixel command.
+const MAX_COMMAND_PARAMS: usize = 5;
+
+/// Default color registers, matching the VT340 palette.
+const DEFAULT_COLOR_REGISTERS: [Rgb; 16] = [
+    Rgb { r: 0, g: 0, b: 0 },
+    Rgb { r: 51, g: 51, b: 204 },
+    Rgb { r: 204, g: 33, b: 33 },
+    Rgb { r: 51, g: 204, b: 51 },
+    Rgb { r: 204, g: 51, b: 204 },
+    Rgb { r: 51, g: 204, b: 204 },
+    Rgb { r: 204, g: 204, b: 51 },
+    Rgb { r: 135, g: 135, b: 135 },
+    Rgb { r: 66, g: 66, b: 66 },
+    Rgb { r: 84, g: 84, b: 153 },
+    Rgb { r: 153, g: 66, b: 66 },
+    Rgb { r: 84, g: 153, b: 84 },
+    Rgb { r: 153, g: 84, b: 153 },
+    Rgb { r: 84, g: 153, b: 153 },
+    Rgb { r: 153, g: 153, b: 84 },
+    Rgb { r: 204, g: 204, b: 204 },
+];
+
+/// Sixel command which accepts parameters.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Command {
+    /// No command is active.
+    None,
+
+    /// `"` - Set the raster attributes.
+    RasterAttributes,
+
+    /// `!` - Repeat the next sixel.
+    Repeat,
+
+    /// `#` - Select or define a color register.
+    ColorIntroducer,
+}
+
+/// Sixel graphics decoder.
+#[derive(Debug)]
+pub struct Parser {
+    /// Command waiting for its parameters.
+    command: Command,
+
+    /// Parameters of the active command.
+    params: Vec<u16>,
+
+    /// Available color registers.
+    color_registers: Vec<Rgb>,
+
+    /// Color register used for drawing.
+    active_color: usize,
+
+    /// Pixels in RGBA format, with `stride` pixels per line.
+    ///
+    /// Pixels which have not been drawn are fully transparent.
+    pixels: Vec<u8>,
+
+    /// Number of pixels allocated for every line.
+    stride: usize,
+
+    /// Width of the image.
+    width: usize,
+
+    /// Height of the image.
+    height: usize,
+
+    /// Horizontal position of the next sixel.
+    x: usize,
+
+    /// Top of the current sixel line.
+    y: usize,
+
+    /// Keep pixels which have not been drawn transparent.
+    transparent_background: bool,
+}
+
+impl Parser {
+    /// Create a new decoder.
+    ///
+    /// Pixels which are never drawn are filled with color register 0, unless
+    /// `transparent_background` is set.
+    pub fn new(transparent_background: bool) -> Self {
+        let mut color_registers = DEFAULT_COLOR_REGISTERS.to_vec();
+        color_registers.resize(MAX_COLOR_REGISTERS, Rgb::default());
+
+        Self {
+            transparent_background,
+            color_registers,
+            command: Command::None,
+            params: Vec::new(),
+            active_color: 0,
+            pixels: Vec::new(),
+            stride: 0,
+            width: 0,
+            height: 0,
+            x: 0,
+            y: 0,
+        }
+    }
+
+    /// Process the next byte of sixel data.
+    pub fn put(&mut self, byte: u8) {
+        match byte {
+            b'0'..=b'9' if self.command != Command::None => {
+                if self.params.is_empty() {
+                    self.params.push(0);
+                }
+
+                let param = self.params.last_mut().unwrap();
+                *param = param.saturating_mul(10).saturating_add(u16::from(byte - b'0'));
+            },
+            b';' if self.command != Command::None => {
+                if self.params.is_empty() {
+                    self.params.push(0);
+                }
+
+                if self.params.len() < MAX_COMMAND_PARAMS {
+                    self.params.push(0);
+                }
+            },
+            _ => {
+                let repeat = self.finish_command();
+
+                match byte {
+                    b'"' => self.command = Command::RasterAttributes,
+                    b'!' => self.command = Command::Repeat,
+                    b'#' => self.command = Command::ColorIntroducer,
+                    b'$' => self.x = 0,
+                    b'-' => {
+                        self.x = 0;
+                        self.y += 6;
+                    },
+                    b'?'..=b'~' => self.draw_sixel(byte - b'?', repeat),
+                    _ => (),
+                }
+            },
+        }
+    }
+
+    /// Complete decoding of the image.
+    ///
+    /// Returns `None` if the image is empty.
+    pub fn finish(mut self) -> Option<GraphicData> {
+        self.finish_command();
+
+        if self.width == 0 || self.height == 0 {
+            return None;
+        }
+
+        // Crop the pixel buffer, the raster area might also exceed the drawn pixels.
+        let mut pixels = vec![0; self.width * self.height * 4];
+        let line_len = min(self.width, self.stride) * 4;
+        if line_len > 0 {
+            for (line, buffer) in self.pixels.chunks(self.stride * 4).take(self.height).enumerate()
+            {
+                let offset = line * self.width * 4;
+                pixels[offset..offset + line_len].copy_from_slice(&buffer[..line_len]);
+            }
+        }
+
+        if !self.transparent_background {
+            let background = self.color_registers[0];
+
+            for pixel in pixels.chunks_mut(4).filter(|pixel| pixel[3] == 0) {
+                pixel.copy_from_slice(&[background.r, background.g, background.b, 255]);
+            }
+        }
+
+        Some(GraphicData { id: GraphicId(0), width: self.width, height: self.height, pixels })
+    }
+
+    /// Apply the pending command.
+    ///
+    /// Returns the number of times the next sixel should be drawn.
+    fn finish_command(&mut self) -> usize {
+        let params = mem::take(&mut self.params);
+        let param = |index: usize| params.get(index).copied().unwrap_or(0);
+
+        match mem::replace(&mut self.command, Command::None) {
+            Command::None => 1,
+            Command::Repeat => max(usize::from(param(0)), 1),
+            Command::RasterAttributes => {
+                // Parameters are pixel aspect ratio numerator and denominator, width and height.
+                let width = min(usize::from(param(2)), MAX_GRAPHIC_DIMENSIONS);
+                let height = min(usize::from(param(3)), MAX_GRAPHIC_DIMENSIONS);
+                self.width = max(self.width, width);
+                self.height = max(self.height, height);
+                1
+            },
+            Command::ColorIntroducer => {
+                let register = usize::from(param(0));
+                if register >= MAX_COLOR_REGISTERS {
+                    return 1;
+                }
+
+                if params.len() >= 5 {
+                    let color = match param(1) {
+                        1 => Some(hls_to_rgb(param(2), param(3), param(4))),
+                        2 => Some(Rgb {
+                            r: percent_to_byte(param(2)),
+                            g: percent_to_byte(param(3)),
+                            b: percent_to_byte(param(4)),
+                        }),
+                        _ => None,
+                    };
+
+                    if let Some(color) = color {
+                        self.color_registers[register] = color;
+                    }
+                }
+
+                self.active_color = register;
+                1
+            },
+        }
+    }
+
+    /// Draw a sixel `repeat` times, using the active color.
+    fn draw_sixel(&mut self, sixel: u8, repeat: usize) {
+        let start = self.x;
+        self.x = start.saturating_add(repeat);
+
+        let end = min(self.x, MAX_GRAPHIC_DIMENSIONS);
+        if sixel == 0 || start >= end || self.y >= MAX_GRAPHIC_DIMENSIONS {
+            self.width = max(self.width, end);
+            return;
+        }
+
+        let lines = min(6, MAX_GRAPHIC_DIMENSIONS - self.y);
+        self.reserve(end, self.y + lines);
+
+        let color = self.color_registers[self.active_color];
+        let rgba = [color.r, color.g, color.b, 255];
+        for bit in (0..lines).filter(|bit| sixel & (1 << bit) != 0) {
+            let offset = ((self.y + bit) * self.stride + start) * 4;
+            for pixel in self.pixels[offset..offset + (end - start) * 4].chunks_mut(4) {
+                pixel.copy_from_slice(&rgba);
+            }
+
+            self.height = max(self.height, self.y + bit + 1);
+        }
+
+        self.width = max(self.width, end);
+    }
+
+    /// Grow the pixel buffer to fit at least `width` x `height` pixels.
+    fn reserve(&mut self, width: usize, height: usize) {
+        if width > self.stride {
+            // Grow exponentially, to avoid moving all pixels for every new column.
+            let stride = min(max(width, self.stride * 2), MAX_GRAPHIC_DIMENSIONS);
+            let line_len = self.stride * 4;
+            let lines = self.pixels.len().checked_div(line_len).unwrap_or(0);
+
+            let mut pixels = vec![0; lines * stride * 4];
+            for line in 0..lines {
+                let old_line = &self.pixels[line * line_len..(line + 1) * line_len];
+                pixels[line * stride * 4..][..line_len].copy_from_slice(old_line);
+            }
+
+            self.pixels = pixels;
+            self.stride = stride;
+        }
+
+        if self.pixels.len() < height * self.stride * 4 {
+            self.pixels.resize(height * self.stride * 4, 0);
+        }
+    }
+}
+
+/// Convert a percentage to a color channel value.
+fn percent_to_byte(percent: u16) -> u8 {
+    (u32::from(min(percent, 100)) * 255 / 100) as u8
+}
+
+/// Convert a sixel HLS color to RGB.
+///
+/// Sixel hues are rotated compared to the usual HLS color space, with blue at 0 degrees.
+fn hls_to_rgb(hue: u16, lightness: u16, saturation: u16) -> Rgb {
+    let hue = f32::from((hue % 360 + 240) % 360);
+    let lightness = f32::from(min(lightness, 100)) / 100.;
+    let saturation = f32::from(min(saturation, 100)) / 100.;
+
+    let chroma = (1. - (2. * lightness - 1.).abs()) * saturation;
+    let x = chroma * (1. - ((hue / 60.) % 2. - 1.).abs());
+    let m = lightness - chroma / 2.;
+
+    let (r, g, b) = match hue as u16 / 60 {
+        0 => (chroma, x, 0.),
+        1 => (x, chroma, 0.),
+        2 => (0., chroma, x),
+        3 => (0., x, chroma),
+        4 => (x, 0., chroma),
+        _ => (chroma, 0., x),
+    };
+
+    let channel = |value: f32| ((value + m) * 255.).round() as u8;
+    Rgb { r: channel(r), g: channel(g), b: channel(b) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(transparent_background: bool, data: &[u8]) -> Option<GraphicData> {
+        let mut parser = Parser::new(transparent_background);
+        for &byte in data {
+            parser.put(byte);
+        }
+        parser.finish()
+    }
+
+    fn pixel(graphic: &GraphicData, x: usize, y: usize) -> [u8; 4] {
+        let offset = (y * graphic.width + x) * 4;
+        let mut pixel = [0; 4];
+        pixel.copy_from_slice(&graphic.pixels[offset..offset + 4]);
+        pixel
+    }
+
+    #[test]
+    fn decode_sixels() {
+        // Two columns of six red pixels, then one green pixel in the next sixel line.
+        let graphic = decode(true, b"#1;2;100;0;0#1~~-#2;2;0;100;0!2@").unwrap();
+
+        assert_eq!(graphic.width, 2);
+        assert_eq!(graphic.height, 7);
+        assert_eq!(graphic.pixels.len(), 2 * 7 * 4);
+
+        for y in 0..6 {
+            assert_eq!(pixel(&graphic, 0, y), [255, 0, 0, 255]);
+            assert_eq!(pixel(&graphic, 1, y), [255, 0, 0, 255]);
+        }
+        assert_eq!(pixel(&graphic, 0, 6), [0, 255, 0, 255]);
+        assert_eq!(pixel(&graphic, 1, 6), [0, 255, 0, 255]);
+    }
+
+    #[test]
+    fn decode_raster_attributes() {
+        let graphic = decode(false, b"\"1;1;4;3#3@$#1?B").unwrap();
+
+        assert_eq!(graphic.width, 4);
+        assert_eq!(graphic.height, 3);
+
+        // First column has the top pixel drawn in green, the second column the top two in blue.
+        assert_eq!(pixel(&graphic, 0, 0), [51, 204, 51, 255]);
+        assert_eq!(pixel(&graphic, 1, 0), [51, 51, 204, 255]);
+        assert_eq!(pixel(&graphic, 1, 1), [51, 51, 204, 255]);
+
+        // Undrawn pixels use the background color.
+        assert_eq!(pixel(&graphic, 3, 2), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn decode_transparent_background() {
+        let graphic = decode(true, b"#2!3@").unwrap();
+
+        assert_eq!(graphic.width, 3);
+        assert_eq!(graphic.height, 1);
+        assert_eq!(pixel(&graphic, 2, 0), [204, 33, 33, 255]);
+
+        let graphic = decode(true, b"\"1;1;2;2#2@").unwrap();
+        assert_eq!(pixel(&graphic, 1, 1), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn decode_hls_colors() {
+        assert_eq!(hls_to_rgb(0, 50, 100), Rgb { r: 0, g: 0, b: 255 });
+        assert_eq!(hls_to_rgb(120, 50, 100), Rgb { r: 255, g: 0, b: 0 });
+        assert_eq!(hls_to_rgb(240, 50, 100), Rgb { r: 0, g: 255, b: 0 });
+        assert_eq!(hls_to_rgb(0, 100, 0), Rgb { r: 255, g: 255, b: 255 });
+    }
+
+    #[test]
+    fn clamp_dimensions() {
+        let graphic = decode(true, b"!5000~-!5000~").unwrap();
+
+        assert_eq!(graphic.width, MAX_GRAPHIC_DIMENSIONS);
+        assert_eq!(graphic.height, 12);
+    }
+
+    #[test]
+    fn empty_image() {
+        assert_eq!(decode(false, b""), None);
+        assert_eq!(decode(false, b"#1;2;0;0;0"), None);
+    }
+}
@@ -1,6 +1,7 @@
 //! A specialized 2D grid implementation optimized for use in a terminal.
 
 use std::cmp::{max, min};
+use std::mem;
 use std::ops::{Bound, Deref, Index, IndexMut, Range, RangeBounds};
 
 use serde::{Deserialize, Serialize};
@@ -15,7 +16,7 @@ mod storage;
 #[cfg(test)]
 mod tests;
 
-pub use self::row::Row;
+pub use self::row::{Row, RowMarks};
 use self::storage::Storage;
 
 pub trait GridCell: Sized {
@@ -296,6 +297,55 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
         }
     }
 
+    /// Move the cells within a column range toward the bottom.
+    ///
+    /// Unlike [`Grid::scroll_down`], cells outside of `columns` are not moved.
+    pub fn scroll_down_columns(
+        &mut self,
+        region: &Range<Line>,
+        columns: &Range<Column>,
+        positions: usize,
+    ) {
+        let positions = min(positions, (region.end - region.start).0 as usize);
+
+        for line in ((region.start + positions).0..region.end.0).rev().map(Line::from) {
+            for column in (columns.start.0..columns.end.0).map(Column::from) {
+                self.raw[line][column] = mem::take(&mut self.raw[line - positions][column]);
+            }
+        }
+
+        for line in (region.start.0..(region.start + positions).0).map(Line::from) {
+            for column in (columns.start.0..columns.end.0).map(Column::from) {
+                self.raw[line][column].reset(&self.cursor.template);
+            }
+        }
+    }
+
+    /// Move the cells within a column range toward the top.
+    ///
+    /// Unlike [`Grid::scroll_up`], cells outside of `columns` are not moved and no scrollback
+    /// history is created.
+    pub fn scroll_up_columns(
+        &mut self,
+        region: &Range<Line>,
+        columns: &Range<Column>,
+        positions: usize,
+    ) {
+        let positions = min(positions, (region.end - region.start).0 as usize);
+
+        for line in (region.start.0..(region.end - positions).0).map(Line::from) {
+            for column in (columns.start.0..columns.end.0).map(Column::from) {
+                self.raw[line][column] = mem::take(&mut self.raw[line + positions][column]);
+            }
+        }
+
+        for line in ((region.end - positions).0..region.end.0).map(Line::from) {
+            for column in (columns.start.0..columns.end.0).map(Column::from) {
+                self.raw[line][column].reset(&self.cursor.template);
+            }
+        }
+    }
+
     pub fn clear_viewport<D>(&mut self)
     where
         T: ResetDiscriminant<D>,
@@ -376,6 +426,11 @@ impl<T> Grid<T> {
     pub fn clear_history(&mut self) {
         // Explicitly purge all lines from history.
         self.raw.shrink_lines(self.history_size());
+
+        // Drop the purged lines instead of keeping up to `MAX_CACHE_SIZE` of them cached for
+        // reuse. Their cells can hold references to graphic textures, which would otherwise
+        // stay alive until the cached lines are recycled.
+        self.raw.truncate();
     }
 
     /// This is used only for initializing after loading ref-tests.
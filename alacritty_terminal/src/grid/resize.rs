@@ -182,6 +182,7 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
                 let line_delta = self.cursor.point.line - target.line;
 
                 if line_delta != 0 && row.is_clear() {
+                    last_row.marks.insert(row.marks);
                     continue;
                 }
 
@@ -197,6 +198,9 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
                     self.cursor.point.line += 1;
                 }
 
+                // Keep the marks of the removed line.
+                last_row.marks.insert(row.marks);
+
                 // Don't push line into the new buffer.
                 continue;
             }
@@ -6,12 +6,29 @@ use std::ops::{Range, RangeFrom, RangeFull, RangeTo, RangeToInclusive};
 use std::ptr;
 use std::slice;
 
+use bitflags::bitflags;
 use serde::{Deserialize, Serialize};
 
+use crate::ansi::LineSize;
 use crate::grid::GridCell;
 use crate::index::Column;
 use crate::term::cell::ResetDiscriminant;
 
+bitflags! {
+    /// Shell integration marks set on a row.
+    #[derive(Serialize, Deserialize, Default)]
+    pub struct RowMarks: u8 {
+        /// Start of the prompt.
+        const PROMPT        = 0b0001;
+        /// Start of the command entered at the prompt.
+        const COMMAND       = 0b0010;
+        /// Start of the command's output.
+        const OUTPUT        = 0b0100;
+        /// End of the command's output.
+        const COMMAND_END   = 0b1000;
+    }
+}
+
 /// A row in the grid.
 #[derive(Serialize, Deserialize, Default, Clone, Debug)]
 pub struct Row<T> {
@@ -22,11 +39,19 @@ pub struct Row<T> {
     /// This is the upper bound on the number of elements in the row, which have been modified
     /// since the last reset. All cells after this point are guaranteed to be equal.
     pub(crate) occ: usize,
+
+    /// Shell integration marks.
+    #[serde(default)]
+    pub(crate) marks: RowMarks,
+
+    /// Size of the characters on this row.
+    #[serde(default)]
+    pub(crate) line_size: LineSize,
 }
 
 impl<T: PartialEq> PartialEq for Row<T> {
     fn eq(&self, other: &Self) -> bool {
-        self.inner == other.inner
+        self.inner == other.inner && self.line_size == other.line_size
     }
 }
 
@@ -52,7 +77,7 @@ impl<T: Clone + Default> Row<T> {
             inner.set_len(columns);
         }
 
-        Row { inner, occ: 0 }
+        Row { inner, occ: 0, marks: RowMarks::empty(), line_size: LineSize::Normal }
     }
 
     /// Increase the number of columns in the row.
@@ -111,6 +136,8 @@ impl<T: Clone + Default> Row<T> {
         }
 
         self.occ = 0;
+        self.marks = RowMarks::empty();
+        self.line_size = LineSize::Normal;
     }
 }
 
@@ -118,7 +145,7 @@ impl<T: Clone + Default> Row<T> {
 impl<T> Row<T> {
     #[inline]
     pub fn from_vec(vec: Vec<T>, occ: usize) -> Row<T> {
-        Row { inner: vec, occ }
+        Row { inner: vec, occ, marks: RowMarks::empty(), line_size: LineSize::Normal }
     }
 
     #[inline]
@@ -126,6 +153,18 @@ impl<T> Row<T> {
         self.inner.len()
     }
 
+    /// Shell integration marks set on this row.
+    #[inline]
+    pub fn marks(&self) -> RowMarks {
+        self.marks
+    }
+
+    /// Size of the characters on this row.
+    #[inline]
+    pub fn line_size(&self) -> LineSize {
+        self.line_size
+    }
+
     #[inline]
     pub fn last(&self) -> Option<&T> {
         self.inner.last()
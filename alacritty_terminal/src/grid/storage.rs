@@ -1,6 +1,7 @@
 use std::cmp::{max, PartialEq};
 use std::mem;
 use std::ops::{Index, IndexMut};
+use std::ptr;
 
 use serde::{Deserialize, Serialize};
 
@@ -141,34 +142,20 @@ impl<T> Storage<T> {
 
     /// Swap implementation for Row<T>.
     ///
-    /// Exploits the known size of Row<T> to produce a slightly more efficient
-    /// swap than going through slice::swap.
-    ///
-    /// The default implementation from swap generates 8 movups and 4 movaps
-    /// instructions. This implementation achieves the swap in only 8 movups
-    /// instructions.
+    /// Since `a` and `b` are guaranteed to be valid indices, this avoids the bounds checks
+    /// performed by `slice::swap`.
     pub fn swap(&mut self, a: Line, b: Line) {
-        debug_assert_eq!(mem::size_of::<Row<T>>(), mem::size_of::<usize>() * 4);
-
         let a = self.compute_index(a);
         let b = self.compute_index(b);
 
+        if a == b {
+            return;
+        }
+
         unsafe {
-            // Cast to a qword array to opt out of copy restrictions and avoid
-            // drop hazards. Byte array is no good here since for whatever
-            // reason LLVM won't optimized it.
-            let a_ptr = self.inner.as_mut_ptr().add(a) as *mut usize;
-            let b_ptr = self.inner.as_mut_ptr().add(b) as *mut usize;
-
-            // Copy 1 qword at a time.
-            //
-            // The optimizer unrolls this loop and vectorizes it.
-            let mut tmp: usize;
-            for i in 0..4 {
-                tmp = *a_ptr.offset(i);
-                *a_ptr.offset(i) = *b_ptr.offset(i);
-                *b_ptr.offset(i) = tmp;
-            }
+            let a_ptr = self.inner.as_mut_ptr().add(a);
+            let b_ptr = self.inner.as_mut_ptr().add(b);
+            ptr::swap_nonoverlapping(a_ptr, b_ptr, 1);
         }
     }
 
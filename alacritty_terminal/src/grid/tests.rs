@@ -118,6 +118,44 @@ fn scroll_down_with_history() {
     assert_eq!(grid[Line(9)].occ, 1);
 }
 
+// Scrolling within columns only moves cells inside the column range.
+#[test]
+fn scroll_up_columns() {
+    let mut grid = Grid::<usize>::new(4, 3, 0);
+    for i in 0..4 {
+        for j in 0..3 {
+            grid[Line(i)][Column(j)] = i as usize * 3 + j + 1;
+        }
+    }
+
+    grid.scroll_up_columns(&(Line(1)..Line(4)), &(Column(1)..Column(2)), 1);
+
+    let columns = |line: i32| (0..3).map(|j| grid[Line(line)][Column(j)]).collect::<Vec<_>>();
+    assert_eq!(columns(0), vec![1, 2, 3]);
+    assert_eq!(columns(1), vec![4, 8, 6]);
+    assert_eq!(columns(2), vec![7, 11, 9]);
+    assert_eq!(columns(3), vec![10, 0, 12]);
+    assert_eq!(grid.history_size(), 0);
+}
+
+#[test]
+fn scroll_down_columns() {
+    let mut grid = Grid::<usize>::new(4, 3, 0);
+    for i in 0..4 {
+        for j in 0..3 {
+            grid[Line(i)][Column(j)] = i as usize * 3 + j + 1;
+        }
+    }
+
+    grid.scroll_down_columns(&(Line(0)..Line(3)), &(Column(1)..Column(3)), 2);
+
+    let columns = |line: i32| (0..3).map(|j| grid[Line(line)][Column(j)]).collect::<Vec<_>>();
+    assert_eq!(columns(0), vec![1, 0, 0]);
+    assert_eq!(columns(1), vec![4, 0, 0]);
+    assert_eq!(columns(2), vec![7, 2, 3]);
+    assert_eq!(columns(3), vec![10, 11, 12]);
+}
+
 // Test that GridIterator works.
 #[test]
 fn test_iter() {
@@ -307,6 +345,30 @@ fn grow_reflow_multiline() {
     }
 }
 
+#[test]
+fn reflow_keeps_marks() {
+    let mut grid = Grid::<Cell>::new(2, 4, 1);
+    grid[Line(0)][Column(0)] = cell('$');
+    grid[Line(0)][Column(1)] = cell('1');
+    grid[Line(0)][Column(2)] = cell('2');
+    grid[Line(0)][Column(3)] = cell('3');
+    grid[Line(0)].marks.insert(RowMarks::PROMPT);
+    grid[Line(1)][Column(0)] = cell('o');
+    grid[Line(1)].marks.insert(RowMarks::OUTPUT);
+    grid.cursor.point = Point::new(Line(1), Column(1));
+
+    grid.resize(true, 2, 2);
+
+    assert_eq!(grid[Line(-1)].marks(), RowMarks::PROMPT);
+    assert_eq!(grid[Line(0)].marks(), RowMarks::empty());
+    assert_eq!(grid[Line(1)].marks(), RowMarks::OUTPUT);
+
+    grid.resize(true, 2, 4);
+
+    assert_eq!(grid[Line(0)].marks(), RowMarks::PROMPT);
+    assert_eq!(grid[Line(1)].marks(), RowMarks::OUTPUT);
+}
+
 #[test]
 fn grow_reflow_disabled() {
     let mut grid = Grid::<Cell>::new(2, 2, 0);
@@ -1,29 +1,42 @@
 use std::boxed::Box;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
 
 use bitflags::bitflags;
 use serde::{Deserialize, Serialize};
 
 use crate::ansi::{Color, NamedColor};
+use crate::graphics::GraphicCell;
 use crate::grid::{self, GridCell};
 use crate::index::Column;
 
 bitflags! {
     #[derive(Serialize, Deserialize)]
-    pub struct Flags: u16 {
-        const INVERSE                   = 0b0000_0000_0000_0001;
-        const BOLD                      = 0b0000_0000_0000_0010;
-        const ITALIC                    = 0b0000_0000_0000_0100;
-        const BOLD_ITALIC               = 0b0000_0000_0000_0110;
-        const UNDERLINE                 = 0b0000_0000_0000_1000;
-        const WRAPLINE                  = 0b0000_0000_0001_0000;
-        const WIDE_CHAR                 = 0b0000_0000_0010_0000;
-        const WIDE_CHAR_SPACER          = 0b0000_0000_0100_0000;
-        const DIM                       = 0b0000_0000_1000_0000;
-        const DIM_BOLD                  = 0b0000_0000_1000_0010;
-        const HIDDEN                    = 0b0000_0001_0000_0000;
-        const STRIKEOUT                 = 0b0000_0010_0000_0000;
-        const LEADING_WIDE_CHAR_SPACER  = 0b0000_0100_0000_0000;
-        const DOUBLE_UNDERLINE          = 0b0000_1000_0000_0000;
+    pub struct Flags: u32 {
+        const INVERSE                   = 0b0000_0000_0000_0000_0000_0000_0000_0001;
+        const BOLD                      = 0b0000_0000_0000_0000_0000_0000_0000_0010;
+        const ITALIC                    = 0b0000_0000_0000_0000_0000_0000_0000_0100;
+        const BOLD_ITALIC               = 0b0000_0000_0000_0000_0000_0000_0000_0110;
+        const UNDERLINE                 = 0b0000_0000_0000_0000_0000_0000_0000_1000;
+        const WRAPLINE                  = 0b0000_0000_0000_0000_0000_0000_0001_0000;
+        const WIDE_CHAR                 = 0b0000_0000_0000_0000_0000_0000_0010_0000;
+        const WIDE_CHAR_SPACER          = 0b0000_0000_0000_0000_0000_0000_0100_0000;
+        const DIM                       = 0b0000_0000_0000_0000_0000_0000_1000_0000;
+        const DIM_BOLD                  = 0b0000_0000_0000_0000_0000_0000_1000_0010;
+        const HIDDEN                    = 0b0000_0000_0000_0000_0000_0001_0000_0000;
+        const STRIKEOUT                 = 0b0000_0000_0000_0000_0000_0010_0000_0000;
+        const LEADING_WIDE_CHAR_SPACER  = 0b0000_0000_0000_0000_0000_0100_0000_0000;
+        const DOUBLE_UNDERLINE          = 0b0000_0000_0000_0000_0000_1000_0000_0000;
+        const UNDERCURL                 = 0b0000_0000_0000_0000_0001_0000_0000_0000;
+        const DOTTED_UNDERLINE          = 0b0000_0000_0000_0000_0010_0000_0000_0000;
+        const DASHED_UNDERLINE          = 0b0000_0000_0000_0000_0100_0000_0000_0000;
+        const BLINK                     = 0b0000_0000_0000_0000_1000_0000_0000_0000;
+        const OVERLINE                  = 0b0000_0000_0000_0001_0000_0000_0000_0000;
+        const SUPERSCRIPT               = 0b0000_0000_0000_0010_0000_0000_0000_0000;
+        const SUBSCRIPT                 = 0b0000_0000_0000_0100_0000_0000_0000_0000;
+        const ALL_UNDERLINES            = Self::UNDERLINE.bits | Self::DOUBLE_UNDERLINE.bits
+                                        | Self::UNDERCURL.bits | Self::DOTTED_UNDERLINE.bits
+                                        | Self::DASHED_UNDERLINE.bits;
     }
 }
 
@@ -45,6 +58,55 @@ impl ResetDiscriminant<Color> for Cell {
     }
 }
 
+/// Counter for hyperlinks without explicit ID.
+static HYPERLINK_ID_SUFFIX: AtomicU32 = AtomicU32::new(0);
+
+/// Hyperlink attached to a cell.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Hyperlink {
+    inner: Arc<HyperlinkInner>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct HyperlinkInner {
+    /// Identifier used to group cells belonging to the same hyperlink.
+    id: String,
+
+    /// Resource the hyperlink is pointing to.
+    uri: String,
+}
+
+impl Hyperlink {
+    pub fn new<T: ToString>(id: Option<T>, uri: T) -> Self {
+        // Hyperlinks without an explicit ID get a unique one, so separate links are never merged.
+        let id = match id {
+            Some(id) => id.to_string(),
+            None => {
+                let suffix = HYPERLINK_ID_SUFFIX.fetch_add(1, Ordering::Relaxed);
+                format!("{}_alacritty", suffix)
+            },
+        };
+
+        Self { inner: Arc::new(HyperlinkInner { id, uri: uri.to_string() }) }
+    }
+
+    /// Identifier shared by all cells of this hyperlink.
+    pub fn id(&self) -> &str {
+        &self.inner.id
+    }
+
+    /// URI this hyperlink is pointing to.
+    pub fn uri(&self) -> &str {
+        &self.inner.uri
+    }
+}
+
+impl From<crate::ansi::Hyperlink> for Hyperlink {
+    fn from(value: crate::ansi::Hyperlink) -> Self {
+        Self::new(value.id, value.uri)
+    }
+}
+
 /// Dynamically allocated cell content.
 ///
 /// This storage is reserved for cell attributes which are rarely set. This allows reducing the
@@ -53,6 +115,15 @@ impl ResetDiscriminant<Color> for Cell {
 #[derive(Serialize, Deserialize, Default, Debug, Clone, Eq, PartialEq)]
 struct CellExtra {
     zerowidth: Vec<char>,
+
+    #[serde(default)]
+    hyperlink: Option<Hyperlink>,
+
+    #[serde(default)]
+    underline_color: Option<Color>,
+
+    #[serde(skip)]
+    graphic: Option<GraphicCell>,
 }
 
 /// Content and attributes of a single cell in the terminal grid.
@@ -92,6 +163,66 @@ impl Cell {
         self.extra.get_or_insert_with(Default::default).zerowidth.push(c);
     }
 
+    /// Hyperlink attached to this cell.
+    #[inline]
+    pub fn hyperlink(&self) -> Option<Hyperlink> {
+        self.extra.as_ref()?.hyperlink.clone()
+    }
+
+    /// Check if this cell is part of a hyperlink.
+    #[inline]
+    pub fn has_hyperlink(&self, hyperlink: &Hyperlink) -> bool {
+        self.extra.as_ref().and_then(|extra| extra.hyperlink.as_ref()) == Some(hyperlink)
+    }
+
+    /// Set or remove the hyperlink attached to this cell.
+    #[inline]
+    pub fn set_hyperlink(&mut self, hyperlink: Option<Hyperlink>) {
+        if hyperlink.is_some() || self.extra.is_some() {
+            self.update_extra(|extra| extra.hyperlink = hyperlink);
+        }
+    }
+
+    /// Underline color of this cell.
+    ///
+    /// If this is `None`, the underline uses the foreground color.
+    #[inline]
+    pub fn underline_color(&self) -> Option<Color> {
+        self.extra.as_ref()?.underline_color
+    }
+
+    /// Set or remove the underline color of this cell.
+    #[inline]
+    pub fn set_underline_color(&mut self, color: Option<Color>) {
+        if color.is_some() || self.extra.is_some() {
+            self.update_extra(|extra| extra.underline_color = color);
+        }
+    }
+
+    /// Graphic tile displayed in this cell.
+    #[inline]
+    pub fn graphic(&self) -> Option<&GraphicCell> {
+        self.extra.as_ref()?.graphic.as_ref()
+    }
+
+    /// Set or remove the graphic tile displayed in this cell.
+    #[inline]
+    pub fn set_graphic(&mut self, graphic: Option<GraphicCell>) {
+        if graphic.is_some() || self.extra.is_some() {
+            self.update_extra(|extra| extra.graphic = graphic);
+        }
+    }
+
+    /// Modify the dynamically allocated storage, freeing it once it is no longer used.
+    fn update_extra<F: FnOnce(&mut CellExtra)>(&mut self, f: F) {
+        let extra = self.extra.get_or_insert_with(Default::default);
+        f(extra);
+
+        if **extra == CellExtra::default() {
+            self.extra = None;
+        }
+    }
+
     /// Free all dynamically allocated cell storage.
     #[inline]
     pub fn drop_extra(&mut self) {
@@ -117,14 +248,18 @@ impl GridCell for Cell {
             && self.fg == Color::Named(NamedColor::Foreground)
             && !self.flags.intersects(
                 Flags::INVERSE
-                    | Flags::UNDERLINE
-                    | Flags::DOUBLE_UNDERLINE
+                    | Flags::ALL_UNDERLINES
                     | Flags::STRIKEOUT
                     | Flags::WRAPLINE
                     | Flags::WIDE_CHAR_SPACER
                     | Flags::LEADING_WIDE_CHAR_SPACER,
             )
-            && self.extra.as_ref().map(|extra| extra.zerowidth.is_empty()) != Some(false)
+            && self.extra.as_ref().map(|extra| {
+                extra.zerowidth.is_empty()
+                    && extra.hyperlink.is_none()
+                    && extra.underline_color.is_none()
+                    && extra.graphic.is_none()
+            }) != Some(false)
     }
 
     #[inline]
@@ -166,7 +301,11 @@ impl LineLength for grid::Row<Cell> {
 
         for (index, cell) in self[..].iter().rev().enumerate() {
             if cell.c != ' '
-                || cell.extra.as_ref().map(|extra| extra.zerowidth.is_empty()) == Some(false)
+                || cell
+                    .extra
+                    .as_ref()
+                    .map(|extra| extra.zerowidth.is_empty() && extra.hyperlink.is_none())
+                    == Some(false)
             {
                 length = Column(self.len() - index);
                 break;
@@ -10,7 +10,7 @@ use serde_yaml::Value;
 use crate::ansi::NamedColor;
 
 /// Number of terminal colors.
-pub const COUNT: usize = 269;
+pub const COUNT: usize = 271;
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Default, Serialize)]
 pub struct Rgb {
@@ -1,21 +1,29 @@
 //! Exports the `Term` type which is a high-level API for the Grid.
 
 use std::cmp::{max, min};
-use std::ops::{Index, IndexMut, Range};
+use std::ops::{Index, IndexMut, Range, RangeInclusive};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::{mem, ptr, str};
 
 use bitflags::bitflags;
 use log::{debug, trace};
 use serde::{Deserialize, Serialize};
-use unicode_width::UnicodeWidthChar;
+use unicode_segmentation::GraphemeCursor;
 
 use crate::ansi::{
-    self, Attr, CharsetIndex, Color, CursorShape, CursorStyle, Handler, NamedColor, StandardCharset,
+    self, Attr, CharsetIndex, Color, CursorShape, CursorStyle, Handler, KeyboardModes,
+    KeyboardModesApplyBehavior, LineSize, MouseHighlight, NamedColor, PromptMark, Rectangle,
+    StandardCharset, StatusStringQuery, WindowOperation,
 };
-use crate::config::Config;
+use crate::config::{AmbiguousWidth, Config, WindowOperationKind, WindowOperations};
 use crate::event::{Event, EventListener};
-use crate::grid::{Dimensions, Grid, GridIterator, Scroll};
+use crate::graphics::kitty::{self, Action, ImageStore};
+use crate::graphics::{
+    GraphicCell, GraphicData, GraphicId, Graphics, Placement, UpdateQueues, MAX_GRAPHICS_MEMORY,
+    MAX_GRAPHIC_DIMENSIONS,
+};
+use crate::grid::{Dimensions, Grid, GridIterator, RowMarks, Scroll};
 use crate::index::{self, Boundary, Column, Direction, Line, Point, Side};
 use crate::selection::{Selection, SelectionRange};
 use crate::term::cell::{Cell, Flags, LineLength};
@@ -25,6 +33,7 @@ use crate::vi_mode::{ViModeCursor, ViMotion};
 pub mod cell;
 pub mod color;
 pub mod search;
+mod termcap;
 
 /// Minimum number of columns.
 ///
@@ -37,32 +46,85 @@ pub const MIN_SCREEN_LINES: usize = 1;
 /// Max size of the window title stack.
 const TITLE_STACK_MAX_DEPTH: usize = 4096;
 
+/// Max size of the keyboard modes stack.
+const KEYBOARD_MODE_STACK_MAX_DEPTH: usize = TITLE_STACK_MAX_DEPTH;
+
 /// Default tab interval, corresponding to terminfo `it` value.
 const INITIAL_TABSTOPS: usize = 8;
 
+/// Mode state reported through DECRPM.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum ModeState {
+    NotRecognized = 0,
+    Set = 1,
+    Reset = 2,
+    PermanentlyReset = 4,
+}
+
+impl From<bool> for ModeState {
+    fn from(value: bool) -> Self {
+        if value {
+            Self::Set
+        } else {
+            Self::Reset
+        }
+    }
+}
+
 bitflags! {
     pub struct TermMode: u32 {
-        const NONE                = 0;
-        const SHOW_CURSOR         = 0b0000_0000_0000_0000_0001;
-        const APP_CURSOR          = 0b0000_0000_0000_0000_0010;
-        const APP_KEYPAD          = 0b0000_0000_0000_0000_0100;
-        const MOUSE_REPORT_CLICK  = 0b0000_0000_0000_0000_1000;
-        const BRACKETED_PASTE     = 0b0000_0000_0000_0001_0000;
-        const SGR_MOUSE           = 0b0000_0000_0000_0010_0000;
-        const MOUSE_MOTION        = 0b0000_0000_0000_0100_0000;
-        const LINE_WRAP           = 0b0000_0000_0000_1000_0000;
-        const LINE_FEED_NEW_LINE  = 0b0000_0000_0001_0000_0000;
-        const ORIGIN              = 0b0000_0000_0010_0000_0000;
-        const INSERT              = 0b0000_0000_0100_0000_0000;
-        const FOCUS_IN_OUT        = 0b0000_0000_1000_0000_0000;
-        const ALT_SCREEN          = 0b0000_0001_0000_0000_0000;
-        const MOUSE_DRAG          = 0b0000_0010_0000_0000_0000;
-        const MOUSE_MODE          = 0b0000_0010_0000_0100_1000;
-        const UTF8_MOUSE          = 0b0000_0100_0000_0000_0000;
-        const ALTERNATE_SCROLL    = 0b0000_1000_0000_0000_0000;
-        const VI                  = 0b0001_0000_0000_0000_0000;
-        const URGENCY_HINTS       = 0b0010_0000_0000_0000_0000;
-        const ANY                 = std::u32::MAX;
+        const NONE                    = 0;
+        const SHOW_CURSOR             = 0b0000_0000_0000_0000_0000_0000_0000_0001;
+        const APP_CURSOR              = 0b0000_0000_0000_0000_0000_0000_0000_0010;
+        const APP_KEYPAD              = 0b0000_0000_0000_0000_0000_0000_0000_0100;
+        const MOUSE_REPORT_CLICK      = 0b0000_0000_0000_0000_0000_0000_0000_1000;
+        const BRACKETED_PASTE         = 0b0000_0000_0000_0000_0000_0000_0001_0000;
+        const SGR_MOUSE               = 0b0000_0000_0000_0000_0000_0000_0010_0000;
+        const MOUSE_MOTION            = 0b0000_0000_0000_0000_0000_0000_0100_0000;
+        const LINE_WRAP               = 0b0000_0000_0000_0000_0000_0000_1000_0000;
+        const LINE_FEED_NEW_LINE      = 0b0000_0000_0000_0000_0000_0001_0000_0000;
+        const ORIGIN                  = 0b0000_0000_0000_0000_0000_0010_0000_0000;
+        const INSERT                  = 0b0000_0000_0000_0000_0000_0100_0000_0000;
+        const FOCUS_IN_OUT            = 0b0000_0000_0000_0000_0000_1000_0000_0000;
+        const ALT_SCREEN              = 0b0000_0000_0000_0000_0001_0000_0000_0000;
+        const MOUSE_DRAG              = 0b0000_0000_0000_0000_0010_0000_0000_0000;
+        const MOUSE_MODE              = 0b0000_0001_0000_0000_0010_0000_0100_1000;
+        const UTF8_MOUSE              = 0b0000_0000_0000_0000_0100_0000_0000_0000;
+        const ALTERNATE_SCROLL        = 0b0000_0000_0000_0000_1000_0000_0000_0000;
+        const VI                      = 0b0000_0000_0000_0001_0000_0000_0000_0000;
+        const URGENCY_HINTS           = 0b0000_0000_0000_0010_0000_0000_0000_0000;
+        const DISAMBIGUATE_ESC_CODES  = 0b0000_0000_0000_0100_0000_0000_0000_0000;
+        const REPORT_EVENT_TYPES      = 0b0000_0000_0000_1000_0000_0000_0000_0000;
+        const REPORT_ALTERNATE_KEYS   = 0b0000_0000_0001_0000_0000_0000_0000_0000;
+        const REPORT_ALL_KEYS_AS_ESC  = 0b0000_0000_0010_0000_0000_0000_0000_0000;
+        const LEFT_RIGHT_MARGIN       = 0b0000_0000_1000_0000_0000_0000_0000_0000;
+        const MOUSE_HIGHLIGHT         = 0b0000_0001_0000_0000_0000_0000_0000_0000;
+        const URXVT_MOUSE             = 0b0000_0010_0000_0000_0000_0000_0000_0000;
+        const SGR_PIXELS_MOUSE        = 0b0000_0100_0000_0000_0000_0000_0000_0000;
+        const GRAPHEME_CLUSTERING     = 0b0000_1000_0000_0000_0000_0000_0000_0000;
+        const MOUSE_ENCODING          = 0b0000_0110_0000_0000_0100_0000_0010_0000;
+        const KITTY_KEYBOARD_PROTOCOL = Self::DISAMBIGUATE_ESC_CODES.bits
+                                      | Self::REPORT_EVENT_TYPES.bits
+                                      | Self::REPORT_ALTERNATE_KEYS.bits
+                                      | Self::REPORT_ALL_KEYS_AS_ESC.bits;
+        const ANY                     = std::u32::MAX;
+    }
+}
+
+impl From<KeyboardModes> for TermMode {
+    fn from(modes: KeyboardModes) -> Self {
+        let mut mode = Self::empty();
+        mode.set(
+            Self::DISAMBIGUATE_ESC_CODES,
+            modes.contains(KeyboardModes::DISAMBIGUATE_ESC_CODES),
+        );
+        mode.set(Self::REPORT_EVENT_TYPES, modes.contains(KeyboardModes::REPORT_EVENT_TYPES));
+        mode.set(Self::REPORT_ALTERNATE_KEYS, modes.contains(KeyboardModes::REPORT_ALTERNATE_KEYS));
+        mode.set(
+            Self::REPORT_ALL_KEYS_AS_ESC,
+            modes.contains(KeyboardModes::REPORT_ALL_KEYS_AS_ESC),
+        );
+        mode
     }
 }
 
@@ -216,6 +278,9 @@ pub struct Term<T> {
 
     pub selection: Option<Selection>,
 
+    /// Region for highlight mouse tracking, requested by the application after a mouse press.
+    pub mouse_highlight: Option<MouseHighlight>,
+
     /// Currently active grid.
     ///
     /// Tracks the screen buffer currently in use. While the alternate screen buffer is active,
@@ -231,6 +296,9 @@ pub struct Term<T> {
     /// Index into `charsets`, pointing to what ASCII is currently being mapped to.
     active_charset: CharsetIndex,
 
+    /// Charset used for the next character only, overriding the `active_charset`.
+    single_shift_charset: Option<CharsetIndex>,
+
     /// Tabstops.
     tabs: TabStops,
 
@@ -242,8 +310,23 @@ pub struct Term<T> {
     /// Range going from top to bottom of the terminal, indexed from the top of the viewport.
     scroll_region: Range<Line>,
 
+    /// Left and right margins, set with DECSLRM while DECLRMM is enabled.
+    horizontal_margins: Range<Column>,
+
+    /// Attribute changes apply to a rectangle, instead of all cells in between, set with DECSACE.
+    rectangular_attribute_change: bool,
+
     semantic_escape_chars: String,
 
+    /// Window operations applications are allowed to request.
+    window_operations: WindowOperations,
+
+    /// Number of columns occupied by East Asian ambiguous characters.
+    ambiguous_width: AmbiguousWidth,
+
+    /// Scratch buffer for checking grapheme cluster boundaries.
+    grapheme_buffer: String,
+
     /// Modified terminal colors.
     colors: Colors,
 
@@ -266,9 +349,27 @@ pub struct Term<T> {
     /// term is set.
     title_stack: Vec<Option<String>>,
 
+    /// Working directory reported by the shell.
+    working_directory: Option<PathBuf>,
+
+    /// Mouse cursor shape requested by the application.
+    mouse_cursor_shape: Option<String>,
+
+    /// Stack of keyboard protocol modes for the active screen.
+    keyboard_mode_stack: Vec<KeyboardModes>,
+
+    /// Stack of keyboard protocol modes for the inactive screen.
+    inactive_keyboard_mode_stack: Vec<KeyboardModes>,
+
     /// Information about cell dimensions.
     cell_width: usize,
     cell_height: usize,
+
+    /// Graphics displayed in the grid.
+    graphics: Graphics,
+
+    /// Images transmitted with the kitty graphics protocol.
+    kitty_images: ImageStore,
 }
 
 impl<T> Term<T> {
@@ -304,12 +405,18 @@ impl<T> Term<T> {
             grid,
             inactive_grid: alt,
             active_charset: Default::default(),
+            single_shift_charset: None,
             vi_mode_cursor: Default::default(),
             tabs,
             mode: Default::default(),
             scroll_region,
+            horizontal_margins: Column(0)..Column(num_cols),
+            rectangular_attribute_change: false,
             colors: color::Colors::default(),
             semantic_escape_chars: config.selection.semantic_escape_chars.to_owned(),
+            window_operations: config.window_operations.clone(),
+            ambiguous_width: config.ambiguous_width,
+            grapheme_buffer: String::new(),
             cursor_style: None,
             default_cursor_style: config.cursor.style(),
             vi_mode_cursor_style: config.cursor.vi_mode_style(),
@@ -317,9 +424,16 @@ impl<T> Term<T> {
             is_focused: true,
             title: None,
             title_stack: Vec::new(),
+            working_directory: None,
+            mouse_cursor_shape: None,
+            keyboard_mode_stack: Vec::new(),
+            inactive_keyboard_mode_stack: Vec::new(),
             selection: None,
+            mouse_highlight: None,
             cell_width: size.cell_width as usize,
             cell_height: size.cell_height as usize,
+            graphics: Graphics::default(),
+            kitty_images: ImageStore::default(),
         }
     }
 
@@ -328,6 +442,8 @@ impl<T> Term<T> {
         T: EventListener,
     {
         self.semantic_escape_chars = config.selection.semantic_escape_chars.to_owned();
+        self.window_operations = config.window_operations.clone();
+        self.ambiguous_width = config.ambiguous_width;
         self.default_cursor_style = config.cursor.style();
         self.vi_mode_cursor_style = config.cursor.vi_mode_style();
 
@@ -517,8 +633,9 @@ impl<T> Term<T> {
         self.vi_mode_cursor.point.line = max(min(vi_point.line, viewport_bottom), viewport_top);
         self.vi_mode_cursor.point.column = min(vi_point.column, self.last_column());
 
-        // Reset scrolling region.
+        // Reset scrolling region and margins.
         self.scroll_region = Line(0)..Line(self.screen_lines() as i32);
+        self.horizontal_margins = Column(0)..Column(self.columns());
     }
 
     /// Active terminal modes.
@@ -527,6 +644,28 @@ impl<T> Term<T> {
         &self.mode
     }
 
+    /// Colors modified through escape sequences.
+    #[inline]
+    pub fn colors(&self) -> &Colors {
+        &self.colors
+    }
+
+    /// Take graphics which need to be uploaded or deleted by the renderer.
+    #[inline]
+    pub fn graphics_take_queues(&mut self) -> Option<UpdateQueues> {
+        self.graphics.take_queues()
+    }
+
+    /// Restore the default mouse cursor shape.
+    fn reset_mouse_cursor_shape(&mut self)
+    where
+        T: EventListener,
+    {
+        if self.mouse_cursor_shape.take().is_some() {
+            self.event_proxy.send_event(Event::MouseCursorShape(None));
+        }
+    }
+
     /// Swap primary and alternate screen buffer.
     pub fn swap_alt(&mut self) {
         if !self.mode.contains(TermMode::ALT_SCREEN) {
@@ -538,13 +677,94 @@ impl<T> Term<T> {
 
             // Reset alternate screen contents.
             self.inactive_grid.reset_region(..);
+
+            // Reset alternate screen keyboard modes.
+            self.inactive_keyboard_mode_stack.clear();
         }
 
         mem::swap(&mut self.grid, &mut self.inactive_grid);
+        mem::swap(&mut self.keyboard_mode_stack, &mut self.inactive_keyboard_mode_stack);
         self.mode ^= TermMode::ALT_SCREEN;
+        self.update_keyboard_mode();
         self.selection = None;
     }
 
+    /// Current state of a terminal mode.
+    fn mode_state(&self, mode: &ansi::Mode) -> ModeState {
+        let flag = match mode {
+            ansi::Mode::CursorKeys => TermMode::APP_CURSOR,
+            ansi::Mode::Insert => TermMode::INSERT,
+            ansi::Mode::Origin => TermMode::ORIGIN,
+            ansi::Mode::LineWrap => TermMode::LINE_WRAP,
+            ansi::Mode::LineFeedNewLine => TermMode::LINE_FEED_NEW_LINE,
+            ansi::Mode::ShowCursor => TermMode::SHOW_CURSOR,
+            ansi::Mode::LeftRightMargin => TermMode::LEFT_RIGHT_MARGIN,
+            ansi::Mode::ReportMouseClicks => TermMode::MOUSE_REPORT_CLICK,
+            ansi::Mode::ReportMouseHighlight => TermMode::MOUSE_HIGHLIGHT,
+            ansi::Mode::ReportCellMouseMotion => TermMode::MOUSE_DRAG,
+            ansi::Mode::ReportAllMouseMotion => TermMode::MOUSE_MOTION,
+            ansi::Mode::ReportFocusInOut => TermMode::FOCUS_IN_OUT,
+            ansi::Mode::Utf8Mouse => TermMode::UTF8_MOUSE,
+            ansi::Mode::SgrMouse => TermMode::SGR_MOUSE,
+            ansi::Mode::UrxvtMouse => TermMode::URXVT_MOUSE,
+            ansi::Mode::SgrPixelsMouse => TermMode::SGR_PIXELS_MOUSE,
+            ansi::Mode::AlternateScroll => TermMode::ALTERNATE_SCROLL,
+            ansi::Mode::UrgencyHints => TermMode::URGENCY_HINTS,
+            ansi::Mode::SwapScreenAndSetRestoreCursor => TermMode::ALT_SCREEN,
+            ansi::Mode::BracketedPaste => TermMode::BRACKETED_PASTE,
+            ansi::Mode::GraphemeClustering => TermMode::GRAPHEME_CLUSTERING,
+            ansi::Mode::BlinkingCursor => return self.cursor_style().blinking.into(),
+            // Changing the number of columns is not supported.
+            ansi::Mode::ColumnMode => return ModeState::PermanentlyReset,
+            // Synchronized updates are tracked by the parser, see `report_sync_update`.
+            ansi::Mode::SyncUpdate => return ModeState::Reset,
+        };
+
+        self.mode.contains(flag).into()
+    }
+
+    /// SGR parameters for the active character attributes.
+    fn graphic_rendition(&self) -> String {
+        let template = &self.grid.cursor.template;
+        let mut params = vec![String::from("0")];
+
+        let flags = [
+            (Flags::BOLD, "1"),
+            (Flags::DIM, "2"),
+            (Flags::ITALIC, "3"),
+            (Flags::UNDERLINE, "4"),
+            (Flags::DOUBLE_UNDERLINE, "4:2"),
+            (Flags::UNDERCURL, "4:3"),
+            (Flags::DOTTED_UNDERLINE, "4:4"),
+            (Flags::DASHED_UNDERLINE, "4:5"),
+            (Flags::BLINK, "5"),
+            (Flags::INVERSE, "7"),
+            (Flags::HIDDEN, "8"),
+            (Flags::STRIKEOUT, "9"),
+            (Flags::OVERLINE, "53"),
+            (Flags::SUPERSCRIPT, "73"),
+            (Flags::SUBSCRIPT, "74"),
+        ];
+        for (flag, param) in flags.iter() {
+            if template.flags.contains(*flag) {
+                params.push(String::from(*param));
+            }
+        }
+
+        params.extend(sgr_color(template.fg, 38));
+        params.extend(sgr_color(template.bg, 48));
+        params.extend(template.underline_color().and_then(|color| sgr_color(color, 58)));
+
+        params.join(";")
+    }
+
+    /// Activate the keyboard protocol modes at the top of the stack.
+    fn update_keyboard_mode(&mut self) {
+        let mode = self.keyboard_mode_stack.last().copied().unwrap_or(KeyboardModes::NO_MODE);
+        self.mode.remove(TermMode::KITTY_KEYBOARD_PROTOCOL);
+        self.mode.insert(mode.into());
+    }
+
     /// Scroll screen down.
     ///
     /// Text moves down; clear at bottom
@@ -558,6 +778,13 @@ impl<T> Term<T> {
 
         let region = origin..self.scroll_region.end;
 
+        // Only move the cells between the left and right margins.
+        if self.has_horizontal_margins() {
+            let columns = self.horizontal_margins.clone();
+            self.grid.scroll_down_columns(&region, &columns, lines);
+            return;
+        }
+
         // Scroll selection.
         self.selection =
             self.selection.take().and_then(|s| s.rotate(self, &region, -(lines as i32)));
@@ -584,6 +811,13 @@ impl<T> Term<T> {
 
         let region = origin..self.scroll_region.end;
 
+        // Only move the cells between the left and right margins.
+        if self.has_horizontal_margins() {
+            let columns = self.horizontal_margins.clone();
+            self.grid.scroll_up_columns(&region, &columns, lines);
+            return;
+        }
+
         // Scroll selection.
         self.selection = self.selection.take().and_then(|s| s.rotate(self, &region, lines as i32));
 
@@ -599,18 +833,398 @@ impl<T> Term<T> {
         self.grid.scroll_up(&region, lines);
     }
 
+    /// Check if the left and right margins are narrower than the screen.
+    #[inline]
+    fn has_horizontal_margins(&self) -> bool {
+        self.horizontal_margins.start != 0 || self.horizontal_margins.end != self.columns()
+    }
+
+    /// Check if the cursor is between the left and right margins.
+    #[inline]
+    fn cursor_in_horizontal_margins(&self) -> bool {
+        self.horizontal_margins.contains(&self.grid.cursor.point.column)
+    }
+
+    /// Number of columns available for input at the cursor position.
+    #[inline]
+    fn input_columns(&self) -> usize {
+        // Wrap at the right margin when the cursor is between the margins.
+        let columns = if self.cursor_in_horizontal_margins() {
+            self.horizontal_margins.end.0
+        } else {
+            self.columns()
+        };
+
+        // Double-width lines end in the middle of the screen.
+        min(columns, self.line_columns(self.grid.cursor.point.line))
+    }
+
+    /// Number of columns available on a line.
+    ///
+    /// Lines with double-width characters only fit half as many characters.
+    #[inline]
+    fn line_columns(&self, line: Line) -> usize {
+        if self.grid[line].line_size().is_double_width() {
+            max(self.columns() / 2, 1)
+        } else {
+            self.columns()
+        }
+    }
+
+    /// Check if a column on a line is a tab stop.
+    ///
+    /// Tab stops are set in screen columns, so every cell of a double-width line covers two.
+    #[inline]
+    fn is_tab_stop(&self, line: Line, column: Column) -> bool {
+        if self.grid[line].line_size().is_double_width() {
+            self.tabs[Column(column.0 * 2)]
+        } else {
+            self.tabs[column]
+        }
+    }
+
+    /// Cursor column relative to the left margin in origin mode, as expected by `goto`.
+    #[inline]
+    fn cursor_origin_column(&self) -> Column {
+        let column = self.grid.cursor.point.column;
+        if self.mode.contains(TermMode::ORIGIN) {
+            Column(column.saturating_sub(self.horizontal_margins.start.0))
+        } else {
+            column
+        }
+    }
+
+    /// Convert a rectangular area to the points of its top-left and bottom-right corner.
+    ///
+    /// In origin mode the area is relative to the scrolling region and margins. The area is clamped
+    /// to the screen, or the margins in origin mode, and `None` is returned if it is empty.
+    fn rectangle_points(&self, area: Rectangle) -> Option<RangeInclusive<Point>> {
+        let (top, left, bottom, right) = if self.mode.contains(TermMode::ORIGIN) {
+            let margins = &self.horizontal_margins;
+            (
+                self.scroll_region.start.0 as usize,
+                margins.start.0,
+                self.scroll_region.end.0 as usize,
+                margins.end.0,
+            )
+        } else {
+            (0, 0, self.screen_lines(), self.columns())
+        };
+
+        let start_line = top + area.top - 1;
+        let start_column = left + area.left - 1;
+        let end_line = area.bottom.map_or(bottom, |area_bottom| min(top + area_bottom, bottom));
+        let end_column = area.right.map_or(right, |area_right| min(left + area_right, right));
+
+        if start_line >= end_line || start_column >= end_column {
+            return None;
+        }
+
+        let start = Point::new(Line(start_line as i32), Column(start_column));
+        let end = Point::new(Line(end_line as i32 - 1), Column(end_column - 1));
+        Some(start..=end)
+    }
+
+    /// Clear wide characters which are split by the left or right edge of a rectangular area.
+    ///
+    /// This should be called before the cells inside the area are overwritten.
+    fn clear_split_wide_chars(&mut self, area: &RangeInclusive<Point>) {
+        let (start, end) = (*area.start(), *area.end());
+        let columns = self.columns();
+
+        for line in start.line.0..=end.line.0 {
+            let row = &mut self.grid[Line(line)];
+
+            if start.column > 0 && row[start.column].flags.contains(Flags::WIDE_CHAR_SPACER) {
+                row[start.column - 1].clear_wide();
+            }
+
+            if end.column + 1 < columns && row[end.column].flags.contains(Flags::WIDE_CHAR) {
+                row[end.column + 1].flags.remove(Flags::WIDE_CHAR_SPACER);
+            }
+        }
+    }
+
+    /// Checksum of all cells in a rectangular area, calculated like xterm does for DECRQCRA.
+    fn rectangle_checksum(&self, area: Rectangle) -> u16 {
+        let points = match self.rectangle_points(area) {
+            Some(points) => points,
+            None => return 0,
+        };
+
+        let mut checksum = 0u16;
+        for line in points.start().line.0..=points.end().line.0 {
+            let row = &self.grid[Line(line)];
+            for column in points.start().column.0..=points.end().column.0 {
+                let cell = &row[Column(column)];
+                if cell.flags.contains(Flags::WIDE_CHAR_SPACER) {
+                    continue;
+                }
+
+                checksum = checksum.wrapping_add(cell.c as u16);
+                for c in cell.zerowidth().unwrap_or_default() {
+                    checksum = checksum.wrapping_add(*c as u16);
+                }
+
+                let attributes = [
+                    (Flags::ALL_UNDERLINES, 0x10),
+                    (Flags::INVERSE, 0x20),
+                    (Flags::BLINK, 0x40),
+                    (Flags::BOLD, 0x80),
+                ];
+                for (flag, value) in attributes.iter() {
+                    if cell.flags.intersects(*flag) {
+                        checksum = checksum.wrapping_add(*value);
+                    }
+                }
+            }
+        }
+
+        checksum.wrapping_neg()
+    }
+
     fn deccolm(&mut self)
     where
         T: EventListener,
     {
         // Setting 132 column font makes no sense, but run the other side effects.
-        // Clear scrolling region.
+        // Clear scrolling region and disable left/right margins.
+        self.mode.remove(TermMode::LEFT_RIGHT_MARGIN);
+        self.horizontal_margins = Column(0)..Column(self.columns());
         self.set_scrolling_region(1, None);
 
         // Clear grid.
         self.grid.reset_region(..);
     }
 
+    /// Store the tiles of a graphic placement in the cells starting at the cursor.
+    ///
+    /// With `scroll`, the terminal is scrolled to fit the placement and the cursor is left on its
+    /// last line. Otherwise lines below the screen are clipped and the cursor is not moved.
+    fn place_graphic(&mut self, placement: &Placement, scroll: bool)
+    where
+        T: EventListener,
+    {
+        let start = self.grid.cursor.point;
+        let columns = min(placement.columns, self.columns() - start.column.0);
+
+        for line in 0..placement.lines {
+            let target = if scroll {
+                if line > 0 {
+                    self.linefeed();
+                }
+                self.grid.cursor.point.line
+            } else if start.line + line <= self.bottommost_line() {
+                start.line + line
+            } else {
+                break;
+            };
+
+            for column in 0..columns {
+                let cell = &mut self.grid[target][start.column + column];
+                *cell = Cell::default();
+                cell.set_graphic(Some(placement.cell(column, line)));
+            }
+        }
+    }
+
+    /// Decode and store a transmitted kitty image.
+    fn kitty_transmit(&mut self, command: &mut kitty::Command) -> Result<(), kitty::Error>
+    where
+        T: EventListener,
+    {
+        let graphic = command.decode()?;
+        if command.action == Action::Query {
+            return Ok(());
+        }
+
+        // Make room for the image by dropping images which are not displayed anywhere.
+        let size = graphic.width * graphic.height * 4;
+        while self.graphics.used_memory() + size > MAX_GRAPHICS_MEMORY
+            && self.kitty_images.evict_unused()
+        {}
+
+        let texture = self
+            .graphics
+            .insert(graphic)
+            .ok_or_else(|| kitty::Error::new("ENOMEM", "graphics memory limit exceeded"))?;
+
+        if command.image_id == 0 {
+            command.image_id = self.kitty_images.next_free_id();
+        }
+        self.kitty_images.insert(command.image_id, command.image_number, texture);
+
+        if command.action == Action::TransmitAndDisplay {
+            self.kitty_put(command)?;
+        }
+
+        Ok(())
+    }
+
+    /// Display a kitty image at the cursor position.
+    fn kitty_put(&mut self, command: &mut kitty::Command) -> Result<(), kitty::Error>
+    where
+        T: EventListener,
+    {
+        if command.image_id == 0 && command.image_number != 0 {
+            command.image_id = self.kitty_images.find_number(command.image_number).unwrap_or(0);
+        }
+
+        let texture = match self.kitty_images.get(command.image_id) {
+            Some(image) => image.texture.clone(),
+            None => return Err(kitty::Error::new("ENOENT", "image not found")),
+        };
+
+        // Clamp the displayed area to the image.
+        let x = min(command.source_x as usize, texture.width);
+        let y = min(command.source_y as usize, texture.height);
+        let width = match command.source_width as usize {
+            0 => texture.width - x,
+            width => min(width, texture.width - x),
+        };
+        let height = match command.source_height as usize {
+            0 => texture.height - y,
+            height => min(height, texture.height - y),
+        };
+
+        if width == 0 || height == 0 {
+            return Err(kitty::Error::new("EINVAL", "empty image area"));
+        }
+
+        // Without an explicit size, the image is displayed with its original size.
+        let cell_width = max(self.cell_width, 1);
+        let cell_height = max(self.cell_height, 1);
+        let (columns, width) = match min(command.columns as usize, MAX_GRAPHIC_DIMENSIONS) {
+            0 => ((width - 1) / cell_width + 1, ((width - 1) / cell_width + 1) * cell_width),
+            columns => (columns, width),
+        };
+        let (lines, height) = match min(command.rows as usize, MAX_GRAPHIC_DIMENSIONS) {
+            0 => ((height - 1) / cell_height + 1, ((height - 1) / cell_height + 1) * cell_height),
+            lines => (lines, height),
+        };
+
+        // Replace the previous placement with the same ID.
+        let placement_id = command.placement_id;
+        if placement_id != 0 {
+            let id = texture.id;
+            let lines = self.topmost_line()..Line(self.screen_lines() as i32);
+            self.delete_kitty_cells(lines, |graphic| {
+                graphic.texture.id == id && graphic.placement_id == placement_id
+            });
+        }
+
+        let placement =
+            Placement { texture, id: placement_id, x, y, width, height, columns, lines };
+
+        if command.move_cursor {
+            // Move the cursor to the cell after the last line of the image.
+            let start = self.grid.cursor.point.column;
+            self.place_graphic(&placement, true);
+            self.grid.cursor.point.column = min(start + columns, self.last_column());
+            self.grid.cursor.input_needs_wrap = false;
+        } else {
+            self.place_graphic(&placement, false);
+        }
+
+        Ok(())
+    }
+
+    /// Delete kitty image placements.
+    ///
+    /// Uppercase deletion targets also free the images which are no longer displayed.
+    fn kitty_delete(&mut self, command: &kitty::Command) {
+        let screen_lines = self.screen_lines() as i32;
+        let all_lines = self.topmost_line()..Line(screen_lines);
+
+        // Get placements intersecting the target cells.
+        let (x, y) = (command.source_x as usize, command.source_y as i32);
+        let targets: Vec<Point> = match command.delete.to_ascii_lowercase() {
+            b'c' => vec![self.grid.cursor.point],
+            b'p' if x > 0 && x <= self.columns() && y > 0 && y <= screen_lines => {
+                vec![Point::new(Line(y - 1), Column(x - 1))]
+            },
+            b'x' if x > 0 && x <= self.columns() => {
+                (0..screen_lines).map(|line| Point::new(Line(line), Column(x - 1))).collect()
+            },
+            b'y' if y > 0 && y <= screen_lines => {
+                (0..self.columns()).map(|column| Point::new(Line(y - 1), Column(column))).collect()
+            },
+            _ => Vec::new(),
+        };
+
+        let removed = match command.delete.to_ascii_lowercase() {
+            b'a' => self.delete_kitty_cells(Line(0)..Line(screen_lines), |_| true),
+            b'i' | b'n' => {
+                let id = if command.delete.eq_ignore_ascii_case(&b'n') {
+                    self.kitty_images.find_number(command.image_number)
+                } else {
+                    Some(command.image_id)
+                };
+
+                let texture = match id.and_then(|id| self.kitty_images.get(id)) {
+                    Some(image) => image.texture.id,
+                    None => return,
+                };
+
+                let placement_id = command.placement_id;
+                self.delete_kitty_cells(all_lines, |graphic| {
+                    graphic.texture.id == texture
+                        && (placement_id == 0 || graphic.placement_id == placement_id)
+                })
+            },
+            b'c' | b'p' | b'x' | b'y' => {
+                let placements: Vec<_> = targets
+                    .into_iter()
+                    .filter_map(|point| self.grid[point].graphic())
+                    .map(|graphic| (graphic.texture.id, graphic.placement_id))
+                    .collect();
+
+                self.delete_kitty_cells(all_lines, |graphic| {
+                    placements.contains(&(graphic.texture.id, graphic.placement_id))
+                })
+            },
+            _ => {
+                debug!("Unsupported kitty graphics deletion: {}", command.delete as char);
+                return;
+            },
+        };
+
+        if command.delete.is_ascii_uppercase() {
+            self.kitty_images.remove_unused(&removed);
+        }
+    }
+
+    /// Remove kitty image tiles matching the predicate from the specified lines.
+    ///
+    /// Returns the textures of all removed tiles.
+    fn delete_kitty_cells<F>(&mut self, lines: Range<Line>, predicate: F) -> Vec<GraphicId>
+    where
+        F: Fn(&GraphicCell) -> bool,
+    {
+        let mut removed = Vec::new();
+
+        for line in lines.start.0..lines.end.0 {
+            for cell in &mut self.grid[Line(line)][..] {
+                let texture = match cell.graphic() {
+                    Some(graphic)
+                        if predicate(graphic)
+                            && self.kitty_images.contains_texture(graphic.texture.id) =>
+                    {
+                        graphic.texture.id
+                    },
+                    _ => continue,
+                };
+
+                if !removed.contains(&texture) {
+                    removed.push(texture);
+                }
+                cell.set_graphic(None);
+            }
+        }
+
+        removed
+    }
+
     #[inline]
     pub fn exit(&mut self)
     where
@@ -734,6 +1348,55 @@ impl<T> Term<T> {
         point
     }
 
+    /// Find the closest line with a prompt mark in the specified direction.
+    ///
+    /// The `origin` line itself is not part of the search.
+    pub fn prompt_line(&self, origin: Line, direction: Direction) -> Option<Line> {
+        let is_prompt = |line: &Line| self.grid[*line].marks().contains(RowMarks::PROMPT);
+
+        match direction {
+            Direction::Left => (self.topmost_line().0..origin.0).rev().map(Line).find(is_prompt),
+            Direction::Right => (origin.0 + 1..=self.bottommost_line().0).map(Line).find(is_prompt),
+        }
+    }
+
+    /// Bounds of the output printed by the last command.
+    ///
+    /// This relies on the shell reporting its prompt marks through OSC 133.
+    pub fn last_command_output(&self) -> Option<RangeInclusive<Point>> {
+        let cursor_line = self.grid.cursor.point.line.0;
+        let marks = |line: i32| self.grid[Line(line)].marks();
+
+        let start = (self.topmost_line().0..=cursor_line)
+            .rev()
+            .find(|&line| marks(line).contains(RowMarks::OUTPUT))?;
+
+        // Stop before the next prompt, or at the cursor if the command is still running.
+        let end = (start..=cursor_line)
+            .find(|&line| marks(line).intersects(RowMarks::PROMPT | RowMarks::COMMAND_END))
+            .map_or(cursor_line, |line| line - 1);
+
+        if end < start {
+            return None;
+        }
+
+        Some(Point::new(Line(start), Column(0))..=Point::new(Line(end), self.last_column()))
+    }
+
+    /// Window title set by the application.
+    #[inline]
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// Working directory reported by the shell through OSC 7.
+    ///
+    /// Directories on remote hosts are ignored, since they cannot be accessed locally.
+    #[inline]
+    pub fn working_directory(&self) -> Option<&Path> {
+        self.working_directory.as_deref()
+    }
+
     #[inline]
     pub fn semantic_escape_chars(&self) -> &str {
         &self.semantic_escape_chars
@@ -765,7 +1428,17 @@ impl<T> Term<T> {
 
         trace!("Wrapping input");
 
-        self.grid.cursor_cell().flags.insert(Flags::WRAPLINE);
+        // Only lines wrapping at the end of the screen continue on the next line.
+        if self.grid.cursor.point.column == self.last_column() {
+            self.grid.cursor_cell().flags.insert(Flags::WRAPLINE);
+        }
+
+        // Continue at the left margin when wrapping at the right margin.
+        let column = if self.cursor_in_horizontal_margins() {
+            self.horizontal_margins.start
+        } else {
+            Column(0)
+        };
 
         if self.grid.cursor.point.line + 1 >= self.scroll_region.end {
             self.linefeed();
@@ -773,17 +1446,75 @@ impl<T> Term<T> {
             self.grid.cursor.point.line += 1;
         }
 
-        self.grid.cursor.point.column = Column(0);
+        self.grid.cursor.point.column = column;
         self.grid.cursor.input_needs_wrap = false;
     }
 
+    /// Add `c` to the grapheme cluster in front of the cursor.
+    ///
+    /// Returns `false` without modifying the grid if `c` starts a new grapheme cluster.
+    fn extend_grapheme_cluster(&mut self, c: char) -> bool {
+        let line = self.grid.cursor.point.line;
+        let mut column = self.grid.cursor.point.column;
+
+        // Find the cell holding the previous cluster, skipping wide char spacers.
+        if !self.grid.cursor.input_needs_wrap {
+            if column == 0 {
+                return false;
+            }
+            column -= 1;
+        }
+        if self.grid[line][column].flags.contains(Flags::WIDE_CHAR_SPACER) && column > 0 {
+            column -= 1;
+        }
+
+        // Reuse the buffer to avoid allocating for every character.
+        let cell = &self.grid[line][column];
+        let cluster = &mut self.grapheme_buffer;
+        cluster.clear();
+        cluster.push(cell.c);
+        cluster.extend(cell.zerowidth().unwrap_or_default());
+        let offset = cluster.len();
+        cluster.push(c);
+
+        let mut cursor = GraphemeCursor::new(offset, cluster.len(), true);
+        if cursor.is_boundary(cluster, 0).unwrap_or(true) {
+            return false;
+        }
+
+        let was_wide = cell.flags.contains(Flags::WIDE_CHAR);
+        self.grid[line][column].push_zerowidth(c);
+
+        // Widen clusters rendered as emoji, unless there's no space left for the spacer.
+        let width = grapheme_width(&self.grapheme_buffer, self.ambiguous_width);
+        if was_wide || width < 2 || self.grid.cursor.input_needs_wrap {
+            return true;
+        }
+
+        self.grid[line][column].flags.insert(Flags::WIDE_CHAR);
+        self.grid.cursor.template.flags.insert(Flags::WIDE_CHAR_SPACER);
+        self.write_at_cursor(' ');
+        self.grid.cursor.template.flags.remove(Flags::WIDE_CHAR_SPACER);
+
+        if self.grid.cursor.point.column + 1 < self.input_columns() {
+            self.grid.cursor.point.column += 1;
+        } else {
+            self.grid.cursor.input_needs_wrap = true;
+        }
+
+        true
+    }
+
     /// Write `c` to the cell at the cursor position.
     #[inline(always)]
     fn write_at_cursor(&mut self, c: char) {
-        let c = self.grid.cursor.charsets[self.active_charset].map(c);
+        let charset = self.single_shift_charset.take().unwrap_or(self.active_charset);
+        let c = self.grid.cursor.charsets[charset].map(c);
         let fg = self.grid.cursor.template.fg;
         let bg = self.grid.cursor.template.bg;
         let flags = self.grid.cursor.template.flags;
+        let hyperlink = self.grid.cursor.template.hyperlink();
+        let underline_color = self.grid.cursor.template.underline_color();
 
         let mut cursor_cell = self.grid.cursor_cell();
 
@@ -813,6 +1544,8 @@ impl<T> Term<T> {
         cursor_cell.fg = fg;
         cursor_cell.bg = bg;
         cursor_cell.flags = flags;
+        cursor_cell.set_hyperlink(hyperlink);
+        cursor_cell.set_underline_color(underline_color);
     }
 }
 
@@ -838,11 +1571,22 @@ impl<T: EventListener> Handler for Term<T> {
     #[inline(never)]
     fn input(&mut self, c: char) {
         // Number of cells the char will occupy.
-        let width = match c.width() {
+        let width = match self.ambiguous_width.char_width(c) {
             Some(width) => width,
             None => return,
         };
 
+        // Add characters continuing a grapheme cluster to the cell of the cluster.
+        //
+        // ASCII characters always start a new cluster, which allows skipping segmentation for
+        // most of the input.
+        if !c.is_ascii()
+            && self.mode.contains(TermMode::GRAPHEME_CLUSTERING)
+            && self.extend_grapheme_cluster(c)
+        {
+            return;
+        }
+
         // Handle zero-width characters.
         if width == 0 {
             // Get previous column.
@@ -866,8 +1610,12 @@ impl<T: EventListener> Handler for Term<T> {
             self.wrapline();
         }
 
+        let columns = self.input_columns();
+        if self.grid.cursor.point.column >= columns {
+            self.grid.cursor.point.column = Column(columns - 1);
+        }
+
         // If in insert mode, first shift cells to the right.
-        let columns = self.columns();
         if self.mode.contains(TermMode::INSERT) && self.grid.cursor.point.column + width < columns {
             let line = self.grid.cursor.point.line;
             let col = self.grid.cursor.point.column;
@@ -924,46 +1672,239 @@ impl<T: EventListener> Handler for Term<T> {
                 *cell = Cell::default();
                 cell.c = 'E';
             }
+
+            self.grid[line].line_size = LineSize::Normal;
         }
     }
 
     #[inline]
-    fn goto(&mut self, line: Line, col: Column) {
-        trace!("Going to: line={}, col={}", line, col);
-        let (y_offset, max_y) = if self.mode.contains(TermMode::ORIGIN) {
-            (self.scroll_region.start, self.scroll_region.end - 1)
-        } else {
-            (Line(0), self.bottommost_line())
-        };
+    fn set_line_size(&mut self, size: LineSize) {
+        trace!("Setting line size: {:?}", size);
 
-        self.grid.cursor.point.line = max(min(line + y_offset, max_y), Line(0));
-        self.grid.cursor.point.column = min(col, self.last_column());
-        self.grid.cursor.input_needs_wrap = false;
-    }
+        let line = self.grid.cursor.point.line;
+        self.grid[line].line_size = size;
 
-    #[inline]
-    fn goto_line(&mut self, line: Line) {
-        trace!("Going to line: {}", line);
-        self.goto(line, self.grid.cursor.point.column)
+        // Characters in the right half of the screen are lost on double-width lines.
+        let columns = self.line_columns(line);
+        for cell in &mut self.grid[line][Column(columns)..] {
+            *cell = Cell::default();
+        }
+
+        let column = min(self.grid.cursor.point.column, Column(columns - 1));
+        self.grid.cursor.point.column = column;
     }
 
     #[inline]
-    fn goto_col(&mut self, col: Column) {
-        trace!("Going to column: {}", col);
-        self.goto(self.grid.cursor.point.line, col)
+    fn copy_area(&mut self, area: Rectangle, top: usize, left: usize) {
+        trace!("Copying area {:?} to line {}, column {}", area, top, left);
+
+        let source = match self.rectangle_points(area) {
+            Some(source) => source,
+            None => return,
+        };
+        let destination = match self.rectangle_points(Rectangle::new(top, left, None, None)) {
+            Some(destination) => destination,
+            None => return,
+        };
+
+        // Clip the copied area to the available space at the destination.
+        let (start, end) = (*source.start(), *source.end());
+        let (destination, destination_end) = (*destination.start(), *destination.end());
+        let lines = min(end.line - start.line, destination_end.line - destination.line);
+        let columns = min(end.column - start.column, destination_end.column - destination.column);
+
+        // Copy cells to a buffer first, since source and destination might overlap.
+        let mut cells = Vec::new();
+        for line in 0..=lines.0 {
+            let row = &self.grid[start.line + line];
+            let row_start = cells.len();
+            for column in 0..=columns.0 {
+                cells.push(row[start.column + column].clone());
+            }
+
+            // Drop halves of wide characters split by the edges of the source area.
+            cells[row_start].flags.remove(Flags::WIDE_CHAR_SPACER);
+            let last = cells.len() - 1;
+            if cells[last].flags.contains(Flags::WIDE_CHAR) {
+                cells[last].clear_wide();
+            }
+        }
+
+        let destination_end = Point::new(destination.line + lines, destination.column + columns);
+        self.clear_split_wide_chars(&(destination..=destination_end));
+
+        let mut cells = cells.into_iter();
+        for line in 0..=lines.0 {
+            let row = &mut self.grid[destination.line + line];
+            for column in 0..=columns.0 {
+                row[destination.column + column] = cells.next().unwrap();
+            }
+        }
+    }
+
+    #[inline]
+    fn fill_area(&mut self, c: char, area: Rectangle) {
+        trace!("Filling area {:?} with {:?}", area, c);
+
+        // Only characters occupying a single cell can be used for filling.
+        if self.ambiguous_width.char_width(c) != Some(1) {
+            return;
+        }
+
+        let points = match self.rectangle_points(area) {
+            Some(points) => points,
+            None => return,
+        };
+
+        self.clear_split_wide_chars(&points);
+
+        let mut cell = self.grid.cursor.template.clone();
+        cell.c = self.grid.cursor.charsets[self.active_charset].map(c);
+
+        for line in points.start().line.0..=points.end().line.0 {
+            let row = &mut self.grid[Line(line)];
+            for column in points.start().column.0..=points.end().column.0 {
+                row[Column(column)] = cell.clone();
+            }
+        }
+    }
+
+    #[inline]
+    fn erase_area(&mut self, area: Rectangle) {
+        trace!("Erasing area {:?}", area);
+
+        let points = match self.rectangle_points(area) {
+            Some(points) => points,
+            None => return,
+        };
+
+        self.clear_split_wide_chars(&points);
+
+        // Cleared cells have current background color set.
+        let bg = self.grid.cursor.template.bg;
+        for line in points.start().line.0..=points.end().line.0 {
+            let row = &mut self.grid[Line(line)];
+            for column in points.start().column.0..=points.end().column.0 {
+                row[Column(column)] = bg.into();
+            }
+        }
+    }
+
+    #[inline]
+    fn change_area_attributes(&mut self, area: Rectangle, attrs: &[Attr]) {
+        trace!("Changing attributes of area {:?} to {:?}", area, attrs);
+
+        let points = match self.rectangle_points(area) {
+            Some(points) => points,
+            None => return,
+        };
+
+        // Combine all attributes into the flags which are added and removed.
+        let mut insert = Flags::empty();
+        let mut remove = Flags::empty();
+        for attr in attrs {
+            let (added, removed) = match attr {
+                Attr::Reset => (
+                    Flags::empty(),
+                    Flags::BOLD | Flags::ALL_UNDERLINES | Flags::BLINK | Flags::INVERSE,
+                ),
+                Attr::Bold => (Flags::BOLD, Flags::empty()),
+                Attr::Underline => (Flags::UNDERLINE, Flags::ALL_UNDERLINES),
+                Attr::BlinkSlow | Attr::BlinkFast => (Flags::BLINK, Flags::empty()),
+                Attr::Reverse => (Flags::INVERSE, Flags::empty()),
+                Attr::CancelBold => (Flags::empty(), Flags::BOLD),
+                Attr::CancelUnderline => (Flags::empty(), Flags::ALL_UNDERLINES),
+                Attr::CancelBlink => (Flags::empty(), Flags::BLINK),
+                Attr::CancelReverse => (Flags::empty(), Flags::INVERSE),
+                _ => continue,
+            };
+            insert = (insert - removed) | added;
+            remove = (remove | removed) - added;
+        }
+
+        let (start, end) = (*points.start(), *points.end());
+        for line in start.line.0..=end.line.0 {
+            let line = Line(line);
+
+            // Without rectangular extent, all cells from start to end are changed.
+            let (first, last) = if self.rectangular_attribute_change {
+                (start.column, end.column)
+            } else {
+                let first = if line == start.line { start.column } else { Column(0) };
+                let last = if line == end.line { end.column } else { self.last_column() };
+                (first, last)
+            };
+
+            let row = &mut self.grid[line];
+            for column in first.0..=last.0 {
+                let flags = &mut row[Column(column)].flags;
+                *flags = (*flags - remove) | insert;
+            }
+        }
+    }
+
+    #[inline]
+    fn set_attribute_change_extent(&mut self, rectangular: bool) {
+        trace!("Setting rectangular attribute change extent: {}", rectangular);
+        self.rectangular_attribute_change = rectangular;
+    }
+
+    #[inline]
+    fn report_area_checksum(&mut self, id: u16, area: Rectangle) {
+        trace!("Reporting checksum of area {:?}", area);
+
+        let checksum = self.rectangle_checksum(area);
+        let text = format!("\x1bP{}!~{:04X}\x1b\\", id, checksum);
+        self.event_proxy.send_event(Event::PtyWrite(text));
+    }
+
+    #[inline]
+    fn goto(&mut self, line: Line, col: Column) {
+        trace!("Going to: line={}, col={}", line, col);
+        let (y_offset, max_y, x_offset, max_x) = if self.mode.contains(TermMode::ORIGIN) {
+            let margins = &self.horizontal_margins;
+            (self.scroll_region.start, self.scroll_region.end - 1, margins.start, margins.end - 1)
+        } else {
+            (Line(0), self.bottommost_line(), Column(0), self.last_column())
+        };
+
+        let line = max(min(line + y_offset, max_y), Line(0));
+        let max_x = min(max_x, Column(self.line_columns(line) - 1));
+
+        self.grid.cursor.point.line = line;
+        self.grid.cursor.point.column = min(col + x_offset, max_x);
+        self.grid.cursor.input_needs_wrap = false;
+    }
+
+    #[inline]
+    fn goto_line(&mut self, line: Line) {
+        trace!("Going to line: {}", line);
+        self.goto(line, self.cursor_origin_column())
+    }
+
+    #[inline]
+    fn goto_col(&mut self, col: Column) {
+        trace!("Going to column: {}", col);
+        self.goto(self.grid.cursor.point.line, col)
     }
 
     #[inline]
     fn insert_blank(&mut self, count: usize) {
+        // Cells are only inserted between the left and right margins.
+        if !self.cursor_in_horizontal_margins() {
+            return;
+        }
+
         let cursor = &self.grid.cursor;
         let bg = cursor.template.bg;
+        let end = self.horizontal_margins.end.0;
 
-        // Ensure inserting within terminal bounds
-        let count = min(count, self.columns() - cursor.point.column.0);
+        // Ensure inserting within margin bounds.
+        let count = min(count, end - cursor.point.column.0);
 
         let source = cursor.point.column;
         let destination = cursor.point.column.0 + count;
-        let num_cells = self.columns() - destination;
+        let num_cells = end - destination;
 
         let line = cursor.point.line;
         let row = &mut self.grid[line][..];
@@ -982,19 +1923,28 @@ impl<T: EventListener> Handler for Term<T> {
     #[inline]
     fn move_up(&mut self, lines: usize) {
         trace!("Moving up: {}", lines);
-        self.goto(self.grid.cursor.point.line - lines, self.grid.cursor.point.column)
+        self.goto(self.grid.cursor.point.line - lines, self.cursor_origin_column())
     }
 
     #[inline]
     fn move_down(&mut self, lines: usize) {
         trace!("Moving down: {}", lines);
-        self.goto(self.grid.cursor.point.line + lines, self.grid.cursor.point.column)
+        self.goto(self.grid.cursor.point.line + lines, self.cursor_origin_column())
     }
 
     #[inline]
     fn move_forward(&mut self, cols: Column) {
         trace!("Moving forward: {}", cols);
-        let last_column = self.last_column();
+
+        // Stop at the right margin when the cursor is between the margins.
+        let last_column = if self.cursor_in_horizontal_margins() {
+            self.horizontal_margins.end - 1
+        } else {
+            self.last_column()
+        };
+        let line_end = Column(self.line_columns(self.grid.cursor.point.line) - 1);
+        let last_column = min(last_column, line_end);
+
         self.grid.cursor.point.column = min(self.grid.cursor.point.column + cols, last_column);
         self.grid.cursor.input_needs_wrap = false;
     }
@@ -1002,8 +1952,16 @@ impl<T: EventListener> Handler for Term<T> {
     #[inline]
     fn move_backward(&mut self, cols: Column) {
         trace!("Moving backward: {}", cols);
-        self.grid.cursor.point.column =
-            Column(self.grid.cursor.point.column.saturating_sub(cols.0));
+
+        // Stop at the left margin when the cursor is between the margins.
+        let first_column = if self.cursor_in_horizontal_margins() {
+            self.horizontal_margins.start
+        } else {
+            Column(0)
+        };
+
+        let column = Column(self.grid.cursor.point.column.saturating_sub(cols.0));
+        self.grid.cursor.point.column = max(column, first_column);
         self.grid.cursor.input_needs_wrap = false;
     }
 
@@ -1012,7 +1970,12 @@ impl<T: EventListener> Handler for Term<T> {
         match intermediate {
             None => {
                 trace!("Reporting primary device attributes");
-                let text = String::from("\x1b[?6c");
+
+                // VT220 with sixel graphics, ANSI colors and rectangular editing.
+                let mut text = String::from("\x1b[?62");
+                #[cfg(feature = "sixel")]
+                text.push_str(";4");
+                text.push_str(";22;28c");
                 self.event_proxy.send_event(Event::PtyWrite(text));
             },
             Some('>') => {
@@ -1021,10 +1984,23 @@ impl<T: EventListener> Handler for Term<T> {
                 let text = format!("\x1b[>0;{};1c", version);
                 self.event_proxy.send_event(Event::PtyWrite(text));
             },
+            Some('=') => {
+                trace!("Reporting tertiary device attributes");
+                let text = String::from("\x1bP!|00000000\x1b\\");
+                self.event_proxy.send_event(Event::PtyWrite(text));
+            },
             _ => debug!("Unsupported device attributes intermediate"),
         }
     }
 
+    #[inline]
+    fn report_version(&mut self) {
+        trace!("Reporting terminal version");
+        let version = version_number(env!("CARGO_PKG_VERSION"));
+        let text = format!("\x1bP>|alacritty({})\x1b\\", version);
+        self.event_proxy.send_event(Event::PtyWrite(text));
+    }
+
     #[inline]
     fn device_status(&mut self, arg: usize) {
         trace!("Reporting device status: {}", arg);
@@ -1042,6 +2018,166 @@ impl<T: EventListener> Handler for Term<T> {
         };
     }
 
+    #[inline]
+    fn report_mode(&mut self, private: bool, mode: u16) {
+        let prefix = if private { "?" } else { "" };
+        trace!("Reporting mode {}{}", prefix, mode);
+
+        let intermediate = if private { Some(&b'?') } else { None };
+        let state = match ansi::Mode::from_primitive(intermediate, mode) {
+            Some(mode) => self.mode_state(&mode),
+            None => ModeState::NotRecognized,
+        };
+
+        let text = format!("\x1b[{}{};{}$y", prefix, mode, state as u8);
+        self.event_proxy.send_event(Event::PtyWrite(text));
+    }
+
+    #[inline]
+    fn report_sync_update(&mut self, active: bool) {
+        trace!("Reporting synchronized update mode: {}", active);
+
+        let state = ModeState::from(active);
+        let text = format!("\x1b[?{};{}$y", ansi::Mode::SyncUpdate as u16, state as u8);
+        self.event_proxy.send_event(Event::PtyWrite(text));
+    }
+
+    #[inline]
+    fn report_status_string(&mut self, query: Option<StatusStringQuery>) {
+        trace!("Reporting status string {:?}", query);
+
+        let setting = match query {
+            Some(StatusStringQuery::GraphicRendition) => format!("{}m", self.graphic_rendition()),
+            Some(StatusStringQuery::CursorStyle) => {
+                let style = self.cursor_style.unwrap_or(self.default_cursor_style);
+                let shape = match style.shape {
+                    CursorShape::Underline => 3,
+                    CursorShape::Beam => 5,
+                    _ => 1,
+                };
+                format!("{} q", shape + !style.blinking as u8)
+            },
+            Some(StatusStringQuery::ScrollingRegion) => {
+                let region = &self.scroll_region;
+                format!("{};{}r", region.start + 1, region.end)
+            },
+            Some(StatusStringQuery::LeftRightMargins) => {
+                let margins = &self.horizontal_margins;
+                format!("{};{}s", margins.start + 1, margins.end)
+            },
+            None => {
+                self.event_proxy.send_event(Event::PtyWrite(String::from("\x1bP0$r\x1b\\")));
+                return;
+            },
+        };
+
+        let text = format!("\x1bP1$r{}\x1b\\", setting);
+        self.event_proxy.send_event(Event::PtyWrite(text));
+    }
+
+    #[inline]
+    fn report_termcap(&mut self, name: &str) {
+        trace!("Reporting terminfo capability {}", name);
+
+        let text = match termcap::lookup(name) {
+            Some(Some(value)) => {
+                format!("\x1bP1+r{}={}\x1b\\", hex_encode(name), hex_encode(value))
+            },
+            Some(None) => format!("\x1bP1+r{}\x1b\\", hex_encode(name)),
+            None => format!("\x1bP0+r{}\x1b\\", hex_encode(name)),
+        };
+        self.event_proxy.send_event(Event::PtyWrite(text));
+    }
+
+    #[inline]
+    fn insert_graphic(&mut self, graphic: GraphicData) {
+        trace!("Inserting graphic: {}x{}", graphic.width, graphic.height);
+
+        if graphic.width == 0 || graphic.height == 0 {
+            return;
+        }
+
+        let cell_width = max(self.cell_width, 1);
+        let cell_height = max(self.cell_height, 1);
+        let columns = (graphic.width - 1) / cell_width + 1;
+        let lines = (graphic.height - 1) / cell_height + 1;
+
+        let texture = match self.graphics.insert(graphic) {
+            Some(texture) => texture,
+            None => {
+                debug!("Ignoring graphic, memory limit for graphics exceeded");
+                return;
+            },
+        };
+
+        let placement = Placement {
+            texture,
+            id: 0,
+            x: 0,
+            y: 0,
+            width: columns * cell_width,
+            height: lines * cell_height,
+            columns,
+            lines,
+        };
+
+        let start = self.grid.cursor.point.column;
+        self.place_graphic(&placement, true);
+
+        // Place the cursor below the graphic.
+        self.linefeed();
+        self.grid.cursor.point.column = start;
+        self.grid.cursor.input_needs_wrap = false;
+    }
+
+    #[inline]
+    fn kitty_graphics(&mut self, command: kitty::Command) {
+        trace!("Kitty graphics command: {:?}", command.action);
+
+        let mut command = match self.kitty_images.add_chunk(command) {
+            Some(command) => command,
+            None => return,
+        };
+
+        // Clients only expect responses for commands referencing an image.
+        let respond = command.image_id != 0 || command.image_number != 0;
+
+        let result = match command.action {
+            Action::Transmit | Action::TransmitAndDisplay | Action::Query => {
+                self.kitty_transmit(&mut command)
+            },
+            Action::Put => self.kitty_put(&mut command),
+            Action::Delete => {
+                self.kitty_delete(&command);
+                return;
+            },
+        };
+
+        let status = match result {
+            Ok(()) if command.quiet == 0 => String::from("OK"),
+            Err(err) if command.quiet < 2 => {
+                debug!("Kitty graphics command failed: {}", err);
+                err.to_string()
+            },
+            _ => return,
+        };
+
+        if !respond {
+            return;
+        }
+
+        let mut text = format!("\x1b_Gi={}", command.image_id);
+        if command.image_number != 0 {
+            text.push_str(&format!(",I={}", command.image_number));
+        }
+        if command.placement_id != 0 {
+            text.push_str(&format!(",p={}", command.placement_id));
+        }
+        text.push_str(&format!(";{}\x1b\\", status));
+
+        self.event_proxy.send_event(Event::PtyWrite(text));
+    }
+
     #[inline]
     fn move_down_and_cr(&mut self, lines: usize) {
         trace!("Moving down and cr: {}", lines);
@@ -1063,7 +2199,9 @@ impl<T: EventListener> Handler for Term<T> {
             return;
         }
 
-        while self.grid.cursor.point.column < self.columns() && count != 0 {
+        let line = self.grid.cursor.point.line;
+        let columns = self.line_columns(line);
+        while self.grid.cursor.point.column < columns && count != 0 {
             count -= 1;
 
             let c = self.grid.cursor.charsets[self.active_charset].map('\t');
@@ -1073,13 +2211,13 @@ impl<T: EventListener> Handler for Term<T> {
             }
 
             loop {
-                if (self.grid.cursor.point.column + 1) == self.columns() {
+                if (self.grid.cursor.point.column + 1) >= columns {
                     break;
                 }
 
                 self.grid.cursor.point.column += 1;
 
-                if self.tabs[self.grid.cursor.point.column] {
+                if self.is_tab_stop(line, self.grid.cursor.point.column) {
                     break;
                 }
             }
@@ -1101,7 +2239,14 @@ impl<T: EventListener> Handler for Term<T> {
     #[inline]
     fn carriage_return(&mut self) {
         trace!("Carriage return");
-        self.grid.cursor.point.column = Column(0);
+
+        // Return to the left margin, unless the cursor is already left of it.
+        let left_margin = self.horizontal_margins.start;
+        if self.grid.cursor.point.column >= left_margin {
+            self.grid.cursor.point.column = left_margin;
+        } else {
+            self.grid.cursor.point.column = Column(0);
+        }
         self.grid.cursor.input_needs_wrap = false;
     }
 
@@ -1111,7 +2256,10 @@ impl<T: EventListener> Handler for Term<T> {
         trace!("Linefeed");
         let next = self.grid.cursor.point.line + 1;
         if next == self.scroll_region.end {
-            self.scroll_up(1);
+            // Content is only scrolled with the cursor between the left and right margins.
+            if self.cursor_in_horizontal_margins() {
+                self.scroll_up(1);
+            }
         } else if next < self.screen_lines() {
             self.grid.cursor.point.line += 1;
         }
@@ -1183,7 +2331,7 @@ impl<T: EventListener> Handler for Term<T> {
         trace!("Inserting blank {} lines", lines);
 
         let origin = self.grid.cursor.point.line;
-        if self.scroll_region.contains(&origin) {
+        if self.scroll_region.contains(&origin) && self.cursor_in_horizontal_margins() {
             self.scroll_down_relative(origin, lines);
         }
     }
@@ -1195,7 +2343,8 @@ impl<T: EventListener> Handler for Term<T> {
 
         trace!("Deleting {} lines", lines);
 
-        if lines > 0 && self.scroll_region.contains(&origin) {
+        if lines > 0 && self.scroll_region.contains(&origin) && self.cursor_in_horizontal_margins()
+        {
             self.scroll_up_relative(origin, lines);
         }
     }
@@ -1220,15 +2369,20 @@ impl<T: EventListener> Handler for Term<T> {
 
     #[inline]
     fn delete_chars(&mut self, count: usize) {
-        let columns = self.columns();
+        // Cells are only deleted between the left and right margins.
+        if !self.cursor_in_horizontal_margins() {
+            return;
+        }
+
+        let columns = self.horizontal_margins.end.0;
         let cursor = &self.grid.cursor;
         let bg = cursor.template.bg;
 
-        // Ensure deleting within terminal bounds.
-        let count = min(count, columns);
-
+        // Ensure deleting within margin bounds.
         let start = cursor.point.column.0;
-        let end = min(start + count, columns - 1);
+        let count = min(count, columns - start);
+
+        let end = start + count;
         let num_cells = columns - end;
 
         let line = cursor.point.line;
@@ -1241,7 +2395,7 @@ impl<T: EventListener> Handler for Term<T> {
         // Clear last `count` cells in the row. If deleting 1 char, need to delete
         // 1 cell.
         let end = columns - count;
-        for cell in &mut row[end..] {
+        for cell in &mut row[end..columns] {
             *cell = bg.into();
         }
     }
@@ -1250,10 +2404,11 @@ impl<T: EventListener> Handler for Term<T> {
     fn move_backward_tabs(&mut self, count: u16) {
         trace!("Moving backward {} tabs", count);
 
+        let line = self.grid.cursor.point.line;
         for _ in 0..count {
             let mut col = self.grid.cursor.point.column;
             for i in (0..(col.0)).rev() {
-                if self.tabs[index::Column(i)] {
+                if self.is_tab_stop(line, index::Column(i)) {
                     col = index::Column(i);
                     break;
                 }
@@ -1323,7 +2478,13 @@ impl<T: EventListener> Handler for Term<T> {
     /// Write a foreground/background color escape sequence with the current color.
     #[inline]
     fn dynamic_color_sequence(&mut self, code: u8, index: usize, terminator: &str) {
-        trace!("Requested write of escape sequence for color code {}: color[{}]", code, index);
+        self.indexed_color_sequence(code.to_string(), index, terminator);
+    }
+
+    /// Write a palette color escape sequence with the current color.
+    #[inline]
+    fn indexed_color_sequence(&mut self, prefix: String, index: usize, terminator: &str) {
+        trace!("Requested write of escape sequence for color code {}: color[{}]", prefix, index);
 
         let terminator = terminator.to_owned();
         self.event_proxy.send_event(Event::ColorRequest(
@@ -1331,7 +2492,7 @@ impl<T: EventListener> Handler for Term<T> {
             Arc::new(move |color| {
                 format!(
                     "\x1b]{};rgb:{1:02x}{1:02x}/{2:02x}{2:02x}/{3:02x}{3:02x}{4}",
-                    code, color.r, color.g, color.b, terminator
+                    prefix, color.r, color.g, color.b, terminator
                 )
             }),
         ));
@@ -1458,14 +2619,23 @@ impl<T: EventListener> Handler for Term<T> {
             mem::swap(&mut self.grid, &mut self.inactive_grid);
         }
         self.active_charset = Default::default();
+        self.single_shift_charset = None;
         self.cursor_style = None;
         self.grid.reset();
         self.inactive_grid.reset();
         self.scroll_region = Line(0)..Line(self.screen_lines() as i32);
+        self.horizontal_margins = Column(0)..Column(self.columns());
+        self.rectangular_attribute_change = false;
         self.tabs = TabStops::new(self.columns());
         self.title_stack = Vec::new();
-        self.title = None;
+        self.set_title(None);
+        self.colors = Colors::default();
         self.selection = None;
+        self.mouse_highlight = None;
+        self.keyboard_mode_stack = Vec::new();
+        self.inactive_keyboard_mode_stack = Vec::new();
+        self.kitty_images = ImageStore::default();
+        self.reset_mouse_cursor_shape();
 
         // Preserve vi mode across resets.
         self.mode &= TermMode::VI;
@@ -1475,12 +2645,38 @@ impl<T: EventListener> Handler for Term<T> {
         self.event_proxy.send_event(Event::CursorBlinkingChange(blinking));
     }
 
+    #[inline]
+    fn soft_reset(&mut self) {
+        trace!("Soft reset");
+
+        // Autowrap is restored to its default instead of being disabled, like xterm does.
+        self.mode.remove(
+            TermMode::ORIGIN | TermMode::INSERT | TermMode::APP_CURSOR | TermMode::APP_KEYPAD,
+        );
+        self.mode.insert(TermMode::SHOW_CURSOR | TermMode::LINE_WRAP);
+
+        self.scroll_region = Line(0)..Line(self.screen_lines() as i32);
+        self.horizontal_margins = Column(0)..Column(self.columns());
+
+        // Reset attributes and character sets, without moving the cursor.
+        self.grid.cursor.template = Cell::default();
+        self.grid.cursor.charsets = Default::default();
+        self.active_charset = Default::default();
+        self.single_shift_charset = None;
+
+        // The saved cursor returns to the home position.
+        self.grid.saved_cursor = Default::default();
+    }
+
     #[inline]
     fn reverse_index(&mut self) {
         trace!("Reversing index");
         // If cursor is at the top.
         if self.grid.cursor.point.line == self.scroll_region.start {
-            self.scroll_down(1);
+            // Content is only scrolled with the cursor between the left and right margins.
+            if self.cursor_in_horizontal_margins() {
+                self.scroll_down(1);
+            }
         } else {
             self.grid.cursor.point.line = max(self.grid.cursor.point.line - 1, Line(0));
         }
@@ -1494,10 +2690,12 @@ impl<T: EventListener> Handler for Term<T> {
         match attr {
             Attr::Foreground(color) => cursor.template.fg = color,
             Attr::Background(color) => cursor.template.bg = color,
+            Attr::UnderlineColor(color) => cursor.template.set_underline_color(color),
             Attr::Reset => {
                 cursor.template.fg = Color::Named(NamedColor::Foreground);
                 cursor.template.bg = Color::Named(NamedColor::Background);
                 cursor.template.flags = Flags::empty();
+                cursor.template.set_underline_color(None);
             },
             Attr::Reverse => cursor.template.flags.insert(Flags::INVERSE),
             Attr::CancelReverse => cursor.template.flags.remove(Flags::INVERSE),
@@ -1508,22 +2706,44 @@ impl<T: EventListener> Handler for Term<T> {
             Attr::Italic => cursor.template.flags.insert(Flags::ITALIC),
             Attr::CancelItalic => cursor.template.flags.remove(Flags::ITALIC),
             Attr::Underline => {
-                cursor.template.flags.remove(Flags::DOUBLE_UNDERLINE);
+                cursor.template.flags.remove(Flags::ALL_UNDERLINES);
                 cursor.template.flags.insert(Flags::UNDERLINE);
             },
             Attr::DoubleUnderline => {
-                cursor.template.flags.remove(Flags::UNDERLINE);
+                cursor.template.flags.remove(Flags::ALL_UNDERLINES);
                 cursor.template.flags.insert(Flags::DOUBLE_UNDERLINE);
             },
-            Attr::CancelUnderline => {
-                cursor.template.flags.remove(Flags::UNDERLINE | Flags::DOUBLE_UNDERLINE);
+            Attr::Undercurl => {
+                cursor.template.flags.remove(Flags::ALL_UNDERLINES);
+                cursor.template.flags.insert(Flags::UNDERCURL);
+            },
+            Attr::DottedUnderline => {
+                cursor.template.flags.remove(Flags::ALL_UNDERLINES);
+                cursor.template.flags.insert(Flags::DOTTED_UNDERLINE);
             },
+            Attr::DashedUnderline => {
+                cursor.template.flags.remove(Flags::ALL_UNDERLINES);
+                cursor.template.flags.insert(Flags::DASHED_UNDERLINE);
+            },
+            Attr::CancelUnderline => cursor.template.flags.remove(Flags::ALL_UNDERLINES),
+            Attr::BlinkSlow | Attr::BlinkFast => cursor.template.flags.insert(Flags::BLINK),
+            Attr::CancelBlink => cursor.template.flags.remove(Flags::BLINK),
             Attr::Hidden => cursor.template.flags.insert(Flags::HIDDEN),
             Attr::CancelHidden => cursor.template.flags.remove(Flags::HIDDEN),
             Attr::Strike => cursor.template.flags.insert(Flags::STRIKEOUT),
             Attr::CancelStrike => cursor.template.flags.remove(Flags::STRIKEOUT),
-            _ => {
-                debug!("Term got unhandled attr: {:?}", attr);
+            Attr::Overline => cursor.template.flags.insert(Flags::OVERLINE),
+            Attr::CancelOverline => cursor.template.flags.remove(Flags::OVERLINE),
+            Attr::Superscript => {
+                cursor.template.flags.remove(Flags::SUBSCRIPT);
+                cursor.template.flags.insert(Flags::SUPERSCRIPT);
+            },
+            Attr::Subscript => {
+                cursor.template.flags.remove(Flags::SUPERSCRIPT);
+                cursor.template.flags.insert(Flags::SUBSCRIPT);
+            },
+            Attr::CancelSuperscriptSubscript => {
+                cursor.template.flags.remove(Flags::SUPERSCRIPT | Flags::SUBSCRIPT)
             },
         }
     }
@@ -1546,6 +2766,11 @@ impl<T: EventListener> Handler for Term<T> {
                 self.mode.insert(TermMode::MOUSE_REPORT_CLICK);
                 self.event_proxy.send_event(Event::MouseCursorDirty);
             },
+            ansi::Mode::ReportMouseHighlight => {
+                self.mode.remove(TermMode::MOUSE_MODE);
+                self.mode.insert(TermMode::MOUSE_HIGHLIGHT);
+                self.event_proxy.send_event(Event::MouseCursorDirty);
+            },
             ansi::Mode::ReportCellMouseMotion => {
                 self.mode.remove(TermMode::MOUSE_MODE);
                 self.mode.insert(TermMode::MOUSE_DRAG);
@@ -1560,17 +2785,26 @@ impl<T: EventListener> Handler for Term<T> {
             ansi::Mode::BracketedPaste => self.mode.insert(TermMode::BRACKETED_PASTE),
             // Mouse encodings are mutually exclusive.
             ansi::Mode::SgrMouse => {
-                self.mode.remove(TermMode::UTF8_MOUSE);
+                self.mode.remove(TermMode::MOUSE_ENCODING);
                 self.mode.insert(TermMode::SGR_MOUSE);
             },
             ansi::Mode::Utf8Mouse => {
-                self.mode.remove(TermMode::SGR_MOUSE);
+                self.mode.remove(TermMode::MOUSE_ENCODING);
                 self.mode.insert(TermMode::UTF8_MOUSE);
             },
+            ansi::Mode::UrxvtMouse => {
+                self.mode.remove(TermMode::MOUSE_ENCODING);
+                self.mode.insert(TermMode::URXVT_MOUSE);
+            },
+            ansi::Mode::SgrPixelsMouse => {
+                self.mode.remove(TermMode::MOUSE_ENCODING);
+                self.mode.insert(TermMode::SGR_PIXELS_MOUSE);
+            },
             ansi::Mode::AlternateScroll => self.mode.insert(TermMode::ALTERNATE_SCROLL),
             ansi::Mode::LineWrap => self.mode.insert(TermMode::LINE_WRAP),
             ansi::Mode::LineFeedNewLine => self.mode.insert(TermMode::LINE_FEED_NEW_LINE),
             ansi::Mode::Origin => self.mode.insert(TermMode::ORIGIN),
+            ansi::Mode::LeftRightMargin => self.mode.insert(TermMode::LEFT_RIGHT_MARGIN),
             ansi::Mode::ColumnMode => self.deccolm(),
             ansi::Mode::Insert => self.mode.insert(TermMode::INSERT),
             ansi::Mode::BlinkingCursor => {
@@ -1578,6 +2812,9 @@ impl<T: EventListener> Handler for Term<T> {
                 style.blinking = true;
                 self.event_proxy.send_event(Event::CursorBlinkingChange(true));
             },
+            ansi::Mode::GraphemeClustering => self.mode.insert(TermMode::GRAPHEME_CLUSTERING),
+            // Synchronized updates are handled by the parser.
+            ansi::Mode::SyncUpdate => (),
         }
     }
 
@@ -1589,6 +2826,9 @@ impl<T: EventListener> Handler for Term<T> {
             ansi::Mode::SwapScreenAndSetRestoreCursor => {
                 if self.mode.contains(TermMode::ALT_SCREEN) {
                     self.swap_alt();
+
+                    // Applications leaving the alternate screen no longer control the mouse.
+                    self.reset_mouse_cursor_shape();
                 }
             },
             ansi::Mode::ShowCursor => self.mode.remove(TermMode::SHOW_CURSOR),
@@ -1597,6 +2837,11 @@ impl<T: EventListener> Handler for Term<T> {
                 self.mode.remove(TermMode::MOUSE_REPORT_CLICK);
                 self.event_proxy.send_event(Event::MouseCursorDirty);
             },
+            ansi::Mode::ReportMouseHighlight => {
+                self.mode.remove(TermMode::MOUSE_HIGHLIGHT);
+                self.mouse_highlight = None;
+                self.event_proxy.send_event(Event::MouseCursorDirty);
+            },
             ansi::Mode::ReportCellMouseMotion => {
                 self.mode.remove(TermMode::MOUSE_DRAG);
                 self.event_proxy.send_event(Event::MouseCursorDirty);
@@ -1609,10 +2854,16 @@ impl<T: EventListener> Handler for Term<T> {
             ansi::Mode::BracketedPaste => self.mode.remove(TermMode::BRACKETED_PASTE),
             ansi::Mode::SgrMouse => self.mode.remove(TermMode::SGR_MOUSE),
             ansi::Mode::Utf8Mouse => self.mode.remove(TermMode::UTF8_MOUSE),
+            ansi::Mode::UrxvtMouse => self.mode.remove(TermMode::URXVT_MOUSE),
+            ansi::Mode::SgrPixelsMouse => self.mode.remove(TermMode::SGR_PIXELS_MOUSE),
             ansi::Mode::AlternateScroll => self.mode.remove(TermMode::ALTERNATE_SCROLL),
             ansi::Mode::LineWrap => self.mode.remove(TermMode::LINE_WRAP),
             ansi::Mode::LineFeedNewLine => self.mode.remove(TermMode::LINE_FEED_NEW_LINE),
             ansi::Mode::Origin => self.mode.remove(TermMode::ORIGIN),
+            ansi::Mode::LeftRightMargin => {
+                self.mode.remove(TermMode::LEFT_RIGHT_MARGIN);
+                self.horizontal_margins = Column(0)..Column(self.columns());
+            },
             ansi::Mode::ColumnMode => self.deccolm(),
             ansi::Mode::Insert => self.mode.remove(TermMode::INSERT),
             ansi::Mode::BlinkingCursor => {
@@ -1620,6 +2871,8 @@ impl<T: EventListener> Handler for Term<T> {
                 style.blinking = false;
                 self.event_proxy.send_event(Event::CursorBlinkingChange(false));
             },
+            ansi::Mode::GraphemeClustering => self.mode.remove(TermMode::GRAPHEME_CLUSTERING),
+            ansi::Mode::SyncUpdate => (),
         }
     }
 
@@ -1648,6 +2901,30 @@ impl<T: EventListener> Handler for Term<T> {
         self.goto(Line(0), Column(0));
     }
 
+    #[inline]
+    fn set_left_right_margins(&mut self, left: usize, right: Option<usize>) {
+        // Without DECLRMM, this sequence saves the cursor position instead.
+        if !self.mode.contains(TermMode::LEFT_RIGHT_MARGIN) {
+            self.save_cursor_position();
+            return;
+        }
+
+        // Fallback to the last column as default.
+        let columns = self.columns();
+        let right = min(right.unwrap_or(columns), columns);
+
+        if left >= right {
+            debug!("Invalid left/right margins: ({};{})", left, right);
+            return;
+        }
+
+        trace!("Setting left/right margins: ({};{})", left, right);
+
+        // Like the scrolling region, the margins are stored as an open range.
+        self.horizontal_margins = Column(left - 1)..Column(right);
+        self.goto(Line(0), Column(0));
+    }
+
     #[inline]
     fn set_keypad_application_mode(&mut self) {
         trace!("Setting keypad application mode");
@@ -1672,6 +2949,12 @@ impl<T: EventListener> Handler for Term<T> {
         self.active_charset = index;
     }
 
+    #[inline]
+    fn set_single_shift_charset(&mut self, index: CharsetIndex) {
+        trace!("Setting single shift charset {:?}", index);
+        self.single_shift_charset = Some(index);
+    }
+
     #[inline]
     fn set_cursor_style(&mut self, style: Option<CursorStyle>) {
         trace!("Setting cursor style {:?}", style);
@@ -1731,6 +3014,11 @@ impl<T: EventListener> Handler for Term<T> {
 
     #[inline]
     fn text_area_size_pixels(&mut self) {
+        if !self.window_operations.allows(WindowOperationKind::ReportWindow) {
+            debug!("Ignoring text area size report, since it is not allowed");
+            return;
+        }
+
         let width = self.cell_width * self.columns();
         let height = self.cell_height * self.screen_lines();
         let text = format!("\x1b[4;{};{}t", height, width);
@@ -1739,22 +3027,241 @@ impl<T: EventListener> Handler for Term<T> {
 
     #[inline]
     fn text_area_size_chars(&mut self) {
+        if !self.window_operations.allows(WindowOperationKind::ReportWindow) {
+            debug!("Ignoring text area size report, since it is not allowed");
+            return;
+        }
+
         let text = format!("\x1b[8;{};{}t", self.screen_lines(), self.columns());
         self.event_proxy.send_event(Event::PtyWrite(text));
     }
-}
 
-/// Terminal version for escape sequence reports.
-///
-/// This returns the current terminal version as a unique number based on alacritty_terminal's
-/// semver version. The different versions are padded to ensure that a higher semver version will
-/// always report a higher version number.
-fn version_number(mut version: &str) -> usize {
-    if let Some(separator) = version.rfind('-') {
-        version = &version[..separator];
+    #[inline]
+    fn window_operation(&mut self, operation: WindowOperation) {
+        trace!("Window operation: {:?}", operation);
+
+        let kind = match operation {
+            WindowOperation::Deiconify | WindowOperation::Iconify => WindowOperationKind::Iconify,
+            WindowOperation::Move { .. } => WindowOperationKind::Move,
+            WindowOperation::ResizePixels { .. } | WindowOperation::ResizeCells { .. } => {
+                WindowOperationKind::Resize
+            },
+            WindowOperation::Maximize(_) => WindowOperationKind::Maximize,
+            WindowOperation::Fullscreen(_) | WindowOperation::ToggleFullscreen => {
+                WindowOperationKind::Fullscreen
+            },
+            WindowOperation::ReportIconTitle | WindowOperation::ReportTitle => {
+                WindowOperationKind::ReportTitle
+            },
+            WindowOperation::ReportState
+            | WindowOperation::ReportPosition
+            | WindowOperation::ReportCellSize
+            | WindowOperation::ReportScreenSize => WindowOperationKind::ReportWindow,
+        };
+
+        if !self.window_operations.allows(kind) {
+            debug!("Ignoring window operation {:?}, since it is not allowed", operation);
+            return;
+        }
+
+        match operation {
+            WindowOperation::ReportCellSize => {
+                let text = format!("\x1b[6;{};{}t", self.cell_height, self.cell_width);
+                self.event_proxy.send_event(Event::PtyWrite(text));
+            },
+            WindowOperation::ReportIconTitle | WindowOperation::ReportTitle => {
+                // Strip control characters, to prevent the title from injecting escapes.
+                let title: String = self
+                    .title
+                    .as_deref()
+                    .unwrap_or_default()
+                    .chars()
+                    .filter(|c| !c.is_control())
+                    .collect();
+
+                let kind = if operation == WindowOperation::ReportTitle { 'l' } else { 'L' };
+                let text = format!("\x1b]{}{}\x1b\\", kind, title);
+                self.event_proxy.send_event(Event::PtyWrite(text));
+            },
+            _ => self.event_proxy.send_event(Event::WindowOperation(operation)),
+        }
     }
 
-    let mut version_number = 0;
+    #[inline]
+    fn start_mouse_highlight(&mut self, highlight: Option<MouseHighlight>) {
+        trace!("Starting mouse highlight: {:?}", highlight);
+
+        // Highlight requests are only valid in response to a reported mouse press.
+        if self.mode.contains(TermMode::MOUSE_HIGHLIGHT) {
+            self.mouse_highlight = highlight;
+        }
+    }
+
+    #[inline]
+    fn set_hyperlink(&mut self, hyperlink: Option<ansi::Hyperlink>) {
+        trace!("Setting hyperlink: {:?}", hyperlink);
+        self.grid.cursor.template.set_hyperlink(hyperlink.map(|link| link.into()));
+    }
+
+    #[inline]
+    fn set_working_directory(&mut self, host: &str, path: PathBuf) {
+        trace!("Setting working directory to {:?} on host '{}'", path, host);
+
+        self.working_directory = if is_local_host(host) { Some(path) } else { None };
+
+        let event = Event::WorkingDirectory(self.working_directory.clone());
+        self.event_proxy.send_event(event);
+    }
+
+    #[inline]
+    fn desktop_notification(&mut self, title: Option<String>, body: String) {
+        trace!("Desktop notification: {:?} {:?}", title, body);
+
+        if body.is_empty() && title.as_ref().map_or(true, |title| title.is_empty()) {
+            return;
+        }
+
+        self.event_proxy.send_event(Event::Notification(title, body));
+    }
+
+    #[inline]
+    fn set_mouse_cursor_shape(&mut self, shape: Option<String>) {
+        trace!("Setting mouse cursor shape to {:?}", shape);
+
+        if shape != self.mouse_cursor_shape {
+            self.mouse_cursor_shape = shape.clone();
+            self.event_proxy.send_event(Event::MouseCursorShape(shape));
+        }
+    }
+
+    #[inline]
+    fn push_keyboard_mode(&mut self, mode: KeyboardModes) {
+        trace!("Pushing '{:?}' onto keyboard mode stack", mode);
+
+        // Associated text is not supported, so it's never reported as active.
+        let mode = mode - KeyboardModes::REPORT_ASSOCIATED_TEXT;
+
+        if self.keyboard_mode_stack.len() >= KEYBOARD_MODE_STACK_MAX_DEPTH {
+            let removed = self.keyboard_mode_stack.remove(0);
+            trace!(
+                "Removing '{:?}' from bottom of keyboard mode stack that exceeds its maximum depth",
+                removed
+            );
+        }
+
+        self.keyboard_mode_stack.push(mode);
+        self.update_keyboard_mode();
+    }
+
+    #[inline]
+    fn pop_keyboard_modes(&mut self, to_pop: u16) {
+        trace!("Attempting to pop {} keyboard modes from stack", to_pop);
+
+        let new_len = self.keyboard_mode_stack.len().saturating_sub(to_pop as usize);
+        self.keyboard_mode_stack.truncate(new_len);
+        self.update_keyboard_mode();
+    }
+
+    #[inline]
+    fn set_keyboard_mode(&mut self, mode: KeyboardModes, behavior: KeyboardModesApplyBehavior) {
+        trace!("Setting keyboard mode to {:?} with behavior {:?}", mode, behavior);
+
+        let mode = mode - KeyboardModes::REPORT_ASSOCIATED_TEXT;
+
+        // Modify the mode at the top of the stack, creating one if necessary.
+        if self.keyboard_mode_stack.is_empty() {
+            self.keyboard_mode_stack.push(KeyboardModes::NO_MODE);
+        }
+        let active_mode = self.keyboard_mode_stack.last_mut().unwrap();
+
+        match behavior {
+            KeyboardModesApplyBehavior::Replace => *active_mode = mode,
+            KeyboardModesApplyBehavior::Union => active_mode.insert(mode),
+            KeyboardModesApplyBehavior::Difference => active_mode.remove(mode),
+        }
+
+        self.update_keyboard_mode();
+    }
+
+    #[inline]
+    fn report_keyboard_mode(&mut self) {
+        let mode = self.keyboard_mode_stack.last().map_or(0, |mode| mode.bits());
+        trace!("Reporting keyboard mode {}", mode);
+
+        let text = format!("\x1b[?{}u", mode);
+        self.event_proxy.send_event(Event::PtyWrite(text));
+    }
+
+    #[inline]
+    fn prompt_mark(&mut self, mark: PromptMark) {
+        trace!("Setting prompt mark: {:?}", mark);
+
+        let mark = match mark {
+            PromptMark::PromptStart => RowMarks::PROMPT,
+            PromptMark::CommandStart => RowMarks::COMMAND,
+            PromptMark::OutputStart => RowMarks::OUTPUT,
+            PromptMark::CommandEnd => RowMarks::COMMAND_END,
+        };
+
+        let line = self.grid.cursor.point.line;
+        self.grid[line].marks.insert(mark);
+    }
+}
+
+/// Check if the host of an OSC 7 URI refers to the local machine.
+fn is_local_host(host: &str) -> bool {
+    if host.is_empty() || host == "localhost" {
+        return true;
+    }
+
+    #[cfg(unix)]
+    {
+        let mut buffer = [0; 256];
+        let hostname = nix::unistd::gethostname(&mut buffer).ok().and_then(|h| h.to_str().ok());
+        hostname == Some(host)
+    }
+
+    #[cfg(windows)]
+    std::env::var("COMPUTERNAME").map_or(false, |hostname| hostname.eq_ignore_ascii_case(host))
+}
+
+/// SGR parameters for a color, identified by its extended color parameter.
+///
+/// The default foreground and background colors do not produce any parameters.
+fn sgr_color(color: Color, extended: u8) -> Option<String> {
+    match color {
+        // Underline colors have no dedicated parameters for the basic colors.
+        Color::Named(color) if extended == 58 && (color as usize) < 16 => {
+            Some(format!("58;5;{}", color as usize))
+        },
+        Color::Named(color) if (color as usize) < 8 => {
+            Some((extended - 8 + color as u8).to_string())
+        },
+        Color::Named(color) if (color as usize) < 16 => {
+            Some((extended + 52 + color as u8 - 8).to_string())
+        },
+        Color::Named(_) => None,
+        Color::Indexed(index) => Some(format!("{};5;{}", extended, index)),
+        Color::Spec(rgb) => Some(format!("{};2;{};{};{}", extended, rgb.r, rgb.g, rgb.b)),
+    }
+}
+
+/// Hex encode a string for XTGETTCAP replies.
+fn hex_encode(text: &str) -> String {
+    text.bytes().map(|byte| format!("{:02X}", byte)).collect()
+}
+
+/// Terminal version for escape sequence reports.
+///
+/// This returns the current terminal version as a unique number based on alacritty_terminal's
+/// semver version. The different versions are padded to ensure that a higher semver version will
+/// always report a higher version number.
+fn version_number(mut version: &str) -> usize {
+    if let Some(separator) = version.rfind('-') {
+        version = &version[..separator];
+    }
+
+    let mut version_number = 0;
 
     let semver_versions = version.split('.');
     for (i, semver_version) in semver_versions.rev().enumerate() {
@@ -1765,6 +3272,22 @@ fn version_number(mut version: &str) -> usize {
     version_number
 }
 
+/// Number of cells occupied by a grapheme cluster.
+fn grapheme_width(cluster: &str, ambiguous_width: AmbiguousWidth) -> usize {
+    let mut chars = cluster.chars();
+    let first = chars.next().unwrap_or_default();
+    let is_regional_indicator = |c: char| ('\u{1f1e6}'..='\u{1f1ff}').contains(&c);
+
+    // Emoji presentation and flags always occupy two cells.
+    if cluster.contains('\u{fe0f}')
+        || (is_regional_indicator(first) && chars.any(is_regional_indicator))
+    {
+        2
+    } else {
+        max(ambiguous_width.char_width(first).unwrap_or(1), 1)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ClipboardType {
     Clipboard,
@@ -1937,10 +3460,12 @@ pub mod test {
 mod tests {
     use super::*;
 
+    use std::cell::RefCell;
     use std::mem;
 
     use crate::ansi::{self, CharsetIndex, Handler, StandardCharset};
-    use crate::config::MockConfig;
+    use crate::config::{AmbiguousWidth, MockConfig};
+    use crate::graphics::{kitty, GraphicId};
     use crate::grid::{Grid, Scroll};
     use crate::index::{Column, Point, Side};
     use crate::selection::{Selection, SelectionType};
@@ -2071,6 +3596,30 @@ mod tests {
         assert_eq!(term.selection_to_string(), Some(String::from("\"aa\"a\n")));
     }
 
+    #[test]
+    fn ambiguous_width_selection() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut config = MockConfig::default();
+        config.ambiguous_width = AmbiguousWidth::Wide;
+        let mut term = Term::new(&config, size, ());
+
+        for c in "○x".chars() {
+            term.input(c);
+        }
+
+        // Ambiguous characters are stored like other fullwidth characters.
+        assert!(term.grid[Line(0)][Column(0)].flags.contains(Flags::WIDE_CHAR));
+        assert!(term.grid[Line(0)][Column(1)].flags.contains(Flags::WIDE_CHAR_SPACER));
+        assert_eq!(term.grid[Line(0)][Column(2)].c, 'x');
+
+        term.selection = Some(Selection::new(
+            SelectionType::Lines,
+            Point { line: Line(0), column: Column(0) },
+            Side::Left,
+        ));
+        assert_eq!(term.selection_to_string(), Some(String::from("○x\n")));
+    }
+
     #[test]
     fn selecting_empty_line() {
         let size = SizeInfo::new(3.0, 3.0, 1.0, 1.0, 0.0, 0.0, false);
@@ -2109,6 +3658,47 @@ mod tests {
         assert_eq!(deserialized, grid);
     }
 
+    #[test]
+    fn input_grapheme_clusters() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, ());
+
+        // Without grapheme clustering, every character with a width gets its own cell.
+        for c in "🇺🇸".chars() {
+            term.input(c);
+        }
+        assert_eq!(term.grid.cursor.point.column, Column(2));
+
+        term.goto(Line(1), Column(0));
+        term.set_mode(ansi::Mode::GraphemeClustering);
+
+        // Emoji ZWJ sequences and flags are stored in a single wide cell.
+        for c in "👨\u{200d}👩🇺🇸e\u{301}".chars() {
+            term.input(c);
+        }
+
+        let row = &term.grid[Line(1)];
+        assert_eq!(row[Column(0)].c, '👨');
+        assert_eq!(row[Column(0)].zerowidth(), Some(&['\u{200d}', '👩'][..]));
+        assert_eq!(row[Column(2)].c, '🇺');
+        assert_eq!(row[Column(2)].zerowidth(), Some(&['🇸'][..]));
+        assert!(row[Column(2)].flags.contains(Flags::WIDE_CHAR));
+        assert!(row[Column(3)].flags.contains(Flags::WIDE_CHAR_SPACER));
+        assert_eq!(row[Column(4)].c, 'e');
+        assert_eq!(row[Column(4)].zerowidth(), Some(&['\u{301}'][..]));
+        assert_eq!(term.grid.cursor.point.column, Column(5));
+
+        // Emoji presentation selectors widen the cluster.
+        term.goto(Line(2), Column(0));
+        for c in "\u{2764}\u{fe0f}a".chars() {
+            term.input(c);
+        }
+
+        let row = &term.grid[Line(2)];
+        assert!(row[Column(0)].flags.contains(Flags::WIDE_CHAR));
+        assert_eq!(row[Column(2)].c, 'a');
+    }
+
     #[test]
     fn input_line_drawing_character() {
         let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
@@ -2120,6 +3710,29 @@ mod tests {
         assert_eq!(term.grid()[cursor].c, '▒');
     }
 
+    #[test]
+    fn input_single_shift_character() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, ());
+        term.configure_charset(CharsetIndex::G2, StandardCharset::DecTechnical);
+        term.configure_charset(CharsetIndex::G3, StandardCharset::DecSupplemental);
+
+        // Single shifts only apply to the next character.
+        term.set_single_shift_charset(CharsetIndex::G2);
+        term.input('a');
+        term.input('a');
+        term.set_single_shift_charset(CharsetIndex::G3);
+        term.input('W');
+
+        // Locking shifts apply until the next shift.
+        term.set_active_charset(CharsetIndex::G3);
+        term.input('a');
+
+        let row = &term.grid()[Line(0)];
+        let text: String = (0..4).map(|column| row[Column(column)].c).collect();
+        assert_eq!(text, "αaŒá");
+    }
+
     #[test]
     fn clear_saved_lines() {
         let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
@@ -2234,6 +3847,542 @@ mod tests {
         assert_eq!(term.grid.cursor.point, Point::new(Line(4), Column(0)));
     }
 
+    #[test]
+    fn prompt_marks() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, ());
+
+        // Prompt with a command producing two lines of output.
+        term.prompt_mark(ansi::PromptMark::PromptStart);
+        term.input('$');
+        term.prompt_mark(ansi::PromptMark::CommandStart);
+        term.input('a');
+        term.newline();
+        term.prompt_mark(ansi::PromptMark::OutputStart);
+        term.input('b');
+        term.newline();
+        term.input('c');
+        term.newline();
+        term.prompt_mark(ansi::PromptMark::CommandEnd);
+        term.prompt_mark(ansi::PromptMark::PromptStart);
+        term.input('$');
+
+        let output = term.last_command_output().unwrap();
+        assert_eq!(*output.start(), Point::new(Line(1), Column(0)));
+        assert_eq!(*output.end(), Point::new(Line(2), Column(6)));
+
+        assert_eq!(term.prompt_line(Line(3), Direction::Left), Some(Line(0)));
+        assert_eq!(term.prompt_line(Line(0), Direction::Right), Some(Line(3)));
+        assert_eq!(term.prompt_line(Line(3), Direction::Right), None);
+    }
+
+    #[test]
+    fn left_right_margins() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, ());
+
+        // Without DECLRMM, DECSLRM saves the cursor position.
+        term.goto(Line(2), Column(3));
+        term.set_left_right_margins(2, Some(4));
+        assert!(!term.has_horizontal_margins());
+        assert_eq!(term.grid.saved_cursor.point, Point::new(Line(2), Column(3)));
+
+        term.set_mode(ansi::Mode::LeftRightMargin);
+        term.set_left_right_margins(2, Some(4));
+        assert_eq!(term.horizontal_margins, Column(1)..Column(4));
+        assert_eq!(term.grid.cursor.point, Point::new(Line(0), Column(0)));
+
+        // Invalid margins are ignored.
+        term.set_left_right_margins(4, Some(4));
+        assert_eq!(term.horizontal_margins, Column(1)..Column(4));
+
+        // Origin mode is relative to the margins.
+        term.set_mode(ansi::Mode::Origin);
+        term.goto(Line(1), Column(5));
+        assert_eq!(term.grid.cursor.point, Point::new(Line(1), Column(3)));
+        term.move_down(1);
+        assert_eq!(term.grid.cursor.point, Point::new(Line(2), Column(3)));
+
+        // Disabling DECLRMM resets the margins.
+        term.unset_mode(ansi::Mode::LeftRightMargin);
+        assert!(!term.has_horizontal_margins());
+    }
+
+    #[test]
+    fn mode_state() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, ());
+
+        assert_eq!(term.mode_state(&ansi::Mode::BracketedPaste), ModeState::Reset);
+        term.set_mode(ansi::Mode::BracketedPaste);
+        assert_eq!(term.mode_state(&ansi::Mode::BracketedPaste), ModeState::Set);
+
+        assert_eq!(term.mode_state(&ansi::Mode::LineWrap), ModeState::Set);
+        term.unset_mode(ansi::Mode::LineWrap);
+        assert_eq!(term.mode_state(&ansi::Mode::LineWrap), ModeState::Reset);
+
+        term.set_mode(ansi::Mode::Insert);
+        assert_eq!(term.mode_state(&ansi::Mode::Insert), ModeState::Set);
+
+        assert_eq!(term.mode_state(&ansi::Mode::ColumnMode), ModeState::PermanentlyReset);
+    }
+
+    /// Event listener collecting everything written to the PTY.
+    #[derive(Default)]
+    struct PtyWriteListener(RefCell<String>);
+
+    impl EventListener for PtyWriteListener {
+        fn send_event(&self, event: Event) {
+            if let Event::PtyWrite(text) = event {
+                self.0.borrow_mut().push_str(&text);
+            }
+        }
+    }
+
+    #[test]
+    fn report_mode() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, PtyWriteListener::default());
+        let mut parser = ansi::Processor::new();
+
+        for byte in b"\x1b[?25$p\x1b[4$p\x1b[?9999$p" {
+            parser.advance(&mut term, *byte);
+        }
+
+        assert_eq!(&*term.event_proxy.0.borrow(), "\x1b[?25;1$y\x1b[4;2$y\x1b[?9999;0$y");
+    }
+
+    #[test]
+    fn report_sync_update_mode() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, PtyWriteListener::default());
+        let mut parser = ansi::Processor::new();
+
+        for byte in b"\x1b[?2026$p\x1b[?2026h\x1b[?2026$p" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(&*term.event_proxy.0.borrow(), "\x1b[?2026;2$y\x1b[?2026;1$y");
+
+        term.event_proxy.0.borrow_mut().clear();
+        for byte in b"\x1b[?2026l\x1b[?2026$p" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(&*term.event_proxy.0.borrow(), "\x1b[?2026;2$y");
+    }
+
+    #[test]
+    fn graphic_rendition() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, ());
+
+        assert_eq!(term.graphic_rendition(), "0");
+
+        term.terminal_attribute(Attr::Bold);
+        term.terminal_attribute(Attr::Undercurl);
+        term.terminal_attribute(Attr::Foreground(Color::Named(NamedColor::BrightRed)));
+        term.terminal_attribute(Attr::Background(Color::Indexed(100)));
+        term.terminal_attribute(Attr::UnderlineColor(Some(Color::Spec(Rgb { r: 1, g: 2, b: 3 }))));
+        assert_eq!(term.graphic_rendition(), "0;1;4:3;91;48;5;100;58;2;1;2;3");
+    }
+
+    #[test]
+    fn ecma48_text_attrs() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, ());
+
+        term.terminal_attribute(Attr::Overline);
+        term.terminal_attribute(Attr::Superscript);
+        term.input('a');
+        term.terminal_attribute(Attr::Subscript);
+        term.input('b');
+        term.terminal_attribute(Attr::CancelOverline);
+        term.terminal_attribute(Attr::CancelSuperscriptSubscript);
+        term.input('c');
+
+        let flags = |column| term.grid[Line(0)][Column(column)].flags;
+        assert_eq!(flags(0), Flags::OVERLINE | Flags::SUPERSCRIPT);
+        assert_eq!(flags(1), Flags::OVERLINE | Flags::SUBSCRIPT);
+        assert_eq!(flags(2), Flags::empty());
+    }
+
+    #[test]
+    fn mouse_protocols() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, ());
+
+        // Mouse encodings are mutually exclusive.
+        term.set_mode(ansi::Mode::SgrMouse);
+        term.set_mode(ansi::Mode::SgrPixelsMouse);
+        assert_eq!(*term.mode() & TermMode::MOUSE_ENCODING, TermMode::SGR_PIXELS_MOUSE);
+        term.set_mode(ansi::Mode::UrxvtMouse);
+        assert_eq!(*term.mode() & TermMode::MOUSE_ENCODING, TermMode::URXVT_MOUSE);
+
+        // Highlight requests are ignored without highlight mouse tracking.
+        let highlight = MouseHighlight { line: 2, column: 3, first_line: 1, last_line: 5 };
+        term.start_mouse_highlight(Some(highlight));
+        assert_eq!(term.mouse_highlight, None);
+
+        term.set_mode(ansi::Mode::ReportMouseHighlight);
+        assert_eq!(*term.mode() & TermMode::MOUSE_MODE, TermMode::MOUSE_HIGHLIGHT);
+        term.start_mouse_highlight(Some(highlight));
+        assert_eq!(term.mouse_highlight, Some(highlight));
+
+        term.unset_mode(ansi::Mode::ReportMouseHighlight);
+        assert_eq!(term.mouse_highlight, None);
+    }
+
+    #[test]
+    fn double_width_lines() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, ());
+
+        for c in "abcdefg".chars() {
+            term.input(c);
+        }
+
+        // Characters in the right half of the line are cleared.
+        term.goto(Line(0), Column(5));
+        term.set_line_size(LineSize::DoubleWidth);
+        assert_eq!(term.grid[Line(0)].line_size(), LineSize::DoubleWidth);
+        assert_eq!(term.grid.cursor.point.column, Column(2));
+        assert_eq!(term.grid[Line(0)][Column(2)].c, 'c');
+        assert_eq!(term.grid[Line(0)][Column(3)].c, ' ');
+
+        // Cursor movement stops in the middle of the line.
+        term.move_forward(Column(10));
+        assert_eq!(term.grid.cursor.point.column, Column(2));
+        term.goto(Line(0), Column(6));
+        assert_eq!(term.grid.cursor.point.column, Column(2));
+
+        // Input wraps in the middle of the line.
+        term.input('x');
+        term.input('y');
+        assert_eq!(term.grid[Line(0)][Column(2)].c, 'x');
+        assert_eq!(term.grid[Line(1)][Column(0)].c, 'y');
+
+        // Clearing the line restores single-width characters.
+        term.goto(Line(0), Column(0));
+        term.clear_screen(ansi::ClearMode::All);
+        assert_eq!(term.grid[Line(0)].line_size(), LineSize::Normal);
+    }
+
+    #[test]
+    fn parse_line_size() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, ());
+        let mut parser = ansi::Processor::new();
+
+        for byte in b"\x1b#6\r\n\x1b#3\r\n\x1b#4\r\n\x1b#6\x1b#5" {
+            parser.advance(&mut term, *byte);
+        }
+
+        assert_eq!(term.grid[Line(0)].line_size(), LineSize::DoubleWidth);
+        assert_eq!(term.grid[Line(1)].line_size(), LineSize::DoubleHeightTop);
+        assert_eq!(term.grid[Line(2)].line_size(), LineSize::DoubleHeightBottom);
+        assert_eq!(term.grid[Line(3)].line_size(), LineSize::Normal);
+    }
+
+    #[test]
+    fn double_width_tab_stops() {
+        let size = SizeInfo::new(51.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, ());
+
+        // Tab stops every 8 screen columns are every 4 cells on double-width lines.
+        term.set_line_size(LineSize::DoubleWidth);
+        term.put_tab(1);
+        assert_eq!(term.grid.cursor.point.column, Column(4));
+
+        // Tabs stop at the end of the double-width line.
+        term.put_tab(2);
+        assert_eq!(term.grid.cursor.point.column, Column(7));
+
+        term.move_backward_tabs(1);
+        assert_eq!(term.grid.cursor.point.column, Column(4));
+    }
+
+    #[test]
+    fn rectangle_checksum() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, ());
+
+        term.input('A');
+        term.input('B');
+        term.terminal_attribute(Attr::Bold);
+        term.input('C');
+
+        assert_eq!(term.rectangle_checksum(Rectangle::new(1, 1, Some(1), Some(1))), 0xffbf);
+        assert_eq!(term.rectangle_checksum(Rectangle::new(1, 1, Some(1), Some(3))), 0xfeba);
+
+        // Empty cells are counted as spaces.
+        assert_eq!(term.rectangle_checksum(Rectangle::new(2, 1, Some(2), Some(2))), 0xffc0);
+
+        // Areas outside of the screen are empty.
+        assert_eq!(term.rectangle_checksum(Rectangle::new(18, 1, None, None)), 0);
+
+        // Origin mode is relative to the scrolling region.
+        term.set_scrolling_region(2, None);
+        term.set_mode(ansi::Mode::Origin);
+        assert_eq!(term.rectangle_checksum(Rectangle::new(1, 1, Some(1), Some(1))), 0xffe0);
+    }
+
+    #[test]
+    fn rectangle_area_wide_chars() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, ());
+
+        for c in "文A字".chars() {
+            term.input(c);
+        }
+        term.goto(Line(1), Column(0));
+        for c in "a文".chars() {
+            term.input(c);
+        }
+
+        // Wide characters split by the filled area are cleared.
+        term.fill_area('x', Rectangle::new(1, 2, Some(1), Some(4)));
+        assert_eq!(term.grid[Line(0)][Column(0)].c, ' ');
+        assert!(!term.grid[Line(0)][Column(0)].flags.contains(Flags::WIDE_CHAR));
+        assert_eq!(term.grid[Line(0)][Column(3)].c, 'x');
+        assert!(!term.grid[Line(0)][Column(4)].flags.contains(Flags::WIDE_CHAR_SPACER));
+
+        // Halves of wide characters at the edges of the source are not copied.
+        term.copy_area(Rectangle::new(2, 1, Some(2), Some(2)), 3, 1);
+        assert_eq!(term.grid[Line(2)][Column(0)].c, 'a');
+        assert_eq!(term.grid[Line(2)][Column(1)].c, ' ');
+        assert!(!term.grid[Line(2)][Column(1)].flags.contains(Flags::WIDE_CHAR));
+
+        term.copy_area(Rectangle::new(2, 3, Some(2), Some(3)), 4, 1);
+        assert!(!term.grid[Line(3)][Column(0)].flags.contains(Flags::WIDE_CHAR_SPACER));
+
+        // Wide characters split by the erased area are cleared.
+        term.erase_area(Rectangle::new(2, 3, Some(2), Some(3)));
+        assert_eq!(term.grid[Line(1)][Column(1)].c, ' ');
+        assert!(!term.grid[Line(1)][Column(1)].flags.contains(Flags::WIDE_CHAR));
+    }
+
+    #[test]
+    fn full_reset() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, ());
+
+        term.set_title(Some(String::from("title")));
+        term.set_color(1, Rgb { r: 1, g: 2, b: 3 });
+
+        term.reset_state();
+
+        assert_eq!(term.title, None);
+        assert_eq!(term.colors[1], None);
+    }
+
+    #[test]
+    fn soft_reset() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, ());
+
+        term.set_mode(ansi::Mode::Origin);
+        term.set_mode(ansi::Mode::Insert);
+        term.unset_mode(ansi::Mode::ShowCursor);
+        term.unset_mode(ansi::Mode::LineWrap);
+        term.set_scrolling_region(2, Some(4));
+        term.terminal_attribute(Attr::Bold);
+        term.configure_charset(CharsetIndex::G0, StandardCharset::SpecialCharacterAndLineDrawing);
+        term.input('a');
+        term.save_cursor_position();
+
+        term.soft_reset();
+
+        assert!(term.mode.contains(TermMode::SHOW_CURSOR | TermMode::LINE_WRAP));
+        assert!(!term.mode.intersects(TermMode::ORIGIN | TermMode::INSERT));
+        assert_eq!(term.scroll_region, Line(0)..Line(17));
+        assert_eq!(term.grid.cursor.template, Cell::default());
+        assert_eq!(term.grid.saved_cursor.point, Point::new(Line(0), Column(0)));
+
+        // Screen content and cursor position are kept.
+        assert_eq!(term.grid.cursor.point, Point::new(Line(1), Column(1)));
+        assert_eq!(term.grid[Line(1)][Column(0)].c, '▒');
+        assert!(term.grid[Line(1)][Column(0)].flags.contains(Flags::BOLD));
+
+        term.input('a');
+        assert_eq!(term.grid[Line(1)][Column(1)].c, 'a');
+        assert!(!term.grid[Line(1)][Column(1)].flags.contains(Flags::BOLD));
+    }
+
+    #[test]
+    fn mouse_cursor_shape() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, ());
+
+        term.set_mouse_cursor_shape(Some(String::from("pointer")));
+        assert_eq!(term.mouse_cursor_shape, Some(String::from("pointer")));
+        term.set_mouse_cursor_shape(None);
+        assert_eq!(term.mouse_cursor_shape, None);
+
+        // Leaving the alternate screen restores the default shape.
+        term.set_mode(ansi::Mode::SwapScreenAndSetRestoreCursor);
+        term.set_mouse_cursor_shape(Some(String::from("text")));
+        term.unset_mode(ansi::Mode::SwapScreenAndSetRestoreCursor);
+        assert_eq!(term.mouse_cursor_shape, None);
+
+        term.set_mouse_cursor_shape(Some(String::from("wait")));
+        term.reset_state();
+        assert_eq!(term.mouse_cursor_shape, None);
+    }
+
+    #[test]
+    fn blinking_text() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, ());
+
+        term.terminal_attribute(Attr::BlinkFast);
+        term.input('a');
+        term.terminal_attribute(Attr::CancelBlink);
+        term.input('b');
+
+        assert!(term.grid[Line(0)][Column(0)].flags.contains(Flags::BLINK));
+        assert!(!term.grid[Line(0)][Column(1)].flags.contains(Flags::BLINK));
+
+        term.terminal_attribute(Attr::BlinkSlow);
+        assert_eq!(term.graphic_rendition(), "0;5");
+    }
+
+    #[test]
+    fn insert_graphic() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, ());
+
+        term.goto(Line(15), Column(2));
+        let pixels = vec![255; 7 * 5 * 4];
+        term.insert_graphic(GraphicData { id: GraphicId(0), width: 7, height: 5, pixels });
+
+        // Graphic covers three columns and two lines, scrolling once to fit the cursor below it.
+        assert_eq!(term.grid.cursor.point, Point::new(Line(16), Column(2)));
+        assert_eq!(term.grid.history_size(), 1);
+
+        let graphic = term.grid[Line(14)][Column(2)].graphic().unwrap();
+        assert_eq!((graphic.offset_x, graphic.offset_y), (0, 0));
+        let graphic = term.grid[Line(15)][Column(4)].graphic().unwrap();
+        assert_eq!((graphic.offset_x, graphic.offset_y), (6, 3));
+        assert!(term.grid[Line(15)][Column(5)].graphic().is_none());
+        assert!(term.grid[Line(16)][Column(2)].graphic().is_none());
+
+        let queues = term.graphics_take_queues().unwrap();
+        assert_eq!(queues.pending.len(), 1);
+        let id = queues.pending[0].id;
+
+        // Graphic is released once all its cells have been cleared.
+        term.clear_screen(ansi::ClearMode::All);
+        assert!(term.graphics_take_queues().is_none());
+        term.clear_screen(ansi::ClearMode::Saved);
+        assert_eq!(term.graphics_take_queues().unwrap().remove_queue, vec![id]);
+    }
+
+    #[test]
+    fn kitty_graphics() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, ());
+
+        let command = |control: &str, payload: &[u8]| {
+            let mut data = control.as_bytes().to_vec();
+            data.push(b';');
+            data.extend_from_slice(base64::encode(payload).as_bytes());
+            kitty::Command::parse(&data).unwrap()
+        };
+
+        // Image of 4x4 pixels, transmitted in two chunks.
+        term.goto(Line(1), Column(1));
+        term.kitty_graphics(command("a=T,s=4,v=4,i=7,m=1", &[255; 32]));
+        assert!(term.grid[Line(1)][Column(1)].graphic().is_none());
+        term.kitty_graphics(command("m=0", &[255; 32]));
+
+        // Image covers two columns and two lines, with the cursor after its last line.
+        assert_eq!(term.grid.cursor.point, Point::new(Line(2), Column(3)));
+        let graphic = term.grid[Line(2)][Column(2)].graphic().unwrap();
+        assert_eq!((graphic.offset_x, graphic.offset_y, graphic.width), (3, 3, 3));
+        assert!(term.grid[Line(1)][Column(3)].graphic().is_none());
+
+        // Second placement of the image, scaled to one cell without moving the cursor.
+        term.kitty_graphics(command("a=p,i=7,p=2,c=1,r=1,C=1", &[]));
+        let graphic = term.grid[Line(2)][Column(3)].graphic().unwrap();
+        assert_eq!((graphic.placement_id, graphic.width, graphic.height), (2, 4, 4));
+        assert_eq!(term.grid.cursor.point, Point::new(Line(2), Column(3)));
+
+        // Deleting the placement at the cursor keeps the other placement and the image.
+        term.kitty_graphics(command("a=d,d=C", &[]));
+        assert!(term.grid[Line(2)][Column(3)].graphic().is_none());
+        assert!(term.grid[Line(1)][Column(1)].graphic().is_some());
+        assert!(term.kitty_images.get(7).is_some());
+
+        // Deleting all placements and freeing their images releases the texture.
+        term.kitty_graphics(command("a=d,d=A", &[]));
+        assert!(term.grid[Line(1)][Column(1)].graphic().is_none());
+        assert!(term.kitty_images.get(7).is_none());
+
+        let queues = term.graphics_take_queues();
+        assert!(queues.map_or(true, |queues| queues.pending.is_empty()));
+    }
+
+    #[test]
+    fn keyboard_mode_stack() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, ());
+
+        // Modes can be pushed and are reflected in the terminal mode.
+        term.push_keyboard_mode(KeyboardModes::DISAMBIGUATE_ESC_CODES);
+        term.push_keyboard_mode(KeyboardModes::REPORT_EVENT_TYPES);
+        assert!(term.mode().contains(TermMode::REPORT_EVENT_TYPES));
+        assert!(!term.mode().contains(TermMode::DISAMBIGUATE_ESC_CODES));
+
+        // Active mode can be modified.
+        term.set_keyboard_mode(
+            KeyboardModes::DISAMBIGUATE_ESC_CODES,
+            KeyboardModesApplyBehavior::Union,
+        );
+        assert!(term.mode().contains(TermMode::DISAMBIGUATE_ESC_CODES));
+        assert!(term.mode().contains(TermMode::REPORT_EVENT_TYPES));
+
+        // Alternate screen has its own stack.
+        term.set_mode(ansi::Mode::SwapScreenAndSetRestoreCursor);
+        assert!(!term.mode().intersects(TermMode::KITTY_KEYBOARD_PROTOCOL));
+        term.push_keyboard_mode(KeyboardModes::REPORT_ALL_KEYS_AS_ESC);
+        term.unset_mode(ansi::Mode::SwapScreenAndSetRestoreCursor);
+        assert_eq!(term.keyboard_mode_stack.len(), 2);
+        assert!(!term.mode().contains(TermMode::REPORT_ALL_KEYS_AS_ESC));
+
+        // Popping more modes than available empties the stack.
+        term.pop_keyboard_modes(1);
+        assert!(term.mode().contains(TermMode::DISAMBIGUATE_ESC_CODES));
+        term.pop_keyboard_modes(5);
+        assert!(!term.mode().intersects(TermMode::KITTY_KEYBOARD_PROTOCOL));
+
+        // Stack doesn't grow infinitely.
+        for _ in 0..4097 {
+            term.push_keyboard_mode(KeyboardModes::DISAMBIGUATE_ESC_CODES);
+        }
+        assert_eq!(term.keyboard_mode_stack.len(), 4096);
+
+        // Stack is cleared when terminal state is reset.
+        term.reset_state();
+        assert!(term.keyboard_mode_stack.is_empty());
+        assert!(!term.mode().intersects(TermMode::KITTY_KEYBOARD_PROTOCOL));
+    }
+
+    #[test]
+    fn report_keyboard_mode() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, PtyWriteListener::default());
+
+        // Associated text is not supported and must not be reported as active.
+        term.push_keyboard_mode(KeyboardModes::all());
+        term.report_keyboard_mode();
+        term.set_keyboard_mode(
+            KeyboardModes::REPORT_ASSOCIATED_TEXT,
+            KeyboardModesApplyBehavior::Replace,
+        );
+        term.report_keyboard_mode();
+
+        assert_eq!(&*term.event_proxy.0.borrow(), "\x1b[?15u\x1b[?0u");
+    }
+
     #[test]
     fn window_title() {
         let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
@@ -0,0 +1,296 @@
+//! Terminfo capabilities reported through XTGETTCAP.
+
+/// Capabilities of the `alacritty` terminfo entry, sorted by name.
+///
+/// This must be kept in sync with `extra/alacritty.info`. Boolean capabilities have no value.
+static CAPABILITIES: &[(&str, Option<&str>)] = &[
+    ("AX", None),
+    ("Cr", Some("\x1b]112\x07")),
+    ("Cs", Some("\x1b]12;%p1%s\x07")),
+    ("E3", Some("\x1b[3J")),
+    ("Ms", Some("\x1b]52;%p1%s;%p2%s\x07")),
+    ("OTbs", None),
+    ("Se", Some("\x1b[0 q")),
+    ("Smulx", Some("\x1b[4:%p1%dm")),
+    ("Ss", Some("\x1b[%p1%d q")),
+    ("Sync", Some("\x1bP=%p1%ds\x1b\\")),
+    ("XT", None),
+    ("acsc", Some("``aaffggiijjkkllmmnnooppqqrrssttuuvvwwxxyyzz{{||}}~~")),
+    ("am", None),
+    ("bce", None),
+    ("bel", Some("\x07")),
+    ("bold", Some("\x1b[1m")),
+    ("cbt", Some("\x1b[Z")),
+    ("ccc", None),
+    ("civis", Some("\x1b[?25l")),
+    ("clear", Some("\x1b[H\x1b[2J")),
+    ("cnorm", Some("\x1b[?12l\x1b[?25h")),
+    ("colors", Some("256")),
+    ("cols", Some("80")),
+    ("cr", Some("\x0d")),
+    ("csr", Some("\x1b[%i%p1%d;%p2%dr")),
+    ("cub", Some("\x1b[%p1%dD")),
+    ("cub1", Some("\x08")),
+    ("cud", Some("\x1b[%p1%dB")),
+    ("cud1", Some("\x0a")),
+    ("cuf", Some("\x1b[%p1%dC")),
+    ("cuf1", Some("\x1b[C")),
+    ("cup", Some("\x1b[%i%p1%d;%p2%dH")),
+    ("cuu", Some("\x1b[%p1%dA")),
+    ("cuu1", Some("\x1b[A")),
+    ("cvvis", Some("\x1b[?12;25h")),
+    ("dch", Some("\x1b[%p1%dP")),
+    ("dch1", Some("\x1b[P")),
+    ("dim", Some("\x1b[2m")),
+    ("dl", Some("\x1b[%p1%dM")),
+    ("dl1", Some("\x1b[M")),
+    ("dsl", Some("\x1b]2;\x07")),
+    ("ech", Some("\x1b[%p1%dX")),
+    ("ed", Some("\x1b[J")),
+    ("el", Some("\x1b[K")),
+    ("el1", Some("\x1b[1K")),
+    ("flash", Some("\x1b[?5h$<100/>\x1b[?5l")),
+    ("fsl", Some("\x07")),
+    ("home", Some("\x1b[H")),
+    ("hpa", Some("\x1b[%i%p1%dG")),
+    ("hs", None),
+    ("ht", Some("\x09")),
+    ("hts", Some("\x1bH")),
+    ("ich", Some("\x1b[%p1%d@")),
+    ("il", Some("\x1b[%p1%dL")),
+    ("il1", Some("\x1b[L")),
+    ("ind", Some("\x0a")),
+    ("indn", Some("\x1b[%p1%dS")),
+    ("initc", Some("\x1b]4;%p1%d;rgb:%p2%{255}%*%{1000}%/%2.2X/%p3%{255}%*%{1000}%/%2.2X/%p4%{255}%*%{1000}%/%2.2X\x1b\\")),
+    ("invis", Some("\x1b[8m")),
+    ("is2", Some("\x1b[!p\x1b[?3;4l\x1b[4l\x1b>")),
+    ("it", Some("8")),
+    ("kDC", Some("\x1b[3;2~")),
+    ("kDC3", Some("\x1b[3;3~")),
+    ("kDC4", Some("\x1b[3;4~")),
+    ("kDC5", Some("\x1b[3;5~")),
+    ("kDC6", Some("\x1b[3;6~")),
+    ("kDC7", Some("\x1b[3;7~")),
+    ("kDN", Some("\x1b[1;2B")),
+    ("kDN3", Some("\x1b[1;3B")),
+    ("kDN4", Some("\x1b[1;4B")),
+    ("kDN5", Some("\x1b[1;5B")),
+    ("kDN6", Some("\x1b[1;6B")),
+    ("kDN7", Some("\x1b[1;7B")),
+    ("kEND", Some("\x1b[1;2F")),
+    ("kEND3", Some("\x1b[1;3F")),
+    ("kEND4", Some("\x1b[1;4F")),
+    ("kEND5", Some("\x1b[1;5F")),
+    ("kEND6", Some("\x1b[1;6F")),
+    ("kEND7", Some("\x1b[1;7F")),
+    ("kHOM", Some("\x1b[1;2H")),
+    ("kHOM3", Some("\x1b[1;3H")),
+    ("kHOM4", Some("\x1b[1;4H")),
+    ("kHOM5", Some("\x1b[1;5H")),
+    ("kHOM6", Some("\x1b[1;6H")),
+    ("kHOM7", Some("\x1b[1;7H")),
+    ("kIC", Some("\x1b[2;2~")),
+    ("kIC3", Some("\x1b[2;3~")),
+    ("kIC4", Some("\x1b[2;4~")),
+    ("kIC5", Some("\x1b[2;5~")),
+    ("kIC6", Some("\x1b[2;6~")),
+    ("kIC7", Some("\x1b[2;7~")),
+    ("kLFT", Some("\x1b[1;2D")),
+    ("kLFT3", Some("\x1b[1;3D")),
+    ("kLFT4", Some("\x1b[1;4D")),
+    ("kLFT5", Some("\x1b[1;5D")),
+    ("kLFT6", Some("\x1b[1;6D")),
+    ("kLFT7", Some("\x1b[1;7D")),
+    ("kNXT", Some("\x1b[6;2~")),
+    ("kNXT3", Some("\x1b[6;3~")),
+    ("kNXT4", Some("\x1b[6;4~")),
+    ("kNXT5", Some("\x1b[6;5~")),
+    ("kNXT6", Some("\x1b[6;6~")),
+    ("kNXT7", Some("\x1b[6;7~")),
+    ("kPRV", Some("\x1b[5;2~")),
+    ("kPRV3", Some("\x1b[5;3~")),
+    ("kPRV4", Some("\x1b[5;4~")),
+    ("kPRV5", Some("\x1b[5;5~")),
+    ("kPRV6", Some("\x1b[5;6~")),
+    ("kPRV7", Some("\x1b[5;7~")),
+    ("kRIT", Some("\x1b[1;2C")),
+    ("kRIT3", Some("\x1b[1;3C")),
+    ("kRIT4", Some("\x1b[1;4C")),
+    ("kRIT5", Some("\x1b[1;5C")),
+    ("kRIT6", Some("\x1b[1;6C")),
+    ("kRIT7", Some("\x1b[1;7C")),
+    ("kUP", Some("\x1b[1;2A")),
+    ("kUP3", Some("\x1b[1;3A")),
+    ("kUP4", Some("\x1b[1;4A")),
+    ("kUP5", Some("\x1b[1;5A")),
+    ("kUP6", Some("\x1b[1;6A")),
+    ("kUP7", Some("\x1b[1;7A")),
+    ("kb2", Some("\x1bOE")),
+    ("kbs", Some("\x7f")),
+    ("kcbt", Some("\x1b[Z")),
+    ("kcub1", Some("\x1bOD")),
+    ("kcud1", Some("\x1bOB")),
+    ("kcuf1", Some("\x1bOC")),
+    ("kcuu1", Some("\x1bOA")),
+    ("kdch1", Some("\x1b[3~")),
+    ("kend", Some("\x1bOF")),
+    ("kent", Some("\x1bOM")),
+    ("kf1", Some("\x1bOP")),
+    ("kf10", Some("\x1b[21~")),
+    ("kf11", Some("\x1b[23~")),
+    ("kf12", Some("\x1b[24~")),
+    ("kf13", Some("\x1b[1;2P")),
+    ("kf14", Some("\x1b[1;2Q")),
+    ("kf15", Some("\x1b[1;2R")),
+    ("kf16", Some("\x1b[1;2S")),
+    ("kf17", Some("\x1b[15;2~")),
+    ("kf18", Some("\x1b[17;2~")),
+    ("kf19", Some("\x1b[18;2~")),
+    ("kf2", Some("\x1bOQ")),
+    ("kf20", Some("\x1b[19;2~")),
+    ("kf21", Some("\x1b[20;2~")),
+    ("kf22", Some("\x1b[21;2~")),
+    ("kf23", Some("\x1b[23;2~")),
+    ("kf24", Some("\x1b[24;2~")),
+    ("kf25", Some("\x1b[1;5P")),
+    ("kf26", Some("\x1b[1;5Q")),
+    ("kf27", Some("\x1b[1;5R")),
+    ("kf28", Some("\x1b[1;5S")),
+    ("kf29", Some("\x1b[15;5~")),
+    ("kf3", Some("\x1bOR")),
+    ("kf30", Some("\x1b[17;5~")),
+    ("kf31", Some("\x1b[18;5~")),
+    ("kf32", Some("\x1b[19;5~")),
+    ("kf33", Some("\x1b[20;5~")),
+    ("kf34", Some("\x1b[21;5~")),
+    ("kf35", Some("\x1b[23;5~")),
+    ("kf36", Some("\x1b[24;5~")),
+    ("kf37", Some("\x1b[1;6P")),
+    ("kf38", Some("\x1b[1;6Q")),
+    ("kf39", Some("\x1b[1;6R")),
+    ("kf4", Some("\x1bOS")),
+    ("kf40", Some("\x1b[1;6S")),
+    ("kf41", Some("\x1b[15;6~")),
+    ("kf42", Some("\x1b[17;6~")),
+    ("kf43", Some("\x1b[18;6~")),
+    ("kf44", Some("\x1b[19;6~")),
+    ("kf45", Some("\x1b[20;6~")),
+    ("kf46", Some("\x1b[21;6~")),
+    ("kf47", Some("\x1b[23;6~")),
+    ("kf48", Some("\x1b[24;6~")),
+    ("kf49", Some("\x1b[1;3P")),
+    ("kf5", Some("\x1b[15~")),
+    ("kf50", Some("\x1b[1;3Q")),
+    ("kf51", Some("\x1b[1;3R")),
+    ("kf52", Some("\x1b[1;3S")),
+    ("kf53", Some("\x1b[15;3~")),
+    ("kf54", Some("\x1b[17;3~")),
+    ("kf55", Some("\x1b[18;3~")),
+    ("kf56", Some("\x1b[19;3~")),
+    ("kf57", Some("\x1b[20;3~")),
+    ("kf58", Some("\x1b[21;3~")),
+    ("kf59", Some("\x1b[23;3~")),
+    ("kf6", Some("\x1b[17~")),
+    ("kf60", Some("\x1b[24;3~")),
+    ("kf61", Some("\x1b[1;4P")),
+    ("kf62", Some("\x1b[1;4Q")),
+    ("kf63", Some("\x1b[1;4R")),
+    ("kf7", Some("\x1b[18~")),
+    ("kf8", Some("\x1b[19~")),
+    ("kf9", Some("\x1b[20~")),
+    ("khome", Some("\x1bOH")),
+    ("kich1", Some("\x1b[2~")),
+    ("kind", Some("\x1b[1;2B")),
+    ("km", None),
+    ("kmous", Some("\x1b[M")),
+    ("knp", Some("\x1b[6~")),
+    ("kpp", Some("\x1b[5~")),
+    ("kri", Some("\x1b[1;2A")),
+    ("lines", Some("24")),
+    ("mc0", Some("\x1b[i")),
+    ("mc4", Some("\x1b[4i")),
+    ("mc5", Some("\x1b[5i")),
+    ("mc5i", None),
+    ("meml", Some("\x1bl")),
+    ("memu", Some("\x1bm")),
+    ("mir", None),
+    ("msgr", None),
+    ("npc", None),
+    ("oc", Some("\x1b]104\x07")),
+    ("op", Some("\x1b[39;49m")),
+    ("pairs", Some("32767")),
+    ("rc", Some("\x1b8")),
+    ("rep", Some("%p1%c\x1b[%p2%{1}%-%db")),
+    ("rev", Some("\x1b[7m")),
+    ("ri", Some("\x1bM")),
+    ("rin", Some("\x1b[%p1%dT")),
+    ("ritm", Some("\x1b[23m")),
+    ("rmacs", Some("\x1b(B")),
+    ("rmam", Some("\x1b[?7l")),
+    ("rmcup", Some("\x1b[?1049l\x1b[23;0;0t")),
+    ("rmir", Some("\x1b[4l")),
+    ("rmkx", Some("\x1b[?1l\x1b>")),
+    ("rmm", Some("\x1b[?1034l")),
+    ("rmso", Some("\x1b[27m")),
+    ("rmul", Some("\x1b[24m")),
+    ("rmxx", Some("\x1b[29m")),
+    ("rs1", Some("\x1bc\x1b]104\x07")),
+    ("rs2", Some("\x1b[!p\x1b[?3;4l\x1b[4l\x1b>")),
+    ("sc", Some("\x1b7")),
+    ("setab", Some("\x1b[%?%p1%{8}%<%t4%p1%d%e%p1%{16}%<%t10%p1%{8}%-%d%e48;5;%p1%d%;m")),
+    ("setaf", Some("\x1b[%?%p1%{8}%<%t3%p1%d%e%p1%{16}%<%t9%p1%{8}%-%d%e38;5;%p1%d%;m")),
+    ("sgr", Some("%?%p9%t\x1b(0%e\x1b(B%;\x1b[0%?%p6%t;1%;%?%p5%t;2%;%?%p2%t;4%;%?%p1%p3%|%t;7%;%?%p4%t;5%;%?%p7%t;8%;m")),
+    ("sgr0", Some("\x1b(B\x1b[m")),
+    ("sitm", Some("\x1b[3m")),
+    ("smacs", Some("\x1b(0")),
+    ("smam", Some("\x1b[?7h")),
+    ("smcup", Some("\x1b[?1049h\x1b[22;0;0t")),
+    ("smir", Some("\x1b[4h")),
+    ("smkx", Some("\x1b[?1h\x1b=")),
+    ("smm", Some("\x1b[?1034h")),
+    ("smso", Some("\x1b[7m")),
+    ("smul", Some("\x1b[4m")),
+    ("smxx", Some("\x1b[9m")),
+    ("tbc", Some("\x1b[3g")),
+    ("tsl", Some("\x1b]2;")),
+    ("u6", Some("\x1b[%i%d;%dR")),
+    ("u7", Some("\x1b[6n")),
+    ("u8", Some("\x1b[?%[;0123456789]c")),
+    ("u9", Some("\x1b[c")),
+    ("vpa", Some("\x1b[%i%p1%dd")),
+    ("xenl", None),
+];
+
+/// Look up a terminfo capability.
+///
+/// Besides the terminfo names, the xterm names `TN` and `Co` are accepted for the terminal name
+/// and the number of colors.
+pub fn lookup(name: &str) -> Option<Option<&'static str>> {
+    let name = match name {
+        "TN" | "name" => return Some(Some("alacritty")),
+        "Co" => "colors",
+        name => name,
+    };
+
+    let index = CAPABILITIES.binary_search_by(|(capability, _)| capability.cmp(&name)).ok()?;
+    Some(CAPABILITIES[index].1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capabilities_sorted() {
+        assert!(CAPABILITIES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn lookup_capabilities() {
+        assert_eq!(lookup("TN"), Some(Some("alacritty")));
+        assert_eq!(lookup("Co"), Some(Some("256")));
+        assert_eq!(lookup("kf1"), Some(Some("\x1bOP")));
+        assert_eq!(lookup("am"), Some(None));
+        assert_eq!(lookup("RGB"), None);
+    }
+}
@@ -6,7 +6,7 @@ use std::io::Read;
 use std::path::Path;
 
 use alacritty_terminal::ansi;
-use alacritty_terminal::config::MockConfig;
+use alacritty_terminal::config::{AmbiguousWidth, MockConfig};
 use alacritty_terminal::event::{Event, EventListener};
 use alacritty_terminal::grid::{Dimensions, Grid};
 use alacritty_terminal::index::{Column, Line};
@@ -41,9 +41,11 @@ ref_tests! {
     vim_simple_edit
     vttest_cursor_movement_1
     vttest_insert
+    vttest_insert_margins
     vttest_origin_mode_1
     vttest_origin_mode_2
     vttest_scroll
+    vttest_scroll_margins
     vttest_tab_clear_set
     zsh_tab_completion
     history
@@ -67,6 +69,16 @@ ref_tests! {
     saved_cursor_alt
     sgr
     underline
+    deccra
+    decfra
+    decera
+    deccara
+    decdwl
+    sync_update
+    sync_update_partial
+    vt220_charsets
+    ambiguous_width_narrow
+    ambiguous_width_wide
 }
 
 fn read_u8<P>(path: P) -> Vec<u8>
@@ -82,6 +94,8 @@ where
 #[derive(Deserialize, Default)]
 struct RefConfig {
     history_size: u32,
+    #[serde(default)]
+    ambiguous_width: AmbiguousWidth,
 }
 
 #[derive(Copy, Clone)]
@@ -103,6 +117,7 @@ fn ref_test(dir: &Path) {
 
     let mut config = MockConfig::default();
     config.scrolling.set_history(ref_config.history_size);
+    config.ambiguous_width = ref_config.ambiguous_width;
 
     let mut terminal = Term::new(&config, size, Mock);
     let mut parser = ansi::Processor::new();